@@ -0,0 +1,284 @@
+use std::collections::HashMap;
+use std::error::Error;
+
+use lsp_server::{Connection, Message, Notification, Request, Response};
+use lsp_types::notification::{
+    DidChangeTextDocument, DidCloseTextDocument, DidOpenTextDocument, DidSaveTextDocument,
+    Notification as _, PublishDiagnostics,
+};
+use lsp_types::request::{Completion, GotoDefinition, HoverRequest, Request as _};
+use lsp_types::{
+    CompletionItem, CompletionItemKind, CompletionOptions, CompletionParams, CompletionResponse,
+    DiagnosticSeverity, DidChangeTextDocumentParams, DidCloseTextDocumentParams,
+    DidOpenTextDocumentParams, DidSaveTextDocumentParams, GotoDefinitionParams,
+    GotoDefinitionResponse, Hover, HoverContents, HoverParams, HoverProviderCapability,
+    LanguageString, Location, MarkedString, OneOf, Position, PublishDiagnosticsParams, Range,
+    ServerCapabilities, TextDocumentSyncCapability, TextDocumentSyncKind, TextDocumentSyncOptions,
+    TextDocumentSyncSaveOptions, Url,
+};
+
+use _datalang::diagnostic::{Diagnostic, Severity, Span};
+use _datalang::symbols::SymbolIndex;
+
+type LspResult<T> = Result<T, Box<dyn Error + Sync + Send>>;
+
+fn main() -> LspResult<()> {
+    let (connection, io_threads) = Connection::stdio();
+
+    let capabilities = ServerCapabilities {
+        text_document_sync: Some(TextDocumentSyncCapability::Options(
+            TextDocumentSyncOptions {
+                open_close: Some(true),
+                change: Some(TextDocumentSyncKind::FULL),
+                save: Some(TextDocumentSyncSaveOptions::Supported(true)),
+                ..Default::default()
+            },
+        )),
+        definition_provider: Some(OneOf::Left(true)),
+        hover_provider: Some(HoverProviderCapability::Simple(true)),
+        completion_provider: Some(CompletionOptions::default()),
+        ..Default::default()
+    };
+    connection.initialize(serde_json::to_value(capabilities)?)?;
+
+    let mut server = Server {
+        connection,
+        documents: HashMap::new(),
+    };
+    server.run()?;
+
+    // the io threads finish once the connection is gone
+    drop(server);
+    io_threads.join()?;
+    Ok(())
+}
+
+struct Server {
+    connection: Connection,
+    // the text of each open document as the editor last sent it
+    documents: HashMap<Url, String>,
+}
+
+impl Server {
+    fn run(&mut self) -> LspResult<()> {
+        while let Ok(msg) = self.connection.receiver.recv() {
+            match msg {
+                Message::Request(req) => {
+                    if self.connection.handle_shutdown(&req)? {
+                        return Ok(());
+                    }
+                    let resp = self.answer(req)?;
+                    self.connection.sender.send(Message::Response(resp))?;
+                }
+                Message::Notification(not) => self.take_notification(not)?,
+                Message::Response(_) => (),
+            }
+        }
+        Ok(())
+    }
+
+    fn answer(&self, req: Request) -> LspResult<Response> {
+        let result = match req.method.as_str() {
+            GotoDefinition::METHOD => {
+                let params: GotoDefinitionParams = serde_json::from_value(req.params)?;
+                serde_json::to_value(self.get_definitions(params))?
+            }
+            HoverRequest::METHOD => {
+                let params: HoverParams = serde_json::from_value(req.params)?;
+                serde_json::to_value(self.get_hover(params))?
+            }
+            Completion::METHOD => {
+                let params: CompletionParams = serde_json::from_value(req.params)?;
+                serde_json::to_value(self.get_completions(params))?
+            }
+            method => {
+                return Ok(Response::new_err(
+                    req.id,
+                    lsp_server::ErrorCode::MethodNotFound as i32,
+                    format!("{method} is not supported"),
+                ))
+            }
+        };
+        Ok(Response {
+            id: req.id,
+            result: Some(result),
+            error: None,
+        })
+    }
+
+    // the diagnostics get published when a document is opened or saved
+    fn take_notification(&mut self, not: Notification) -> LspResult<()> {
+        match not.method.as_str() {
+            DidOpenTextDocument::METHOD => {
+                let params: DidOpenTextDocumentParams = serde_json::from_value(not.params)?;
+                let uri = params.text_document.uri;
+                self.documents
+                    .insert(uri.to_owned(), params.text_document.text);
+                self.publish_diagnostics(uri)
+            }
+            DidChangeTextDocument::METHOD => {
+                let params: DidChangeTextDocumentParams = serde_json::from_value(not.params)?;
+                if let Some(change) = params.content_changes.into_iter().last() {
+                    self.documents.insert(params.text_document.uri, change.text);
+                }
+                Ok(())
+            }
+            DidSaveTextDocument::METHOD => {
+                let params: DidSaveTextDocumentParams = serde_json::from_value(not.params)?;
+                let uri = params.text_document.uri;
+                if let Some(text) = params.text {
+                    self.documents.insert(uri.to_owned(), text);
+                }
+                self.publish_diagnostics(uri)
+            }
+            DidCloseTextDocument::METHOD => {
+                let params: DidCloseTextDocumentParams = serde_json::from_value(not.params)?;
+                self.documents.remove(&params.text_document.uri);
+                self.send_diagnostics(params.text_document.uri, vec![])
+            }
+            _ => Ok(()),
+        }
+    }
+
+    fn publish_diagnostics(&self, uri: Url) -> LspResult<()> {
+        let source = match self.documents.get(&uri) {
+            Some(source) => source,
+            None => return Ok(()),
+        };
+        let diagnostics = SymbolIndex::new(source)
+            .diagnostics
+            .iter()
+            .map(|diagnostic| to_lsp_diagnostic(source, diagnostic))
+            .collect();
+        self.send_diagnostics(uri, diagnostics)
+    }
+
+    fn send_diagnostics(&self, uri: Url, diagnostics: Vec<lsp_types::Diagnostic>) -> LspResult<()> {
+        let params = PublishDiagnosticsParams {
+            uri,
+            diagnostics,
+            version: None,
+        };
+        self.connection
+            .sender
+            .send(Message::Notification(Notification::new(
+                PublishDiagnostics::METHOD.into(),
+                params,
+            )))?;
+        Ok(())
+    }
+
+    // the document with its index and the byte the position points at
+    fn look_up(&self, uri: &Url, position: Position) -> Option<(&String, SymbolIndex, usize)> {
+        let source = self.documents.get(uri)?;
+        let byte = to_byte(source, position);
+        Some((source, SymbolIndex::new(source), byte))
+    }
+
+    fn get_definitions(&self, params: GotoDefinitionParams) -> Option<GotoDefinitionResponse> {
+        let document = params.text_document_position_params;
+        let (source, index, byte) = self.look_up(&document.text_document.uri, document.position)?;
+        let occurrence = index.get_occurrence_at(byte)?;
+        let locations = index
+            .get_definitions(&occurrence.rel_id)
+            .iter()
+            .map(|definition| Location {
+                uri: document.text_document.uri.to_owned(),
+                range: to_range(source, definition.span),
+            })
+            .collect();
+        Some(GotoDefinitionResponse::Array(locations))
+    }
+
+    fn get_hover(&self, params: HoverParams) -> Option<Hover> {
+        let document = params.text_document_position_params;
+        let (source, index, byte) = self.look_up(&document.text_document.uri, document.position)?;
+        let occurrence = index.get_occurrence_at(byte)?;
+        Some(Hover {
+            contents: HoverContents::Scalar(MarkedString::LanguageString(LanguageString {
+                language: "datalang".into(),
+                value: index.describe(&occurrence.rel_id),
+            })),
+            range: Some(to_range(source, occurrence.span)),
+        })
+    }
+
+    fn get_completions(&self, params: CompletionParams) -> Option<CompletionResponse> {
+        let source = self
+            .documents
+            .get(&params.text_document_position.text_document.uri)?;
+        let index = SymbolIndex::new(source);
+        let items = index
+            .get_rel_ids()
+            .iter()
+            .map(|rel_id| CompletionItem {
+                label: rel_id.identifier.to_owned(),
+                kind: Some(CompletionItemKind::STRUCT),
+                detail: Some(index.describe(rel_id)),
+                ..Default::default()
+            })
+            .collect();
+        Some(CompletionResponse::Array(items))
+    }
+}
+
+fn to_lsp_diagnostic(source: &str, diagnostic: &Diagnostic) -> lsp_types::Diagnostic {
+    let mut message = diagnostic.message.to_owned();
+    if !diagnostic.expected.is_empty() {
+        message += &format!("\nexpected {}", diagnostic.expected.join(" or "));
+    }
+    if let Some(hint) = &diagnostic.hint {
+        message += &format!("\nhint: {hint}");
+    }
+    for note in &diagnostic.notes {
+        message += &format!("\nnote: {}", note.message);
+    }
+    lsp_types::Diagnostic {
+        range: to_range(source, diagnostic.span.unwrap_or(Span { start: 0, end: 0 })),
+        severity: Some(match diagnostic.severity {
+            Severity::Error => DiagnosticSeverity::ERROR,
+            Severity::Warning => DiagnosticSeverity::WARNING,
+        }),
+        code: Some(lsp_types::NumberOrString::String(diagnostic.code.into())),
+        source: Some("datalang".into()),
+        message,
+        ..Default::default()
+    }
+}
+
+fn to_range(source: &str, span: Span) -> Range {
+    Range {
+        start: to_position(source, span.start),
+        end: to_position(source, span.end),
+    }
+}
+
+// lsp counts the columns in utf-16 code units
+fn to_position(source: &str, byte: usize) -> Position {
+    let mut byte = byte.min(source.len());
+    while !source.is_char_boundary(byte) {
+        byte -= 1;
+    }
+    let before = &source[..byte];
+    let line_start = before.rfind('\n').map_or(0, |pos| pos + 1);
+    Position {
+        line: before.matches('\n').count() as u32,
+        character: before[line_start..].encode_utf16().count() as u32,
+    }
+}
+
+fn to_byte(source: &str, position: Position) -> usize {
+    let line_start: usize = source
+        .split_inclusive('\n')
+        .take(position.line as usize)
+        .map(str::len)
+        .sum();
+    let mut units = 0;
+    for (i, c) in source[line_start..].char_indices() {
+        if units >= position.character as usize || c == '\n' {
+            return line_start + i;
+        }
+        units += c.len_utf16();
+    }
+    source.len()
+}
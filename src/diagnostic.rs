@@ -0,0 +1,175 @@
+use std::fmt;
+
+use serde_json::{json, Value};
+
+use crate::utils::print_hilighted;
+
+// the codes a diagnostic can carry, E for errors and W for warnings, grouped by the step that
+// reports them
+pub const LEXER_ERROR: &str = "E0001";
+pub const PARSER_INTERNAL: &str = "E0100";
+pub const UNEXPECTED_LEXOGRAM: &str = "E0101";
+pub const UNFINISHED_INPUT: &str = "E0102";
+pub const INVALID_LINE_START: &str = "E0103";
+pub const INVALID_CONSTRUCT: &str = "E0104";
+pub const RUNTIME_ERROR: &str = "E0200";
+pub const NEGATION_CYCLE: &str = "E0201";
+pub const UNMATCHING_LINE: &str = "E0202";
+pub const NO_CONTEXT: &str = "E0203";
+pub const STORAGE_WARNING: &str = "W0204";
+pub const TYPE_ISSUE: &str = "W0300";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Severity {
+    Error,
+    Warning,
+}
+
+impl fmt::Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Severity::Error => write!(f, "error"),
+            Severity::Warning => write!(f, "warning"),
+        }
+    }
+}
+
+// the bytes of the input a diagnostic points at
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Span {
+    pub start: usize,
+    pub end: usize,
+}
+
+// lines and columns count from 1, columns in chars
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Location {
+    pub byte: usize,
+    pub line: usize,
+    pub column: usize,
+}
+
+impl Location {
+    pub fn new(source: &str, byte: usize) -> Self {
+        let byte = byte.min(source.len());
+        let before = &source[..byte];
+        let line_start = before.rfind('\n').map_or(0, |pos| pos + 1);
+        Self {
+            byte,
+            line: before.matches('\n').count() + 1,
+            column: before[line_start..].chars().count() + 1,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Note {
+    pub span: Option<Span>,
+    pub message: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Diagnostic {
+    pub severity: Severity,
+    pub code: &'static str,
+    pub span: Option<Span>,
+    pub message: String,
+    pub expected: Vec<String>,
+    pub hint: Option<String>,
+    pub notes: Vec<Note>,
+}
+
+impl Diagnostic {
+    pub fn new(severity: Severity, code: &'static str, message: String) -> Self {
+        Self {
+            severity,
+            code,
+            span: None,
+            message,
+            expected: vec![],
+            hint: None,
+            notes: vec![],
+        }
+    }
+
+    pub fn is_error(&self) -> bool {
+        self.severity == Severity::Error
+    }
+
+    pub fn print(&self, source: &str) -> String {
+        let mut ret = format!(
+            "\x1b[1m{}[{}]: {}\x1b[0m\n",
+            self.severity, self.code, self.message
+        );
+        if let Some(span) = self.span {
+            ret += &format!("{}\n", print_span(source, span));
+        }
+        if !self.expected.is_empty() {
+            ret += &format!("expected {}\n", self.expected.join(" or "));
+        }
+        if let Some(hint) = &self.hint {
+            ret += &format!("hint: {hint}\n");
+        }
+        for note in &self.notes {
+            ret += &format!("note: {}\n", note.message);
+            if let Some(span) = note.span {
+                ret += &format!("{}\n", print_span(source, span));
+            }
+        }
+        ret
+    }
+
+    pub fn to_json(&self, source: &str) -> Value {
+        let notes: Vec<Value> = self
+            .notes
+            .iter()
+            .map(|note| json!({"message": note.message, "span": json_span(source, note.span)}))
+            .collect();
+        json!({
+            "severity": self.severity.to_string(),
+            "code": self.code,
+            "message": self.message,
+            "span": json_span(source, self.span),
+            "expected": self.expected,
+            "hint": self.hint,
+            "notes": notes,
+        })
+    }
+}
+
+pub fn print(diagnostics: &[Diagnostic], source: &str) -> String {
+    diagnostics
+        .iter()
+        .map(|diagnostic| diagnostic.print(source))
+        .collect::<Vec<String>>()
+        .join("\n")
+}
+
+pub fn to_json(diagnostics: &[Diagnostic], source: &str) -> String {
+    let items: Vec<Value> = diagnostics
+        .iter()
+        .map(|diagnostic| diagnostic.to_json(source))
+        .collect();
+    Value::Array(items).to_string()
+}
+
+// print_hilighted numbers the lines after each line break, so the source is given a leading one
+fn print_span(source: &str, span: Span) -> String {
+    let prefixed = format!("\n{source}");
+    let end = (span.end + 1).min(prefixed.len());
+    print_hilighted(&prefixed, (span.start + 1).min(end), end, "".into())
+}
+
+fn json_span(source: &str, span: Option<Span>) -> Value {
+    match span {
+        Some(span) => json!({
+            "start": json_location(Location::new(source, span.start)),
+            "end": json_location(Location::new(source, span.end)),
+        }),
+        None => Value::Null,
+    }
+}
+
+fn json_location(location: Location) -> Value {
+    json!({"byte": location.byte, "line": location.line, "column": location.column})
+}
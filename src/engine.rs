@@ -1,25 +1,38 @@
+pub mod answer_table;
+pub mod explain;
+pub mod fixpoint;
 pub mod operations;
+pub mod provenance;
 pub mod recursion_tally;
 pub mod relation;
+pub mod storage;
+pub mod stratification;
 pub mod truth_list;
+pub mod type_inference;
 pub mod var_context;
 pub mod var_context_universe;
 
 use print_macros::*;
 
 use crate::{
-    lexer,
+    diagnostic::{self, Diagnostic, Note, Severity, Span},
+    lexer::{self, Lexogram},
     parser::{
-        self, assumption_token::Assumption, defered_relation_token::DeferedRelation,
-        inmediate_relation_token::InmediateRelation, line_token::Line, HasRelId,
+        self, assumption_token::Assumption, conditional_token::Conditional,
+        defered_relation_token::DeferedRelation, inmediate_relation_token::InmediateRelation,
+        line_token::Line, transaction_token::Transaction, HasRelId, SourceLine,
     },
 };
-use std::{collections::BTreeMap, fmt, vec};
+use std::{cell::RefCell, collections::BTreeMap, fmt, path::Path, rc::Rc, vec};
 
 use self::{
+    answer_table::AnswerTables,
+    provenance::Proof,
     recursion_tally::RecursionTally,
     relation::{truth::Truth, Relation},
+    storage::{Storage, SNAPSHOT_INTERVAL},
     truth_list::TruthList,
+    type_inference::TypeInference,
     var_context::VarContext,
 };
 
@@ -29,11 +42,19 @@ pub struct RelId {
     pub column_count: usize,
 }
 
+impl fmt::Display for RelId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}/{}", self.identifier, self.column_count)
+    }
+}
+
 #[derive(Debug, Clone)]
 pub enum RuntimeError {
-    UnmatchingLine(Line),
+    UnmatchingLine(Box<Line>),
     Explanation(String),
     NoContextWhenNeeded,
+    NegationCycle(Vec<RelId>),
+    Deduction(DeductionError),
 }
 
 impl From<String> for RuntimeError {
@@ -42,39 +63,216 @@ impl From<String> for RuntimeError {
     }
 }
 
+impl From<DeductionError> for RuntimeError {
+    fn from(value: DeductionError) -> Self {
+        Self::Deduction(value)
+    }
+}
+
+// an error raised while deducing, along with the innermost rule it came from once one is known
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct DeductionError {
+    pub message: String,
+    // the rule as written and where it was read from
+    pub rule: Option<(String, Option<Span>)>,
+}
+
+impl From<String> for DeductionError {
+    fn from(message: String) -> Self {
+        Self {
+            message,
+            rule: None,
+        }
+    }
+}
+
+impl From<DeductionError> for String {
+    fn from(value: DeductionError) -> Self {
+        value.message
+    }
+}
+
+impl DeductionError {
+    // the outer rules only pass the error along
+    pub fn blame(self, rule: &Conditional) -> Self {
+        Self {
+            rule: self
+                .rule
+                .or_else(|| Some((rule.to_string(), rule.get_span()))),
+            ..self
+        }
+    }
+}
+
+impl fmt::Display for RuntimeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RuntimeError::UnmatchingLine(line) => write!(f, "{line} cant be run as a line"),
+            RuntimeError::Explanation(msg) => write!(f, "{msg}"),
+            RuntimeError::NoContextWhenNeeded => write!(f, "there was no context to evaluate on"),
+            RuntimeError::Deduction(err) => write!(f, "{}", err.message),
+            RuntimeError::NegationCycle(cycle) => {
+                let rel_ids: Vec<String> = cycle.iter().map(RelId::to_string).collect();
+                match rel_ids.len() {
+                    1 => write!(f, "{} depends on its own negation", rel_ids[0]),
+                    _ => write!(f, "{} depend on their own negation", rel_ids.join(", ")),
+                }
+            }
+        }
+    }
+}
+
+impl RuntimeError {
+    pub fn to_diagnostic(&self) -> Diagnostic {
+        match self {
+            RuntimeError::UnmatchingLine(_) => Diagnostic::new(
+                Severity::Error,
+                diagnostic::UNMATCHING_LINE,
+                self.to_string(),
+            ),
+            RuntimeError::Explanation(_) | RuntimeError::Deduction(_) => {
+                Diagnostic::new(Severity::Error, diagnostic::RUNTIME_ERROR, self.to_string())
+            }
+            RuntimeError::NoContextWhenNeeded => {
+                Diagnostic::new(Severity::Error, diagnostic::NO_CONTEXT, self.to_string())
+            }
+            RuntimeError::NegationCycle(_) => Diagnostic::new(
+                Severity::Error,
+                diagnostic::NEGATION_CYCLE,
+                self.to_string(),
+            ),
+        }
+    }
+}
+
+// the line that stopped an input from running
+#[derive(Debug, Clone)]
+struct LineFailure {
+    error: RuntimeError,
+    span: Span,
+    rolled_back: bool,
+    // the rule the error came from, if it came from one
+    rule: Option<Note>,
+}
+
+impl LineFailure {
+    fn to_diagnostic(&self) -> Diagnostic {
+        let mut ret = self.error.to_diagnostic();
+        ret.span = Some(self.span);
+        ret.notes.extend(self.rule.to_owned());
+        if self.rolled_back {
+            ret.notes.push(Note {
+                span: None,
+                message: "the open transaction was rolled back".into(),
+            });
+        }
+        ret
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum EvaluationMode {
+    TopDown,
+    SemiNaive,
+    Tabled,
+}
+
 #[derive(Debug, Clone)]
 pub struct Engine {
     recursion_limit: usize,
+    evaluation_mode: EvaluationMode,
     tables: BTreeMap<RelId, Relation>,
+    answer_tables: Rc<RefCell<AnswerTables>>,
+    storage: Option<Rc<RefCell<Storage>>>,
+    // truths and rules ingested by the current line or transaction, waiting to reach the storage
+    journal: Vec<Assumption>,
+    // the tables as they were when the open transaction began
+    transaction_start: Option<Rc<BTreeMap<RelId, Relation>>>,
+    // storage failures that didnt lose anything, waiting to be reported
+    storage_warnings: Vec<String>,
+    // the column types given by the schemas and facts held, kept as they are ingested
+    types: Rc<TypeInference>,
 }
 
 use std::hash::Hash;
 impl Hash for Engine {
     fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
         self.recursion_limit.hash(state);
+        self.evaluation_mode.hash(state);
         self.tables.hash(state);
     }
 }
 
-fn get_lines_from_chars(raw_commands: String) -> Result<Vec<Line>, String> {
-    let commands = String::from("\n") + &raw_commands;
+// the lexograms and the lines that could be read from an input, along with every mistake found
+// on it, all of their spans refering to the input as it was given
+pub fn read_source(raw_commands: &str) -> (Vec<Lexogram>, Vec<SourceLine>, Vec<Diagnostic>) {
+    let commands = String::from("\n") + raw_commands;
 
     let lex_res = lexer::lex(&commands);
 
     printparse!("{:?}", lex_res);
 
-    match lex_res {
-        Ok(lexic) => {
-            let ast_res = parser::parse(&lexic);
-            match ast_res {
-                Ok(ast_vec) => {
-                    printparse!("{:?}", ast_vec);
-                    Ok(ast_vec)
-                }
-                Err(err) => Err(err.print(&lexic, &commands)),
-            }
+    // the leading line break the lexer was given is taken off the positions before parsing, so
+    // the spans kept on the lines and their nodes refer to the input as it was given
+    let mut lexograms = match lex_res {
+        Ok(lexic) => lexic,
+        Err(e) => {
+            let mut diagnostic = e.to_diagnostic();
+            diagnostic.span = diagnostic.span.map(|span| Span {
+                start: span.start.saturating_sub(1),
+                end: span.end.saturating_sub(1),
+            });
+            return (vec![], vec![], vec![diagnostic]);
         }
-        Err(e) => Err(e.print(&commands)),
+    };
+    for lex in &mut lexograms {
+        lex.pos_s = lex.pos_s.saturating_sub(1);
+        lex.pos_f = lex.pos_f.saturating_sub(1);
+    }
+
+    match parser::parse_recovering(&lexograms, raw_commands) {
+        Ok((lines, diagnostics)) => (lexograms, lines, diagnostics),
+        Err(err) => (lexograms, vec![], err.into_diagnostics()),
+    }
+}
+
+// a rule only points at the source when it was read from the same input that failed, rules from
+// earlier inputs have spans on other texts
+fn get_rule_note(rule: &(String, Option<Span>), rules: &[(Span, String)]) -> Note {
+    let (rule_text, rule_span) = rule;
+    let span = rule_span.and_then(|rule_span| {
+        rules
+            .iter()
+            .find(|(span, text)| span.start == rule_span.start && text == rule_text)
+            .map(|(span, _)| *span)
+    });
+    Note {
+        span,
+        message: match span {
+            Some(_) => format!("the error came from the rule {rule_text}"),
+            None => format!("the error came from the rule {rule_text}, given on an earlier input"),
+        },
+    }
+}
+
+// the lines of the input with the bytes each was read from, or everything wrong with it
+fn get_spanned_lines(raw_commands: &str) -> Result<Vec<SourceLine>, Vec<Diagnostic>> {
+    let (_, lines, diagnostics) = read_source(raw_commands);
+    if diagnostics.is_empty() {
+        printparse!("{:?}", lines);
+        Ok(lines)
+    } else {
+        Err(diagnostics)
+    }
+}
+
+fn get_lines_from_chars(raw_commands: String) -> Result<Vec<Line>, String> {
+    match get_spanned_lines(&raw_commands) {
+        Ok(lines) => Ok(lines
+            .into_iter()
+            .map(|source_line| source_line.line)
+            .collect()),
+        Err(diagnostics) => Err(diagnostic::print(&diagnostics, &raw_commands)),
     }
 }
 
@@ -93,42 +291,253 @@ impl Engine {
     pub fn new() -> Self {
         Self {
             recursion_limit: 5,
+            evaluation_mode: EvaluationMode::TopDown,
             tables: BTreeMap::new(),
+            answer_tables: Rc::new(RefCell::new(AnswerTables::new())),
+            storage: None,
+            journal: vec![],
+            transaction_start: None,
+            storage_warnings: vec![],
+            types: Rc::new(TypeInference::default()),
+        }
+    }
+
+    pub fn open(path: &str) -> Result<Engine, String> {
+        let (storage, entries, torn_log) = Storage::open(Path::new(path))?;
+
+        let mut ret = Engine::new();
+        for entry in entries {
+            // a rule logged again after the snapshot that already holds it was taken
+            if let Assumption::Conditional(cond) = &entry {
+                if let Some(relation) = ret.tables.get(&cond.get_rel_id()) {
+                    if relation.has_conditional(cond) {
+                        continue;
+                    }
+                }
+            }
+            ret.ingest_assumption(&entry, &VarContext::new())?;
+        }
+        // rules only reach the log once stratified, but the files could have been put together
+        // by other means
+        if let Err(cycle) = ret.stratify() {
+            return Err(RuntimeError::NegationCycle(cycle).to_string());
+        }
+        ret.storage = Some(Rc::new(RefCell::new(storage)));
+
+        if torn_log {
+            ret.snapshot()?;
+        }
+        Ok(ret)
+    }
+
+    // an open transaction hasnt reached the log yet, so neither should it reach the snapshot
+    pub fn snapshot(&self) -> Result<(), String> {
+        let tables = match &self.transaction_start {
+            Some(start) => start.as_ref(),
+            None => &self.tables,
+        };
+        match &self.storage {
+            Some(storage) => storage
+                .borrow_mut()
+                .write_snapshot(&Self::get_stored_entries(tables)),
+            None => Err("there is no storage to take a snapshot on".into()),
+        }
+    }
+
+    fn get_stored_entries(tables: &BTreeMap<RelId, Relation>) -> Vec<Assumption> {
+        let mut ret = vec![];
+        for (rel_id, relation) in tables {
+            if let Some(schema) = relation.get_schema() {
+                ret.push(Assumption::Schema(schema.to_owned()));
+            }
+            for conditional in relation.get_conditions() {
+                ret.push(Assumption::Conditional(Box::new(
+                    conditional.to_conditional(),
+                )));
+            }
+            for truth in relation.get_literal_truths() {
+                ret.push(Assumption::RelationInmediate(InmediateRelation {
+                    negated: false,
+                    rel_name: rel_id.identifier.to_owned(),
+                    args: truth.get_data().to_owned(),
+                    span: None,
+                    arg_spans: vec![],
+                }));
+            }
+        }
+        ret
+    }
+
+    pub fn begin(&mut self) -> Result<(), String> {
+        if self.transaction_start.is_some() {
+            return Err("a transaction is already open".into());
         }
+        self.transaction_start = Some(Rc::new(self.tables.to_owned()));
+        Ok(())
+    }
+
+    // the whole transaction reaches the log as a single batch, if that fails nothing was committed
+    pub fn commit(&mut self) -> Result<(), String> {
+        let start = match self.transaction_start.take() {
+            Some(start) => start,
+            None => return Err("there is no transaction to commit".into()),
+        };
+
+        if let Err(err) = self.persist() {
+            self.transaction_start = Some(start);
+            self.rollback()?;
+            return Err(format!("the transaction was rolled back: {err}"));
+        }
+        Ok(())
+    }
+
+    pub fn rollback(&mut self) -> Result<(), String> {
+        match self.transaction_start.take() {
+            Some(start) => {
+                self.tables = start.as_ref().to_owned();
+                self.types = Rc::new(TypeInference::of_tables(&self.tables));
+                self.answer_tables = Rc::new(RefCell::new(AnswerTables::new()));
+                self.journal = vec![];
+                Ok(())
+            }
+            None => Err("there is no transaction to roll back".into()),
+        }
+    }
+
+    // whatever a line changed reaches the log as a single batch, even when the line failed
+    // halfway through, as the changes it made before failing are kept in memory too. changes made
+    // inside a transaction wait for the commit
+    fn persist(&mut self) -> Result<(), String> {
+        if self.transaction_start.is_some() {
+            return Ok(());
+        }
+        let journal = std::mem::take(&mut self.journal);
+
+        let storage = match &self.storage {
+            Some(storage) if !journal.is_empty() => storage.to_owned(),
+            _ => return Ok(()),
+        };
+        storage.borrow_mut().append(&journal)?;
+
+        // the batch is already safe on the log, so a failed snapshot only means a longer replay
+        if storage.borrow().get_logged_entries() >= SNAPSHOT_INTERVAL {
+            if let Err(err) = self.snapshot() {
+                self.storage_warnings
+                    .push(format!("the snapshot couldnt be taken: {err}"));
+            }
+        }
+        Ok(())
     }
 
     pub fn set_recursion_limit(&mut self, rl: usize) {
         self.recursion_limit = rl;
     }
 
+    pub fn set_evaluation_mode(&mut self, mode: EvaluationMode) {
+        self.evaluation_mode = mode;
+    }
+
+    pub fn get_evaluation_mode(&self) -> EvaluationMode {
+        self.evaluation_mode
+    }
+
     pub fn input(self: &mut Engine, commands: String) -> String {
-        let mut ret = String::new();
-        match get_lines_from_chars(commands) {
+        match get_spanned_lines(&commands) {
             Ok(lines) => {
-                for line in lines {
-                    printdev!("\nexecuting: {}", line);
-
-                    match self.ingest_line(line) {
-                        Ok(Some(output)) => {
-                            let mut sorted_output = output.to_vector();
-                            sorted_output.sort();
-                            ret += &draw_table(sorted_output)
-                        }
-                        Ok(None) => (),
-                        Err(err) => {
-                            ret += &format!("An error ocurred on the execution step: \n {err:?}");
-                            break;
-                        }
-                    }
+                let (mut ret, warnings, failure) = self.execute(lines);
+                ret += &diagnostic::print(&warnings, &commands);
+                if let Some(failure) = failure {
+                    ret += &failure.to_diagnostic().print(&commands);
                 }
+                ret
             }
-            Err(err) => return err,
+            Err(diagnostics) => diagnostic::print(&diagnostics, &commands),
         }
+    }
+
+    // the output of the input kept apart from the diagnostics of whatever went wrong with it
+    pub fn run(self: &mut Engine, commands: String) -> (String, Vec<Diagnostic>) {
+        let lines = match get_spanned_lines(&commands) {
+            Ok(lines) => lines,
+            Err(diagnostics) => return (String::new(), diagnostics),
+        };
+        let mut diagnostics = self.type_diagnostics(&lines);
+        let (ret, warnings, failure) = self.execute(lines);
+        diagnostics.extend(warnings);
+        if let Some(failure) = failure {
+            diagnostics.push(failure.to_diagnostic());
+        }
+        (ret, diagnostics)
+    }
+
+    // the lines run until one fails, the output of the ones before it is kept along with the
+    // warnings they raised
+    fn execute(
+        &mut self,
+        lines: Vec<SourceLine>,
+    ) -> (String, Vec<Diagnostic>, Option<LineFailure>) {
+        let rules: Vec<(Span, String)> = lines
+            .iter()
+            .filter_map(|source_line| match &source_line.line {
+                Line::Assumption(Assumption::Conditional(cond)) => {
+                    Some((source_line.span, cond.to_string()))
+                }
+                _ => None,
+            })
+            .collect();
+
+        let mut ret = String::new();
+        let mut warnings = vec![];
+        for SourceLine { line, span, .. } in lines {
+            printdev!("\nexecuting: {}", line);
+
+            // only a failed commit closes the open transaction, any other error leaves it as it was
+            let in_transaction = self.transaction_start.is_some();
+            let result = self.ingest_line(line);
+
+            warnings.extend(self.storage_warnings.drain(..).map(|message| Diagnostic {
+                span: Some(span),
+                ..Diagnostic::new(Severity::Warning, diagnostic::STORAGE_WARNING, message)
+            }));
+
+            match result {
+                Ok(output) => ret += &output,
+                Err(error) => {
+                    let rule = match &error {
+                        RuntimeError::Deduction(DeductionError {
+                            rule: Some(rule), ..
+                        }) => Some(get_rule_note(rule, &rules)),
+                        _ => None,
+                    };
+                    return (
+                        ret,
+                        warnings,
+                        Some(LineFailure {
+                            error,
+                            span,
+                            rolled_back: in_transaction && self.transaction_start.is_none(),
+                            rule,
+                        }),
+                    );
+                }
+            }
+        }
+        (ret, warnings, None)
+    }
+
+    // lower strata are tabled so they are complete no matter the recursion limit
+    pub fn get_lower_stratum(&self) -> Engine {
+        let mut ret = self.clone();
+        ret.evaluation_mode = EvaluationMode::Tabled;
+        ret.answer_tables = Rc::new(RefCell::new(AnswerTables::new()));
         ret
     }
 
-    pub fn get_relation(&mut self, rel_id: RelId) -> Relation {
-        self.tables[&rel_id].to_owned()
+    pub fn get_relation(&self, rel_id: RelId) -> Relation {
+        match self.tables.get(&rel_id) {
+            Some(relation) => relation.to_owned(),
+            None => Relation::new(&rel_id),
+        }
     }
 
     pub fn query(
@@ -136,7 +545,7 @@ impl Engine {
         query: &DeferedRelation,
         context: &VarContext,
         recursion_tally: &RecursionTally,
-    ) -> Result<TruthList, String> {
+    ) -> Result<TruthList, DeductionError> {
         printprocess!("query {}", query);
 
         let rel_id = query.get_rel_id();
@@ -146,9 +555,23 @@ impl Engine {
             hypothetical_engine.ingest_assumption(assumption, context)?;
         }
 
-        Ok(hypothetical_engine
-            .get_relation(rel_id)
-            .get_filtered_truths(&query, &hypothetical_engine, recursion_tally)?)
+        let assumes_conditionals = query
+            .assumptions
+            .iter()
+            .any(|assumption| matches!(assumption, Assumption::Conditional(_)));
+
+        if assumes_conditionals {
+            if let Err(cycle) = hypothetical_engine.stratify() {
+                return Err(RuntimeError::NegationCycle(cycle).to_string().into());
+            }
+        }
+
+        match self.evaluation_mode {
+            EvaluationMode::Tabled => hypothetical_engine.tabled_query(query),
+            _ => Ok(hypothetical_engine
+                .get_relation(rel_id)
+                .get_filtered_truths(&query, &hypothetical_engine, recursion_tally)?),
+        }
     }
 
     fn ingest_assumption(
@@ -156,9 +579,33 @@ impl Engine {
         assumption: &Assumption,
         context: &VarContext,
     ) -> Result<(), String> {
+        self.answer_tables = Rc::new(RefCell::new(AnswerTables::new()));
+
         match assumption {
+            Assumption::Schema(schema) => {
+                let rel_id = schema.get_rel_id();
+                if let Some((other_id, _)) = self.tables.iter().find(|(other_id, _)| {
+                    other_id.identifier == rel_id.identifier && **other_id != rel_id
+                }) {
+                    return Err(format!(
+                        "{} is already used with {} columns, the schema declares {}",
+                        rel_id.identifier, other_id.column_count, rel_id.column_count
+                    ));
+                }
+
+                self.tables
+                    .entry(rel_id.to_owned())
+                    .or_insert_with(|| Relation::new(&rel_id))
+                    .set_schema(schema.to_owned())?;
+                Rc::make_mut(&mut self.types).learn_schema(schema);
+                if self.storage.is_some() {
+                    self.journal.push(assumption.to_owned());
+                }
+                Ok(())
+            }
             Assumption::Conditional(cond) => {
                 let rel_id = cond.get_rel_id();
+                self.check_schema_arity(&rel_id)?;
                 let insertion_key = rel_id.clone();
 
                 if let None = self.tables.get(&rel_id) {
@@ -166,13 +613,61 @@ impl Engine {
                 }
 
                 if let Some(table) = self.tables.get_mut(&rel_id) {
-                    table.add_conditional(cond.to_owned())?;
+                    table.add_conditional(*cond.to_owned())?;
+                }
+                if self.storage.is_some() {
+                    self.journal.push(assumption.to_owned());
+                }
+                Ok(())
+            }
+            Assumption::Update(update) => {
+                let matches = match self.tables.get(&update.filter.get_rel_id()) {
+                    Some(relation) => relation.get_matching_truths(&update.filter, context),
+                    None => vec![],
+                };
+
+                let mut goals = vec![];
+                for (_, bound_context) in &matches {
+                    match update.goal.to_truth(bound_context) {
+                        Ok(truth) => goals.push(truth),
+                        Err(msg) => {
+                            return Err("Cant update to a relation with unliteralizable items: "
+                                .to_string()
+                                + &msg)
+                        }
+                    }
+                }
+
+                for (truth, _) in matches {
+                    self.ingest_assumption(
+                        &Assumption::RelationInmediate(InmediateRelation {
+                            negated: true,
+                            rel_name: update.filter.rel_name.to_owned(),
+                            args: truth.get_data().to_owned(),
+                            span: None,
+                            arg_spans: vec![],
+                        }),
+                        context,
+                    )?;
+                }
+
+                for truth in goals {
+                    self.ingest_assumption(
+                        &Assumption::RelationInmediate(InmediateRelation {
+                            negated: false,
+                            rel_name: update.goal.rel_name.to_owned(),
+                            args: truth.get_data().to_owned(),
+                            span: None,
+                            arg_spans: vec![],
+                        }),
+                        context,
+                    )?;
                 }
                 Ok(())
             }
-            Assumption::Update(_) => todo!(),
             Assumption::RelationInmediate(rel) => {
                 let rel_id = rel.get_rel_id();
+                self.check_schema_arity(&rel_id)?;
                 let insertion_key = rel_id.clone();
 
                 if let None = self.tables.get(&rel_id) {
@@ -182,6 +677,12 @@ impl Engine {
                 if let Some(relation) = self.tables.get_mut(&rel_id) {
                     relation.add_truth(rel.to_owned())?;
                 }
+                if !rel.negated {
+                    Rc::make_mut(&mut self.types).learn_fact(rel);
+                }
+                if self.storage.is_some() {
+                    self.journal.push(assumption.to_owned());
+                }
                 Ok(())
             }
             Assumption::RelationDefered(d_rel) => {
@@ -202,6 +703,8 @@ impl Engine {
                         negated: false,
                         rel_name: d_rel.rel_name.to_owned(),
                         args: datas,
+                        span: None,
+                        arg_spans: vec![],
                     }),
                     context,
                 )
@@ -209,18 +712,76 @@ impl Engine {
         }
     }
 
-    pub fn ingest_line(self: &mut Engine, line: Line) -> Result<Option<TruthList>, RuntimeError> {
+    // a schema fixes how many columns its relation has
+    fn check_schema_arity(&self, rel_id: &RelId) -> Result<(), String> {
+        if self.tables.contains_key(rel_id) {
+            return Ok(());
+        }
+        for (other_id, relation) in &self.tables {
+            if let (true, Some(schema)) = (
+                other_id.identifier == rel_id.identifier,
+                relation.get_schema(),
+            ) {
+                return Err(format!(
+                    "{} has {} columns on its schema, found {}: {schema}",
+                    rel_id.identifier, other_id.column_count, rel_id.column_count
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    // the output of the line, as it is shown
+    pub fn ingest_line(self: &mut Engine, line: Line) -> Result<String, RuntimeError> {
+        let ret = self.apply_line(line);
+        self.persist()?;
+        ret
+    }
+
+    fn apply_line(self: &mut Engine, line: Line) -> Result<String, RuntimeError> {
         match line {
-            Line::Query(q) => Ok(Some(self.query(
-                &q,
-                &VarContext::new(),
-                &RecursionTally::new(self.recursion_limit),
-            )?)),
+            Line::Query(q) => {
+                let output = match self.evaluation_mode {
+                    EvaluationMode::TopDown | EvaluationMode::Tabled => self.query(
+                        &q,
+                        &VarContext::new(),
+                        &RecursionTally::new(self.recursion_limit),
+                    )?,
+                    EvaluationMode::SemiNaive => self.semi_naive_query(&q)?,
+                };
+                let mut sorted_output = output.to_vector();
+                sorted_output.sort();
+                Ok(draw_table(sorted_output))
+            }
+            Line::Why(q) => Ok(draw_proofs(self.why(&q)?)),
+            Line::Assumption(Assumption::Conditional(cond)) => {
+                let mut hypothetical_engine = self.clone();
+                hypothetical_engine
+                    .ingest_assumption(&Assumption::Conditional(cond), &VarContext::new())?;
+                if let Err(cycle) = hypothetical_engine.stratify() {
+                    return Err(RuntimeError::NegationCycle(cycle));
+                }
+
+                *self = hypothetical_engine;
+                Ok(String::new())
+            }
             Line::Assumption(assumption) => {
                 self.ingest_assumption(&assumption, &VarContext::new())?;
-                Ok(None)
+                Ok(String::new())
             }
-            Line::Comment(_) => Ok(None),
+            Line::Transaction(Transaction::Begin) => {
+                self.begin()?;
+                Ok(String::new())
+            }
+            Line::Transaction(Transaction::Commit) => {
+                self.commit()?;
+                Ok(String::new())
+            }
+            Line::Transaction(Transaction::Rollback) => {
+                self.rollback()?;
+                Ok(String::new())
+            }
+            Line::Comment(_) => Ok(String::new()),
         }
     }
 
@@ -229,6 +790,17 @@ impl Engine {
     }
 }
 
+fn draw_proofs(proofs: Vec<Proof>) -> String {
+    if proofs.is_empty() {
+        return "\nEmpty Result\n".into();
+    }
+    let mut ret = String::from("\n");
+    for proof in proofs {
+        ret += &format!("{proof}");
+    }
+    ret
+}
+
 fn draw_table(matrix: Vec<Truth>) -> String {
     if matrix.len() == 0 {
         return "\nEmpty Result\n".into();
@@ -0,0 +1,187 @@
+use std::collections::BTreeMap;
+
+use print_macros::*;
+
+use crate::parser::{
+    data_token::Data, defered_relation_token::DeferedRelation, expresion_token::ExpresionSemantics,
+    HasRelId,
+};
+
+use super::{
+    recursion_tally::RecursionTally, truth_list::TruthList, var_context::VarContext,
+    DeductionError, Engine, RelId,
+};
+
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub struct CallPattern {
+    rel_id: RelId,
+    bound_args: Vec<Option<Data>>,
+}
+
+impl From<&DeferedRelation> for CallPattern {
+    fn from(value: &DeferedRelation) -> Self {
+        Self {
+            rel_id: value.get_rel_id(),
+            bound_args: value
+                .args
+                .iter()
+                .map(|exp| match exp.literalize(&VarContext::new()) {
+                    Ok(Data::Any) | Err(_) => None,
+                    Ok(data) => Some(data),
+                })
+                .collect(),
+        }
+    }
+}
+
+impl CallPattern {
+    fn to_defered_relation(&self) -> DeferedRelation {
+        DeferedRelation::from((
+            &self.rel_id.identifier,
+            self.bound_args
+                .iter()
+                .map(|arg| ExpresionSemantics::Literal(arg.to_owned().unwrap_or(Data::Any)).into())
+                .collect(),
+        ))
+    }
+}
+
+#[derive(Debug)]
+struct AnswerTable {
+    answers: TruthList,
+    complete: bool,
+}
+
+#[derive(Debug, Default)]
+pub struct AnswerTables {
+    tables: BTreeMap<CallPattern, AnswerTable>,
+    evaluating: bool,
+}
+
+impl AnswerTables {
+    pub fn new() -> Self {
+        Self {
+            tables: BTreeMap::new(),
+            evaluating: false,
+        }
+    }
+}
+
+impl Engine {
+    pub fn tabled_query(&self, query: &DeferedRelation) -> Result<TruthList, DeductionError> {
+        printprocess!("tabled query {}", query);
+
+        let answers = self.tabled_call(&CallPattern::from(query))?;
+
+        let mut ret = TruthList::new();
+        for truth in answers.into_iter() {
+            if let Ok(fitted) = truth.fits_filter(query, VarContext::new()) {
+                ret.add(fitted);
+            }
+        }
+        Ok(ret)
+    }
+
+    // a call that is already tabled returns whatever answers it has so far, the first call of an
+    // evaluation (the leader) keeps reevaluating every incomplete table until none of them grows
+    fn tabled_call(&self, call: &CallPattern) -> Result<TruthList, DeductionError> {
+        if let Some(table) = self.answer_tables.borrow().tables.get(call) {
+            return Ok(table.answers.to_owned());
+        }
+
+        let leader = {
+            let mut answer_tables = self.answer_tables.borrow_mut();
+            answer_tables.tables.insert(
+                call.to_owned(),
+                AnswerTable {
+                    answers: TruthList::new(),
+                    complete: false,
+                },
+            );
+            let leader = !answer_tables.evaluating;
+            answer_tables.evaluating = true;
+            leader
+        };
+
+        if leader {
+            let result = self.complete_tables();
+
+            let mut answer_tables = self.answer_tables.borrow_mut();
+            answer_tables.evaluating = false;
+            if result.is_err() {
+                answer_tables.tables.retain(|_, table| table.complete);
+            }
+            result?;
+        } else {
+            self.evaluate_call(call)?;
+        }
+
+        match self.answer_tables.borrow().tables.get(call) {
+            Some(table) => Ok(table.answers.to_owned()),
+            None => Err(format!("answer table for {call:?} was lost").into()),
+        }
+    }
+
+    fn complete_tables(&self) -> Result<(), DeductionError> {
+        loop {
+            let pending: Vec<CallPattern> = self
+                .answer_tables
+                .borrow()
+                .tables
+                .iter()
+                .filter(|(_, table)| !table.complete)
+                .map(|(call, _)| call.to_owned())
+                .collect();
+
+            let mut changed = false;
+            for call in &pending {
+                if self.evaluate_call(call)? {
+                    changed = true;
+                }
+            }
+
+            let answer_tables = self.answer_tables.borrow();
+            let incomplete_count = answer_tables
+                .tables
+                .values()
+                .filter(|table| !table.complete)
+                .count();
+
+            if !changed && incomplete_count == pending.len() {
+                break;
+            }
+        }
+
+        for table in self.answer_tables.borrow_mut().tables.values_mut() {
+            table.complete = true;
+        }
+        Ok(())
+    }
+
+    fn evaluate_call(&self, call: &CallPattern) -> Result<bool, DeductionError> {
+        printprocess!("evaluating call {:?}", call);
+
+        let truths = self
+            .get_relation(call.rel_id.to_owned())
+            .get_filtered_truths(
+                &call.to_defered_relation(),
+                self,
+                &RecursionTally::new(usize::MAX),
+            )?;
+
+        let mut answer_tables = self.answer_tables.borrow_mut();
+        let table = match answer_tables.tables.get_mut(call) {
+            Some(table) => table,
+            None => return Err(format!("answer table for {call:?} was lost").into()),
+        };
+
+        let mut changed = false;
+        for truth in truths.into_iter() {
+            if !table.answers.contains(&truth) {
+                table.answers.add(truth);
+                changed = true;
+            }
+        }
+        Ok(changed)
+    }
+}
@@ -0,0 +1,151 @@
+use std::fmt;
+
+use print_macros::*;
+
+use crate::diagnostic::{self, Diagnostic, Severity};
+use crate::parser::{data_token::Data, defered_relation_token::DeferedRelation, HasRelId};
+
+use super::{
+    get_spanned_lines, recursion_tally::RecursionTally, var_context::VarContext,
+    var_context_universe::VarContextUniverse, Engine, EvaluationMode, Line,
+};
+
+#[derive(Debug, Clone)]
+pub struct PlanNode {
+    pub operation: String,
+    pub incoming: usize,
+    pub outgoing: usize,
+    pub children: Vec<PlanNode>,
+}
+
+impl PlanNode {
+    fn draw(&self, depth: usize) -> String {
+        let mut ret = format!(
+            "{}{} (in: {}, out: {})\n",
+            "  ".repeat(depth),
+            self.operation,
+            self.incoming,
+            self.outgoing
+        );
+        for child in &self.children {
+            ret += &child.draw(depth + 1);
+        }
+        ret
+    }
+}
+
+impl fmt::Display for PlanNode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.draw(0))
+    }
+}
+
+// one letter per argument: b when every incoming context binds it, f when none does and ? when
+// only some of them do
+pub fn adornment(rel: &DeferedRelation, universe: &VarContextUniverse) -> String {
+    rel.args
+        .iter()
+        .map(|arg| {
+            let bound_count = universe
+                .iter()
+                .filter(|context| !matches!(arg.literalize(context), Ok(Data::Any) | Err(_)))
+                .count();
+
+            if bound_count == 0 {
+                'f'
+            } else if bound_count == universe.len() {
+                'b'
+            } else {
+                '?'
+            }
+        })
+        .collect()
+}
+
+impl Engine {
+    pub fn explain(&self, query: &DeferedRelation) -> Result<String, String> {
+        printprocess!("explain {}", query);
+
+        let rel_id = query.get_rel_id();
+        let mut hypothetical_engine = self.clone();
+
+        for assumption in &query.assumptions {
+            hypothetical_engine.ingest_assumption(assumption, &VarContext::new())?;
+        }
+
+        // bottom up evaluation runs every rule body once over the fixpoint
+        let evaluation_engine = match self.evaluation_mode {
+            EvaluationMode::SemiNaive => hypothetical_engine.semi_naive_closure()?,
+            _ => hypothetical_engine.clone(),
+        };
+
+        let mut recursion_tally = RecursionTally::new(self.recursion_limit);
+        recursion_tally.count_up(&rel_id);
+
+        let mut ret = format!("{query} on {:?} evaluation\n", self.evaluation_mode);
+        ret += &format!(
+            "facts: {}\n",
+            evaluation_engine
+                .get_relation(rel_id.to_owned())
+                .get_matching_truths(query, &VarContext::new())
+                .len()
+        );
+
+        let relation = hypothetical_engine.get_relation(rel_id);
+        for (i, conditional) in relation.get_conditions().iter().enumerate() {
+            let base_context = conditional.get_base_context(query);
+
+            let mut universe = VarContextUniverse::new();
+            universe.insert(base_context.to_owned());
+
+            let plan = conditional.get_condition().explain(
+                &evaluation_engine,
+                &recursion_tally,
+                &universe,
+            )?;
+
+            let deductions = conditional.to_owned().get_deductions(
+                query,
+                &evaluation_engine,
+                &recursion_tally,
+            )?;
+
+            ret += &format!("rule {}: {conditional}\n", i + 1);
+            ret += &format!("  bindings: {base_context}\n");
+            ret += &plan.draw(1);
+            ret += &format!("  deductions: {}\n", deductions.len());
+        }
+
+        Ok(ret)
+    }
+
+    pub fn explain_input(&self, commands: String) -> String {
+        let mut ret = String::new();
+        match get_spanned_lines(&commands) {
+            Ok(lines) => {
+                for source_line in lines {
+                    match source_line.line {
+                        Line::Query(query) => match self.explain(&query) {
+                            Ok(explanation) => ret += &explanation,
+                            Err(err) => {
+                                let diagnostic = Diagnostic {
+                                    span: Some(source_line.span),
+                                    ..Diagnostic::new(
+                                        Severity::Error,
+                                        diagnostic::RUNTIME_ERROR,
+                                        err,
+                                    )
+                                };
+                                ret += &diagnostic.print(&commands);
+                                break;
+                            }
+                        },
+                        other => ret += &format!("only queries can be explained, found: {other}\n"),
+                    }
+                }
+            }
+            Err(diagnostics) => return diagnostic::print(&diagnostics, &commands),
+        }
+        ret
+    }
+}
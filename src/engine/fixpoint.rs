@@ -0,0 +1,173 @@
+use std::{cell::RefCell, collections::BTreeMap, rc::Rc};
+
+use print_macros::*;
+
+use crate::parser::{
+    data_token::Data, defered_relation_token::DeferedRelation, expresion_token::ExpresionSemantics,
+    HasRelId,
+};
+
+use super::{
+    answer_table::AnswerTables,
+    recursion_tally::RecursionTally,
+    relation::{conditional_truth::ConditionalTruth, Relation},
+    truth_list::TruthList,
+    var_context::VarContext,
+    DeductionError, Engine, EvaluationMode, RelId, RuntimeError,
+};
+
+// '#' always starts a comment on the lexer, so no user relation can collide with a delta table
+const DELTA_PREFIX: &str = "#delta:";
+
+fn delta_rel_id(rel_id: &RelId) -> RelId {
+    RelId {
+        identifier: DELTA_PREFIX.to_string() + &rel_id.identifier,
+        column_count: rel_id.column_count,
+    }
+}
+
+fn delta_relation(rel: &DeferedRelation) -> DeferedRelation {
+    let mut ret = rel.to_owned();
+    ret.rel_name = DELTA_PREFIX.to_string() + &rel.rel_name;
+    ret
+}
+
+impl Engine {
+    pub fn semi_naive_query(&self, query: &DeferedRelation) -> Result<TruthList, DeductionError> {
+        printprocess!("semi naive query {}", query);
+
+        let mut hypothetical_engine = self.clone();
+
+        for assumption in &query.assumptions {
+            hypothetical_engine.ingest_assumption(assumption, &VarContext::new())?;
+        }
+
+        let closure = hypothetical_engine.semi_naive_closure()?;
+
+        closure
+            .get_relation(query.get_rel_id())
+            .get_filtered_truths(query, &closure, &RecursionTally::new(self.recursion_limit))
+    }
+
+    // computes the least fixpoint of every conditional one stratum at a time, only rules whose
+    // head can be fully literalized from its body contribute truths, just like on the top down
+    // evaluation
+    pub fn semi_naive_closure(&self) -> Result<Engine, DeductionError> {
+        let mut rules = vec![];
+        let mut full = BTreeMap::new();
+
+        for (rel_id, relation) in &self.tables {
+            let mut truths = TruthList::new();
+            for truth in relation.get_literal_truths() {
+                truths.add(truth.to_owned());
+            }
+            full.insert(rel_id.to_owned(), truths);
+
+            for conditional in relation.get_conditions() {
+                rules.push((rel_id.to_owned(), conditional.to_owned()));
+            }
+        }
+
+        let strata = match self.stratify() {
+            Ok(strata) => strata,
+            Err(cycle) => return Err(RuntimeError::NegationCycle(cycle).to_string().into()),
+        };
+
+        for stratum in strata {
+            let stratum_rules: Vec<(RelId, ConditionalTruth)> = rules
+                .iter()
+                .filter(|(rel_id, _)| stratum.contains(rel_id))
+                .cloned()
+                .collect();
+
+            let mut delta = self.fire_rules(stratum_rules.to_owned(), &full, &BTreeMap::new())?;
+
+            while delta.values().any(|truths| !truths.is_empty()) {
+                printprocess!("semi naive iteration with {} new relations", delta.len());
+
+                for (rel_id, truths) in &delta {
+                    let full_truths = full.entry(rel_id.to_owned()).or_insert_with(TruthList::new);
+                    for truth in truths.into_iter() {
+                        full_truths.add(truth);
+                    }
+                }
+
+                let mut delta_rules = vec![];
+                for (rel_id, rule) in &stratum_rules {
+                    for variant in rule.map_condition_relations(&delta_relation) {
+                        delta_rules.push((rel_id.to_owned(), variant));
+                    }
+                }
+
+                delta = self.fire_rules(delta_rules, &full, &delta)?;
+            }
+        }
+
+        Ok(self.materialize(&full, &BTreeMap::new()))
+    }
+
+    fn fire_rules(
+        &self,
+        rules: Vec<(RelId, ConditionalTruth)>,
+        full: &BTreeMap<RelId, TruthList>,
+        delta: &BTreeMap<RelId, TruthList>,
+    ) -> Result<BTreeMap<RelId, TruthList>, DeductionError> {
+        let evaluation_engine = self.materialize(full, delta);
+        let mut ret = BTreeMap::new();
+
+        for (rel_id, mut rule) in rules {
+            let filter = DeferedRelation::from((
+                &rel_id.identifier,
+                vec![ExpresionSemantics::Literal(Data::Any).into(); rel_id.column_count],
+            ));
+
+            let deductions = rule.get_deductions(
+                &filter,
+                &evaluation_engine,
+                &RecursionTally::new(self.recursion_limit),
+            )?;
+
+            for truth in deductions.into_iter() {
+                let known = match full.get(&rel_id) {
+                    Some(truths) => truths.contains(&truth),
+                    None => false,
+                };
+                if !known {
+                    ret.entry(rel_id.to_owned())
+                        .or_insert_with(TruthList::new)
+                        .add(truth);
+                }
+            }
+        }
+
+        Ok(ret)
+    }
+
+    fn materialize(
+        &self,
+        full: &BTreeMap<RelId, TruthList>,
+        delta: &BTreeMap<RelId, TruthList>,
+    ) -> Engine {
+        let mut tables = BTreeMap::new();
+
+        for (rel_id, truths) in full {
+            tables.insert(rel_id.to_owned(), Relation::from((rel_id, truths)));
+        }
+        for (rel_id, truths) in delta {
+            let delta_id = delta_rel_id(rel_id);
+            tables.insert(delta_id.to_owned(), Relation::from((&delta_id, truths)));
+        }
+
+        Engine {
+            recursion_limit: self.recursion_limit,
+            evaluation_mode: EvaluationMode::TopDown,
+            tables,
+            answer_tables: Rc::new(RefCell::new(AnswerTables::new())),
+            storage: None,
+            journal: vec![],
+            transaction_start: None,
+            storage_warnings: vec![],
+            types: self.types.to_owned(),
+        }
+    }
+}
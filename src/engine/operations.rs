@@ -1,8 +1,76 @@
-use crate::parser::data_token::Data;
+use crate::parser::{data_token::Data, decimal::Decimal};
+
+// mixing kinds of numbers gives the least exact one, integers become decimals when they overflow
+// or dont divide evenly and anything touching a float becomes a float
+fn operate_numbers(
+    op1: &Data,
+    op2: &Data,
+    on_integers: fn(i64, i64) -> Option<i64>,
+    on_decimals: fn(&Decimal, &Decimal) -> Result<Decimal, String>,
+    on_floats: fn(f64, f64) -> f64,
+) -> Option<Result<Data, String>> {
+    if let (Data::Integer(x), Data::Integer(y)) = (op1, op2) {
+        if let Some(ret) = on_integers(*x, *y) {
+            return Some(Ok(Data::Integer(ret)));
+        }
+    }
+    match (op1, op2) {
+        (Data::Number(_), _) | (_, Data::Number(_)) => {
+            Some(Ok(Data::Number(on_floats(op1.to_f64()?, op2.to_f64()?))))
+        }
+        _ => Some(on_decimals(&op1.to_decimal()?, &op2.to_decimal()?).map(Data::Decimal)),
+    }
+}
+
+fn add_numbers(op1: &Data, op2: &Data) -> Option<Result<Data, String>> {
+    operate_numbers(op1, op2, i64::checked_add, Decimal::checked_add, |x, y| {
+        x + y
+    })
+}
+
+fn substract_numbers(op1: &Data, op2: &Data) -> Option<Result<Data, String>> {
+    operate_numbers(op1, op2, i64::checked_sub, Decimal::checked_sub, |x, y| {
+        x - y
+    })
+}
+
+fn multiply_numbers(op1: &Data, op2: &Data) -> Option<Result<Data, String>> {
+    operate_numbers(op1, op2, i64::checked_mul, Decimal::checked_mul, |x, y| {
+        x * y
+    })
+}
+
+fn divide_numbers(op1: &Data, op2: &Data) -> Option<Result<Data, String>> {
+    operate_numbers(
+        op1,
+        op2,
+        |x, y| match x.checked_rem(y) {
+            Some(0) => x.checked_div(y),
+            _ => None,
+        },
+        Decimal::checked_div,
+        |x, y| x / y,
+    )
+}
+
+// solving for a factor must give it back when multiplied, so a quotient rounded on the last digit
+// of a decimal binds nothing. floats are never exact to begin with
+fn divide_exactly(op1: &Data, op2: &Data) -> Option<Result<Data, String>> {
+    Some(match divide_numbers(op1, op2)? {
+        Ok(quotient @ Data::Number(_)) => Ok(quotient),
+        Ok(quotient) => match multiply_numbers(&quotient, op2) {
+            Some(Ok(product)) if product == *op1 => Ok(quotient),
+            _ => Err(format!("{op1} / {op2} has no exact quotient")),
+        },
+        Err(err) => Err(err),
+    })
+}
 
 pub fn add_direct(op1: Data, op2: Data) -> Result<Data, String> {
+    if let Some(ret) = add_numbers(&op1, &op2) {
+        return ret;
+    }
     Ok(match (op1, op2) {
-        (Data::Number(x), Data::Number(y)) => Data::Number(x + y),
         (Data::String(x), Data::String(y)) => Data::String(x.clone() + &y),
         (Data::Array(x), Data::Array(y)) => {
             Data::Array(x.iter().chain(y.iter()).map(|e| e.clone()).collect())
@@ -13,8 +81,10 @@ pub fn add_direct(op1: Data, op2: Data) -> Result<Data, String> {
 }
 
 pub fn add_reverse_op1(op2: Data, res: Data) -> Result<Data, String> {
+    if let Some(ret) = substract_numbers(&res, &op2) {
+        return ret;
+    }
     match (op2, res) {
-        (Data::Number(x), Data::Number(r)) => Ok(Data::Number(r - x)),
         (Data::String(x), Data::String(r)) => {
             if r.ends_with(&x) {
                 Ok(Data::String(r[0..r.len() - x.len()].to_string()))
@@ -45,8 +115,10 @@ pub fn add_reverse_op1(op2: Data, res: Data) -> Result<Data, String> {
 }
 
 pub fn add_reverse_op2(op1: Data, res: Data) -> Result<Data, String> {
+    if let Some(ret) = substract_numbers(&res, &op1) {
+        return ret;
+    }
     match (op1, res) {
-        (Data::Number(x), Data::Number(r)) => Ok(Data::Number(r - x)),
         (Data::String(x), Data::String(r)) => {
             if r.starts_with(&x) {
                 Ok(Data::String(r[x.len()..].to_string()))
@@ -77,82 +149,344 @@ pub fn add_reverse_op2(op1: Data, res: Data) -> Result<Data, String> {
 }
 
 pub fn substract_direct(op1: Data, op2: Data) -> Result<Data, String> {
-    Ok(match (op1, op2) {
-        (Data::Number(x), Data::Number(y)) => Data::Number(x - y),
-        (Data::String(_), Data::String(_)) => return Err("cant substract strings".into()),
-        (Data::Array(_), Data::Array(_)) => return Err("cant substract arrays".into()),
-        _ => return Err("cant operate on diferently typed literals".into()),
-    })
+    if let Some(ret) = substract_numbers(&op1, &op2) {
+        return ret;
+    }
+    match (op1, op2) {
+        (Data::String(_), Data::String(_)) => Err("cant substract strings".into()),
+        (Data::Array(_), Data::Array(_)) => Err("cant substract arrays".into()),
+        _ => Err("cant operate on diferently typed literals".into()),
+    }
 }
 
 pub fn substract_reverse_op1(op2: Data, res: Data) -> Result<Data, String> {
-    Ok(match (op2, res) {
-        (Data::Number(x), Data::Number(r)) => Data::Number(r + x),
-        (Data::String(_), Data::String(_)) => return Err("cant substract strings".into()),
-        (Data::Array(_), Data::Array(_)) => return Err("cant substract arrays".into()),
-        _ => return Err("cant operate on diferently typed literals".into()),
-    })
+    if let Some(ret) = add_numbers(&res, &op2) {
+        return ret;
+    }
+    match (op2, res) {
+        (Data::String(_), Data::String(_)) => Err("cant substract strings".into()),
+        (Data::Array(_), Data::Array(_)) => Err("cant substract arrays".into()),
+        _ => Err("cant operate on diferently typed literals".into()),
+    }
 }
 
 pub fn substract_reverse_op2(op1: Data, res: Data) -> Result<Data, String> {
-    Ok(match (op1, res) {
-        (Data::Number(x), Data::Number(r)) => Data::Number(r - x),
-        (Data::String(_), Data::String(_)) => return Err("cant substract strings".into()),
-        (Data::Array(_), Data::Array(_)) => return Err("cant substract arrays".into()),
-        _ => return Err("cant operate on diferently typed literals".into()),
-    })
+    if let Some(ret) = substract_numbers(&op1, &res) {
+        return ret;
+    }
+    match (op1, res) {
+        (Data::String(_), Data::String(_)) => Err("cant substract strings".into()),
+        (Data::Array(_), Data::Array(_)) => Err("cant substract arrays".into()),
+        _ => Err("cant operate on diferently typed literals".into()),
+    }
 }
 
 pub fn multiply_direct(op1: Data, op2: Data) -> Result<Data, String> {
-    Ok(match (op1, op2) {
-        (Data::Number(x), Data::Number(y)) => Data::Number(x * y),
-        (Data::String(_), Data::String(_)) => return Err("cant multiply strings".into()),
-        (Data::Array(_), Data::Array(_)) => return Err("cant multiply arrays".into()),
-        _ => return Err("cant operate on diferently typed literals".into()),
-    })
+    if let Some(ret) = multiply_numbers(&op1, &op2) {
+        return ret;
+    }
+    match (op1, op2) {
+        (Data::String(_), Data::String(_)) => Err("cant multiply strings".into()),
+        (Data::Array(_), Data::Array(_)) => Err("cant multiply arrays".into()),
+        _ => Err("cant operate on diferently typed literals".into()),
+    }
 }
 
 pub fn multiply_reverse_op1(op2: Data, res: Data) -> Result<Data, String> {
-    Ok(match (op2, res) {
-        (Data::Number(x), Data::Number(r)) => Data::Number(r / x),
-        (Data::String(_), Data::String(_)) => return Err("cant multiply strings".into()),
-        (Data::Array(_), Data::Array(_)) => return Err("cant multiply arrays".into()),
-        _ => return Err("cant operate on diferently typed literals".into()),
-    })
+    if let Some(ret) = divide_exactly(&res, &op2) {
+        return ret;
+    }
+    match (op2, res) {
+        (Data::String(_), Data::String(_)) => Err("cant multiply strings".into()),
+        (Data::Array(_), Data::Array(_)) => Err("cant multiply arrays".into()),
+        _ => Err("cant operate on diferently typed literals".into()),
+    }
 }
 
 pub fn multiply_reverse_op2(op1: Data, res: Data) -> Result<Data, String> {
-    Ok(match (op1, res) {
-        (Data::Number(x), Data::Number(r)) => Data::Number(r / x),
-        (Data::String(_), Data::String(_)) => return Err("cant multiply strings".into()),
-        (Data::Array(_), Data::Array(_)) => return Err("cant multiply arrays".into()),
-        _ => return Err("cant operate on diferently typed literals".into()),
-    })
+    if let Some(ret) = divide_exactly(&res, &op1) {
+        return ret;
+    }
+    match (op1, res) {
+        (Data::String(_), Data::String(_)) => Err("cant multiply strings".into()),
+        (Data::Array(_), Data::Array(_)) => Err("cant multiply arrays".into()),
+        _ => Err("cant operate on diferently typed literals".into()),
+    }
 }
 
 pub fn divide_direct(op1: Data, op2: Data) -> Result<Data, String> {
-    Ok(match (op1, op2) {
-        (Data::Number(x), Data::Number(y)) => Data::Number(x / y),
-        (Data::String(_), Data::String(_)) => return Err("cant divide strings".into()),
-        (Data::Array(_), Data::Array(_)) => return Err("cant divide arrays".into()),
-        _ => return Err("cant operate on diferently typed literals".into()),
-    })
+    if let Some(ret) = divide_numbers(&op1, &op2) {
+        return ret;
+    }
+    match (op1, op2) {
+        (Data::String(_), Data::String(_)) => Err("cant divide strings".into()),
+        (Data::Array(_), Data::Array(_)) => Err("cant divide arrays".into()),
+        _ => Err("cant operate on diferently typed literals".into()),
+    }
 }
 
 pub fn divide_reverse_op1(op2: Data, res: Data) -> Result<Data, String> {
-    Ok(match (op2, res) {
-        (Data::Number(x), Data::Number(r)) => Data::Number(x * r),
-        (Data::String(_), Data::String(_)) => return Err("cant divide strings".into()),
-        (Data::Array(_), Data::Array(_)) => return Err("cant divide arrays".into()),
-        _ => return Err("cant operate on diferently typed literals".into()),
-    })
+    if let Some(ret) = multiply_numbers(&op2, &res) {
+        return ret;
+    }
+    match (op2, res) {
+        (Data::String(_), Data::String(_)) => Err("cant divide strings".into()),
+        (Data::Array(_), Data::Array(_)) => Err("cant divide arrays".into()),
+        _ => Err("cant operate on diferently typed literals".into()),
+    }
 }
 
 pub fn divide_reverse_op2(op1: Data, res: Data) -> Result<Data, String> {
-    Ok(match (op1, res) {
-        (Data::Number(x), Data::Number(r)) => Data::Number(x / r),
-        (Data::String(_), Data::String(_)) => return Err("cant divide strings".into()),
-        (Data::Array(_), Data::Array(_)) => return Err("cant divide arrays".into()),
-        _ => return Err("cant operate on diferently typed literals".into()),
-    })
+    if let Some(ret) = divide_exactly(&op1, &res) {
+        return ret;
+    }
+    match (op1, res) {
+        (Data::String(_), Data::String(_)) => Err("cant divide strings".into()),
+        (Data::Array(_), Data::Array(_)) => Err("cant divide arrays".into()),
+        _ => Err("cant operate on diferently typed literals".into()),
+    }
+}
+
+// % and // round the quotient down, so the remainder takes the sign of the divisor and
+// x = (x // y) * y + x % y always holds
+fn floor_divide_integers(x: i64, y: i64) -> Option<i64> {
+    let quotient = x.checked_div(y)?;
+    if x % y != 0 && (x < 0) != (y < 0) {
+        quotient.checked_sub(1)
+    } else {
+        Some(quotient)
+    }
+}
+
+fn modulo_integers(x: i64, y: i64) -> Option<i64> {
+    let remainder = x.checked_rem(y)?;
+    if remainder != 0 && (remainder < 0) != (y < 0) {
+        remainder.checked_add(y)
+    } else {
+        Some(remainder)
+    }
+}
+
+pub fn modulo_direct(op1: Data, op2: Data) -> Result<Data, String> {
+    let ret = operate_numbers(&op1, &op2, modulo_integers, Decimal::checked_rem, |x, y| {
+        x - y * (x / y).floor()
+    });
+    match ret {
+        Some(ret) => ret,
+        None => Err(format!("cant take the modulo of {op1} by {op2}")),
+    }
+}
+
+pub fn floor_divide_direct(op1: Data, op2: Data) -> Result<Data, String> {
+    let ret = operate_numbers(
+        &op1,
+        &op2,
+        floor_divide_integers,
+        Decimal::checked_floor_div,
+        |x, y| (x / y).floor(),
+    );
+    match ret {
+        Some(ret) => ret,
+        None => Err(format!("cant floor divide {op1} by {op2}")),
+    }
+}
+
+// many values share the same remainder or floor quotient, so neither can be solved for
+pub fn not_reversible_op(_: Data, _: Data) -> Result<Data, String> {
+    Err("not reverseable opration".into())
+}
+
+// whole exponents on exact numbers stay exact, any other goes through floats
+pub fn power_direct(op1: Data, op2: Data) -> Result<Data, String> {
+    if let (Data::Integer(x), Data::Integer(y)) = (&op1, &op2) {
+        if let Some(ret) = u32::try_from(*y).ok().and_then(|y| x.checked_pow(y)) {
+            return Ok(Data::Integer(ret));
+        }
+    }
+    let exponent = op2.to_decimal().and_then(|y| y.to_integer());
+    match (op1.to_decimal(), exponent) {
+        (Some(x), Some(y)) => Ok(Data::Decimal(x.checked_pow(y)?)),
+        _ => match (op1.to_f64(), op2.to_f64()) {
+            (Some(x), Some(y)) => Ok(Data::Number(x.powf(y))),
+            _ => Err(format!("cant raise {op1} to {op2}")),
+        },
+    }
+}
+
+// the positive root, an exact one when there is
+pub fn power_reverse_op1(op2: Data, res: Data) -> Result<Data, String> {
+    let (exponent, result) = match (op2.to_f64(), res.to_f64()) {
+        (Some(exponent), Some(result)) if exponent != 0.0 => (exponent, result),
+        _ => return Err("not reverseable opration".into()),
+    };
+    let root = result.powf(1.0 / exponent);
+    if root.is_nan() {
+        return Err(format!("{res} has no root of degree {op2}"));
+    }
+    let rounded = Data::Integer(root.round() as i64);
+    if power_direct(rounded.to_owned(), op2)? == res {
+        Ok(rounded)
+    } else {
+        Ok(Data::Number(root))
+    }
+}
+
+pub fn power_reverse_op2(op1: Data, res: Data) -> Result<Data, String> {
+    let (base, result) = match (op1.to_f64(), res.to_f64()) {
+        (Some(base), Some(result)) if base > 0.0 && base != 1.0 && result > 0.0 => (base, result),
+        _ => return Err("not reverseable opration".into()),
+    };
+    let exponent = result.ln() / base.ln();
+    let rounded = Data::Integer(exponent.round() as i64);
+    if power_direct(op1, rounded.to_owned())? == res {
+        Ok(rounded)
+    } else {
+        Ok(Data::Number(exponent))
+    }
+}
+
+// negating is its own reverse
+pub fn negate_direct(op: Data) -> Result<Data, String> {
+    match substract_numbers(&Data::Integer(0), &op) {
+        Some(ret) => ret,
+        None => Err(format!("cant negate {op}")),
+    }
+}
+
+// built in functions take their arguments already literalized and checked for arity
+
+fn get_string<'a>(args: &'a [Data], i: usize, function: &str) -> Result<&'a String, String> {
+    match &args[i] {
+        Data::String(s) => Ok(s),
+        other => Err(format!(
+            "argument {} of {function} must be a string, found {other}",
+            i + 1
+        )),
+    }
+}
+
+fn get_position(args: &[Data], i: usize, function: &str) -> Result<usize, String> {
+    match args[i].to_decimal().and_then(|x| x.to_integer()) {
+        Some(x) if x >= 0 => Ok(x as usize),
+        _ => Err(format!(
+            "argument {} of {function} must be a positive integer, found {}",
+            i + 1,
+            args[i]
+        )),
+    }
+}
+
+pub fn len_direct(args: &[Data]) -> Result<Data, String> {
+    Ok(Data::Integer(match &args[0] {
+        Data::String(s) => s.chars().count(),
+        Data::Array(arr) => arr.len(),
+        Data::Record(fields) => fields.len(),
+        other => return Err(format!("cant get the length of {other}")),
+    } as i64))
+}
+
+pub fn upper_direct(args: &[Data]) -> Result<Data, String> {
+    Ok(Data::String(get_string(args, 0, "upper")?.to_uppercase()))
+}
+
+pub fn lower_direct(args: &[Data]) -> Result<Data, String> {
+    Ok(Data::String(get_string(args, 0, "lower")?.to_lowercase()))
+}
+
+// positions count chars from 0, a substring reaching past the end is just cut there
+pub fn substr_direct(args: &[Data]) -> Result<Data, String> {
+    let s = get_string(args, 0, "substr")?;
+    let start = get_position(args, 1, "substr")?;
+    let count = get_position(args, 2, "substr")?;
+    Ok(Data::String(s.chars().skip(start).take(count).collect()))
+}
+
+pub fn split_direct(args: &[Data]) -> Result<Data, String> {
+    let s = get_string(args, 0, "split")?;
+    let separator = get_string(args, 1, "split")?;
+    if separator.is_empty() {
+        return Err("cant split by an empty separator".into());
+    }
+    Ok(Data::Array(
+        s.split(separator.as_str())
+            .map(|part| Data::String(part.to_string()))
+            .collect(),
+    ))
+}
+
+// the splitted string is the parts joined back, the separator cant be told from them
+pub fn split_reverse(args: &[Option<Data>], unknown: usize, goal: &Data) -> Result<Data, String> {
+    let separator = match (unknown, &args[1]) {
+        (0, Some(Data::String(separator))) => separator,
+        _ => return Err("split can only be solved for the splitted string".into()),
+    };
+    let parts = match goal {
+        Data::Array(parts) if !parts.is_empty() => parts,
+        other => return Err(format!("split gives an array, not {other}")),
+    };
+
+    let mut joined = vec![];
+    for part in parts {
+        match part {
+            Data::String(part) if !part.contains(separator.as_str()) => joined.push(part.as_str()),
+            other => return Err(format!("{other} cant be a part splitted by {separator:?}")),
+        }
+    }
+    Ok(Data::String(joined.join(separator)))
+}
+
+pub fn concat_direct(args: &[Data]) -> Result<Data, String> {
+    let mut ret = String::new();
+    for i in 0..args.len() {
+        ret += get_string(args, i, "concat")?;
+    }
+    Ok(Data::String(ret))
+}
+
+// what is left of the goal once the known arguments before and after the unknown one are cut
+pub fn concat_reverse(args: &[Option<Data>], unknown: usize, goal: &Data) -> Result<Data, String> {
+    let goal = match goal {
+        Data::String(goal) => goal,
+        other => return Err(format!("concat gives a string, not {other}")),
+    };
+    let known_part = |args: &[Option<Data>]| {
+        let mut ret = String::new();
+        for arg in args {
+            match arg {
+                Some(Data::String(s)) => ret += s,
+                _ => return Err("concat can only be solved for a single unknown string"),
+            }
+        }
+        Ok(ret)
+    };
+    let prefix = known_part(&args[..unknown])?;
+    let suffix = known_part(&args[unknown + 1..])?;
+
+    if goal.len() >= prefix.len() + suffix.len()
+        && goal.starts_with(&prefix)
+        && goal.ends_with(&suffix)
+    {
+        Ok(Data::String(
+            goal[prefix.len()..goal.len() - suffix.len()].to_string(),
+        ))
+    } else {
+        Err(format!(
+            "{goal:?} doesnt start with {prefix:?} and end with {suffix:?}"
+        ))
+    }
+}
+
+pub fn starts_with_direct(args: &[Data]) -> Result<Data, String> {
+    let s = get_string(args, 0, "starts_with")?;
+    let prefix = get_string(args, 1, "starts_with")?;
+    Ok(Data::Bool(s.starts_with(prefix.as_str())))
+}
+
+// a string contains its substrings and an array its items
+pub fn contains_direct(args: &[Data]) -> Result<Data, String> {
+    match (&args[0], &args[1]) {
+        (Data::String(s), Data::String(part)) => Ok(Data::Bool(s.contains(part.as_str()))),
+        (Data::Array(arr), item) => Ok(Data::Bool(arr.contains(item))),
+        (container, item) => Err(format!("{container} cant contain {item}")),
+    }
 }
@@ -0,0 +1,151 @@
+use std::fmt;
+
+use print_macros::*;
+
+use crate::parser::{defered_relation_token::DeferedRelation, HasRelId};
+
+use super::{
+    recursion_tally::RecursionTally,
+    relation::{conditional_truth::ConditionalTruth, truth::Truth, Relation},
+    var_context::VarContext,
+    var_context_universe::VarContextUniverse,
+    Engine, EvaluationMode,
+};
+
+#[derive(Debug, Clone)]
+pub enum Proof {
+    Fact(Truth),
+    Deduction {
+        truth: Truth,
+        rule: Box<ConditionalTruth>,
+        bindings: VarContext,
+        support: Vec<Proof>,
+    },
+    // comparisons and negations, they hold on the bindings of the rule that uses them
+    Check(String),
+    Unproven(Truth),
+}
+
+impl Proof {
+    fn draw(&self, depth: usize) -> String {
+        let indentation = "  ".repeat(depth);
+        match self {
+            Proof::Fact(truth) => format!("{indentation}{truth} is a fact\n"),
+            Proof::Deduction {
+                truth,
+                rule,
+                bindings,
+                support,
+            } => {
+                let mut ret = format!("{indentation}{truth} by {rule} with {bindings}\n");
+                for proof in support {
+                    ret += &proof.draw(depth + 1);
+                }
+                ret
+            }
+            Proof::Check(statement) => format!("{indentation}{statement} holds\n"),
+            Proof::Unproven(truth) => format!("{indentation}{truth} has no finite proof\n"),
+        }
+    }
+}
+
+impl fmt::Display for Proof {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.draw(0))
+    }
+}
+
+impl Engine {
+    // every answer of the query gets the first derivation found for it, derivations are searched
+    // on a tabled engine so a proof never depends on the recursion limit
+    pub fn why(&self, query: &DeferedRelation) -> Result<Vec<Proof>, String> {
+        printprocess!("why {}", query);
+
+        let answers = match self.evaluation_mode {
+            EvaluationMode::SemiNaive => self.semi_naive_query(query)?,
+            _ => self.query(
+                query,
+                &VarContext::new(),
+                &RecursionTally::new(self.recursion_limit),
+            )?,
+        };
+
+        let mut hypothetical_engine = self.clone();
+        for assumption in &query.assumptions {
+            hypothetical_engine.ingest_assumption(assumption, &VarContext::new())?;
+        }
+        let proving_engine = hypothetical_engine.get_lower_stratum();
+
+        let mut sorted_answers = answers.to_vector();
+        sorted_answers.sort();
+
+        let mut ret = vec![];
+        for truth in sorted_answers {
+            ret.push(match proving_engine.prove_truth(&truth, &mut vec![])? {
+                Some(proof) => proof,
+                None => Proof::Unproven(truth),
+            });
+        }
+        Ok(ret)
+    }
+
+    // a truth cant support itself, so the path being proven is skipped
+    pub fn prove_truth(
+        &self,
+        truth: &Truth,
+        path: &mut Vec<Truth>,
+    ) -> Result<Option<Proof>, String> {
+        if path.contains(truth) {
+            return Ok(None);
+        }
+
+        let relation = self.get_relation(truth.get_rel_id());
+        if relation.has_literal_truth(truth) {
+            return Ok(Some(Proof::Fact(truth.to_owned())));
+        }
+
+        path.push(truth.to_owned());
+        let ret = self.prove_deduction(&relation, truth, path);
+        path.pop();
+        ret
+    }
+
+    fn prove_deduction(
+        &self,
+        relation: &Relation,
+        truth: &Truth,
+        path: &mut Vec<Truth>,
+    ) -> Result<Option<Proof>, String> {
+        let filter = truth.to_defered_relation();
+        let recursion_tally = RecursionTally::new(self.recursion_limit);
+
+        for rule in relation.get_conditions() {
+            let mut universe = VarContextUniverse::new();
+            universe.insert(rule.get_base_context(&filter));
+
+            let mut contexts: Vec<VarContext> = rule
+                .get_condition()
+                .to_owned()
+                .memo_get_posible_contexts(self, &recursion_tally, &universe)?
+                .iter()
+                .filter(|context| rule.get_template().to_truth(context).as_ref() == Ok(truth))
+                .collect();
+            contexts.sort_by_key(|context| context.to_string());
+
+            for bindings in contexts {
+                if let Some(support) =
+                    rule.get_condition()
+                        .prove(self, &recursion_tally, &bindings, path)?
+                {
+                    return Ok(Some(Proof::Deduction {
+                        truth: truth.to_owned(),
+                        rule: Box::new(rule.to_owned()),
+                        bindings,
+                        support,
+                    }));
+                }
+            }
+        }
+        Ok(None)
+    }
+}
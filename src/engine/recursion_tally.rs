@@ -15,6 +15,10 @@ impl RecursionTally {
         }
     }
 
+    pub fn restart(&self) -> Self {
+        Self::new(self.max_recursion)
+    }
+
     pub fn go_deeper(&self, rel_id: &RelId) -> bool {
         if let Some(depth_count) = self.tally.get(rel_id) {
             depth_count.to_owned() > 0
@@ -1,25 +1,32 @@
-use std::{collections::HashSet, fmt, hash};
-mod conditional_truth;
+use std::{fmt, hash, rc::Rc};
+pub mod conditional_truth;
 pub mod truth;
+pub mod truth_index;
 
 use print_macros::*;
 
 use crate::parser::{
-    conditional_token::Conditional, defered_relation_token::DeferedRelation,
-    inmediate_relation_token::InmediateRelation,
+    conditional_token::Conditional, data_token::Data, defered_relation_token::DeferedRelation,
+    inmediate_relation_token::InmediateRelation, schema_token::Schema,
 };
 
-use self::{conditional_truth::ConditionalTruth, truth::Truth};
+use self::{conditional_truth::ConditionalTruth, truth::Truth, truth_index::TruthIndex};
 
 use super::{
-    recursion_tally::RecursionTally, truth_list::TruthList, var_context::VarContext, Engine, RelId,
+    recursion_tally::RecursionTally, truth_list::TruthList, var_context::VarContext,
+    DeductionError, Engine, RelId,
 };
 
+// rules have no size until they are evaluated, each one is assumed to derive this many truths
+const DERIVED_TRUTHS_ESTIMATE: f64 = 100.0;
+
 #[derive(Debug, Clone)]
 pub struct Relation {
     rel_id: RelId,
-    truths: HashSet<Truth>,
+    // shared between the clones of the engine until one of them changes it
+    truths: Rc<TruthIndex>,
     conditions: Vec<ConditionalTruth>,
+    schema: Option<Schema>,
 }
 use std::hash::Hash;
 impl Hash for Relation {
@@ -35,6 +42,7 @@ impl Hash for Relation {
             .cloned()
             .collect::<Vec<ConditionalTruth>>()
             .hash(state);
+        self.schema.hash(state);
     }
 }
 
@@ -42,30 +50,102 @@ impl Relation {
     pub fn new(rel_id: &RelId) -> Self {
         Self {
             rel_id: rel_id.to_owned(),
-            truths: HashSet::new(),
+            truths: Rc::new(TruthIndex::new()),
             conditions: vec![],
+            schema: None,
+        }
+    }
+
+    pub fn get_literal_truths(&self) -> impl Iterator<Item = &Truth> {
+        self.truths.iter()
+    }
+
+    pub fn has_literal_truth(&self, truth: &Truth) -> bool {
+        self.truths.contains(truth)
+    }
+
+    pub fn get_conditions(&self) -> &Vec<ConditionalTruth> {
+        &self.conditions
+    }
+
+    pub fn get_schema(&self) -> Option<&Schema> {
+        self.schema.as_ref()
+    }
+
+    // the truths and rules already on the relation must fit the schema before it replaces the
+    // current one
+    pub fn set_schema(&mut self, schema: Schema) -> Result<(), String> {
+        for truth in self.truths.iter() {
+            schema.check_truth(truth.get_data())?;
+        }
+        for conditional in &self.conditions {
+            schema.check_template(conditional.get_template())?;
         }
+        self.schema = Some(schema);
+        Ok(())
     }
 
     pub fn add_truth(&mut self, rule: InmediateRelation) -> Result<(), String> {
+        if let (Some(schema), false) = (&self.schema, rule.negated) {
+            schema.check_truth(&rule.args)?;
+        }
         match rule.negated {
-            false => {
-                self.truths.insert(Truth::from(&rule));
-            }
-            true => {
-                let what_we_want_to_remove = &rule.args.to_owned();
-                self.truths
-                    .retain(|elm| !elm.afirms(what_we_want_to_remove));
-            }
+            false => Rc::make_mut(&mut self.truths).insert(Truth::from(&rule)),
+            true => Rc::make_mut(&mut self.truths).remove(&Truth::from(&rule)),
         };
         Ok(())
     }
 
-    pub(crate) fn add_conditional(&mut self, cond: Conditional) -> Result<(), String> {
-        if self
-            .conditions
+    // truths expected to fit a filter pinning the given columns, assuming values are evenly spread
+    pub fn estimate_cardinality(&self, bound_columns: &[usize]) -> f64 {
+        let mut literal_truths = self.truths.count_truths() as f64;
+        let mut derived_truths = self.conditions.len() as f64 * DERIVED_TRUTHS_ESTIMATE;
+
+        for column in bound_columns {
+            literal_truths /= self.truths.count_distinct(*column).max(1) as f64;
+            derived_truths /= 10.0;
+        }
+        literal_truths + derived_truths
+    }
+
+    // the columns a filter pins to a value, those are looked up on the index instead of scanned
+    fn get_bound_columns(filter: &DeferedRelation, context: &VarContext) -> Vec<(usize, Data)> {
+        filter
+            .args
+            .iter()
+            .enumerate()
+            .filter_map(|(i, arg)| match arg.literalize(context) {
+                Ok(Data::Any) | Err(_) => None,
+                Ok(data) => Some((i, data)),
+            })
+            .collect()
+    }
+
+    pub fn get_matching_truths(
+        &self,
+        filter: &DeferedRelation,
+        caller_context: &VarContext,
+    ) -> Vec<(Truth, VarContext)> {
+        let mut ret = vec![];
+        let bound_columns = Relation::get_bound_columns(filter, caller_context);
+        for truth in self.truths.lookup(&bound_columns) {
+            if let Ok(context) = truth.bind_filter(filter, caller_context.to_owned()) {
+                ret.push((truth.to_owned(), context));
+            }
+        }
+        ret
+    }
+
+    pub(crate) fn has_conditional(&self, cond: &Conditional) -> bool {
+        self.conditions
             .contains(&ConditionalTruth::from(cond.to_owned()))
-        {
+    }
+
+    pub(crate) fn add_conditional(&mut self, cond: Conditional) -> Result<(), String> {
+        if let Some(schema) = &self.schema {
+            schema.check_template(&cond.relation)?;
+        }
+        if self.has_conditional(&cond) {
             Err(format!(
                 "La condición {} ya existe dentro de la tabla {:?}",
                 ConditionalTruth::from(cond),
@@ -82,12 +162,13 @@ impl Relation {
         filter: &DeferedRelation,
         engine: &Engine,
         caller_recursion_tally: &RecursionTally,
-    ) -> Result<TruthList, String> {
+    ) -> Result<TruthList, DeductionError> {
         let mut ret = TruthList::new();
         let mut recursion_tally = caller_recursion_tally.to_owned();
 
-        for literal_truth in self.truths.to_owned() {
-            ret.add(literal_truth);
+        let bound_columns = Relation::get_bound_columns(filter, &VarContext::new());
+        for literal_truth in self.truths.lookup(&bound_columns) {
+            ret.add(literal_truth.to_owned());
         }
         recursion_tally.count_up(&self.rel_id);
 
@@ -111,7 +192,7 @@ impl Relation {
         filter: &DeferedRelation,
         engine: &Engine,
         recursion_tally: &RecursionTally,
-    ) -> Result<TruthList, String> {
+    ) -> Result<TruthList, DeductionError> {
         printprocess!(
             "get filtered truths of {} with filter {}",
             self.rel_id.identifier,
@@ -132,14 +213,37 @@ impl Relation {
     }
 }
 
+impl From<(&RelId, &TruthList)> for Relation {
+    fn from(value: (&RelId, &TruthList)) -> Self {
+        let (rel_id, truths) = value;
+        Self {
+            rel_id: rel_id.to_owned(),
+            truths: Rc::new(
+                truths
+                    .into_iter()
+                    .fold(TruthIndex::new(), |mut acc, truth| {
+                        acc.insert(truth);
+                        acc
+                    }),
+            ),
+            conditions: vec![],
+            schema: None,
+        }
+    }
+}
+
 impl fmt::Display for Relation {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         let mut ret = String::new();
+        // one per line, so /export writes something that can be read back
+        if let Some(schema) = &self.schema {
+            ret += &format!("{schema}\n");
+        }
         for truth in self.truths.iter() {
-            ret += &format!("{truth}");
+            ret += &format!("{truth}\n");
         }
         for condition in self.conditions.iter() {
-            ret += &format!("{condition}");
+            ret += &format!("{condition}\n");
         }
 
         write!(f, "{}", ret)
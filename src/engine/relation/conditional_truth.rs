@@ -5,7 +5,7 @@ use print_macros::*;
 use crate::{
     engine::{
         recursion_tally::RecursionTally, truth_list::TruthList, var_context::VarContext,
-        var_context_universe::VarContextUniverse, Engine,
+        var_context_universe::VarContextUniverse, DeductionError, Engine,
     },
     parser::{
         conditional_token::Conditional, data_token::Data, defered_relation_token::DeferedRelation,
@@ -31,9 +31,37 @@ impl ConditionalTruth {
         filter: &DeferedRelation,
         engine: &Engine,
         recursion_tally: &RecursionTally,
-    ) -> Result<TruthList, String> {
+    ) -> Result<TruthList, DeductionError> {
         printprocess!("getting deductions of {}", self);
 
+        let mut posible_contexts = VarContextUniverse::new();
+        posible_contexts.insert(self.get_base_context(filter));
+
+        posible_contexts = match self.condition.memo_get_posible_contexts(
+            engine,
+            recursion_tally,
+            &posible_contexts,
+        ) {
+            Ok(posible_contexts) => posible_contexts,
+            Err(err) => return Err(err.blame(&self.to_conditional())),
+        };
+
+        printprocess!("* universe of {} is {}", self, posible_contexts);
+
+        let mut ret = TruthList::new();
+        for context in posible_contexts.iter() {
+            match self.template.to_truth(&context) {
+                Ok(truth) => ret.add(truth),
+                Err(_) => (),
+            };
+        }
+
+        printprocess!("* truths of {} filtered by {} are {}", self, filter, ret);
+
+        Ok(ret)
+    }
+
+    pub fn get_base_context(&self, filter: &DeferedRelation) -> VarContext {
         let mut base_context = VarContext::new();
 
         for (filter, template) in filter.args.iter().zip(self.template.args.to_owned()) {
@@ -46,28 +74,36 @@ impl ConditionalTruth {
                 Err(_) => (),
             }
         }
+        base_context
+    }
 
-        let mut posible_contexts = VarContextUniverse::new();
-        posible_contexts.insert(base_context);
+    pub fn get_condition(&self) -> &Statement {
+        &self.condition
+    }
 
-        posible_contexts =
-            self.condition
-                .memo_get_posible_contexts(engine, recursion_tally, &posible_contexts)?;
+    pub fn get_template(&self) -> &DeferedRelation {
+        &self.template
+    }
 
-        printprocess!("* universe of {} is {}", self, posible_contexts);
+    pub fn map_condition_relations(
+        &self,
+        f: &dyn Fn(&DeferedRelation) -> DeferedRelation,
+    ) -> Vec<ConditionalTruth> {
+        (0..self.condition.count_relations())
+            .map(|occurrence| ConditionalTruth {
+                condition: self.condition.map_relation(occurrence, f),
+                template: self.template.to_owned(),
+            })
+            .collect()
+    }
 
-        let mut ret = TruthList::new();
-        for context in posible_contexts.iter() {
-            match self.template.to_truth(&context) {
-                Ok(truth) => ret.add(truth),
-                Err(_) => (),
-            };
+    pub fn to_conditional(&self) -> Conditional {
+        Conditional {
+            conditional: self.condition.to_owned(),
+            relation: self.template.to_owned(),
         }
-
-        printprocess!("* truths of {} filtered by {} are {}", self, filter, ret);
-
-        Ok(ret)
     }
+
     pub fn from(c: Conditional) -> Self {
         ConditionalTruth {
             condition: c.conditional,
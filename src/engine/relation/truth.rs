@@ -4,7 +4,7 @@ use crate::{
     engine::{var_context::VarContext, RelId},
     parser::{
         data_token::Data, defered_relation_token::DeferedRelation,
-        inmediate_relation_token::InmediateRelation, HasRelId,
+        expresion_token::ExpresionSemantics, inmediate_relation_token::InmediateRelation, HasRelId,
     },
 };
 #[derive(Debug, Clone, PartialEq, PartialOrd, Eq, Hash)]
@@ -18,7 +18,7 @@ impl fmt::Display for Truth {
         let mut args = String::new();
         args += &"(";
         for (i, d) in self.data.iter().enumerate() {
-            args += &format!("{d}");
+            args += &d.to_string();
             if i != self.data.len() - 1 {
                 args += &",";
             }
@@ -44,10 +44,23 @@ impl Ord for Truth {
     }
 }
 
+impl HasRelId for Truth {
+    fn get_rel_id(&self) -> RelId {
+        self.rel_id.to_owned()
+    }
+}
+
 impl Truth {
-    pub fn afirms(&self, query: &Vec<Data>) -> bool {
-        query == &self.data
+    pub fn to_defered_relation(&self) -> DeferedRelation {
+        DeferedRelation::from((
+            &self.rel_id.identifier,
+            self.data
+                .iter()
+                .map(|data| ExpresionSemantics::Literal(data.to_owned()).into())
+                .collect(),
+        ))
     }
+
     pub fn get_data(&self) -> &Vec<Data> {
         &self.data
     }
@@ -61,28 +74,33 @@ impl Truth {
     ) -> Result<Truth, String> {
         let mut ret = self.clone();
 
+        let context = self.bind_filter(filter, caller_context)?;
+
+        for (i, filter_expresion) in filter.args.iter().enumerate() {
+            match filter_expresion.literalize(&context) {
+                Ok(Data::Any) => (),
+                Ok(data) => ret.data[i] = data,
+                Err(_) => unimplemented!(),
+            }
+        }
+        Ok(ret)
+    }
+
+    pub fn bind_filter(
+        &self,
+        filter: &DeferedRelation,
+        caller_context: VarContext,
+    ) -> Result<VarContext, String> {
         let mut context = caller_context;
         let mut pinned = vec![false; self.get_width()];
 
         while !pinned.iter().all(|&e| e) {
             let starting_pinned_count = pinned.iter().filter(|e| **e).count();
-            for (i, (goal, filter_expresion)) in
-                self.data.iter().zip(filter.to_owned().args).enumerate()
-            {
+            for (i, (goal, filter_expresion)) in self.data.iter().zip(&filter.args).enumerate() {
                 if !pinned[i] {
-                    let solution = filter_expresion.solve(&goal, &context);
-                    match solution {
-                        Ok(new_context) => {
-                            context = new_context;
-                            pinned[i] = true;
-                            match filter_expresion.literalize(&context) {
-                                Ok(Data::Any) => (),
-                                Ok(data) => ret.data[i] = data,
-                                Err(_) => unimplemented!(),
-                            }
-                        }
-
-                        Err(_) => (),
+                    if let Ok(new_context) = filter_expresion.solve(goal, &context) {
+                        context = new_context;
+                        pinned[i] = true;
                     }
                 }
             }
@@ -91,7 +109,7 @@ impl Truth {
                 return Err("unsolveable".into());
             }
         }
-        Ok(ret)
+        Ok(context)
     }
 }
 
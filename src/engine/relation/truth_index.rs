@@ -0,0 +1,108 @@
+use std::{
+    collections::{HashMap, HashSet},
+    rc::Rc,
+};
+
+use crate::parser::data_token::Data;
+
+use super::truth::Truth;
+
+// every column of a relation is indexed by value, truths are shared between the indexes so each
+// one only costs a pointer per row
+#[derive(Debug, Clone, Default)]
+pub struct TruthIndex {
+    truths: HashSet<Rc<Truth>>,
+    columns: Vec<HashMap<Data, HashSet<Rc<Truth>>>>,
+    // _ doesnt equal any value but fits every filter, so those truths are candidates for any lookup
+    wildcards: Vec<HashSet<Rc<Truth>>>,
+}
+
+impl TruthIndex {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn count_truths(&self) -> usize {
+        self.truths.len()
+    }
+
+    pub fn count_distinct(&self, column: usize) -> usize {
+        match self.columns.get(column) {
+            Some(values) => values.len(),
+            None => 0,
+        }
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &Truth> {
+        self.truths.iter().map(|truth| truth.as_ref())
+    }
+
+    pub fn contains(&self, truth: &Truth) -> bool {
+        self.truths.contains(truth)
+    }
+
+    pub fn insert(&mut self, truth: Truth) {
+        if self.truths.contains(&truth) {
+            return;
+        }
+        let truth = Rc::new(truth);
+
+        while self.columns.len() < truth.get_width() {
+            self.columns.push(HashMap::new());
+            self.wildcards.push(HashSet::new());
+        }
+        for (i, data) in truth.get_data().iter().enumerate() {
+            match data {
+                Data::Any => self.wildcards[i].insert(truth.to_owned()),
+                _ => self.columns[i]
+                    .entry(data.to_owned())
+                    .or_default()
+                    .insert(truth.to_owned()),
+            };
+        }
+        self.truths.insert(truth);
+    }
+
+    pub fn remove(&mut self, truth: &Truth) {
+        if !self.truths.remove(truth) {
+            return;
+        }
+        for (i, data) in truth.get_data().iter().enumerate() {
+            match data {
+                Data::Any => {
+                    self.wildcards[i].remove(truth);
+                }
+                _ => {
+                    if let Some(holders) = self.columns[i].get_mut(data) {
+                        holders.remove(truth);
+                        if holders.is_empty() {
+                            self.columns[i].remove(data);
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    // the truths that may fit a filter binding the given columns, only the most selective column
+    // is looked up so the caller still has to check the rest
+    pub fn lookup(&self, bound_columns: &[(usize, Data)]) -> Vec<&Truth> {
+        let candidates = bound_columns
+            .iter()
+            .filter(|(i, _)| *i < self.columns.len())
+            .map(|(i, data)| (self.columns[*i].get(data), &self.wildcards[*i]))
+            .min_by_key(|(holders, wildcards)| {
+                holders.map_or(0, |holders| holders.len()) + wildcards.len()
+            });
+
+        match candidates {
+            Some((holders, wildcards)) => holders
+                .into_iter()
+                .flatten()
+                .chain(wildcards.iter())
+                .map(|truth| truth.as_ref())
+                .collect(),
+            None => self.iter().collect(),
+        }
+    }
+}
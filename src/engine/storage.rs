@@ -0,0 +1,327 @@
+use std::{
+    collections::BTreeMap,
+    fs::{self, File, OpenOptions},
+    io::Write,
+    path::{Path, PathBuf},
+};
+
+use print_macros::*;
+
+use crate::parser::{
+    assumption_token::Assumption, data_token::Data, decimal::Decimal,
+    inmediate_relation_token::InmediateRelation,
+};
+
+use super::{get_lines_from_chars, Line};
+
+const SNAPSHOT_FILE: &str = "snapshot";
+const LOG_FILE: &str = "log";
+const COMMIT_MARK: &str = ".\n";
+
+// the log gets folded into a new snapshot once it holds this many entries
+pub const SNAPSHOT_INTERVAL: usize = 1000;
+
+// base truths are kept on a length prefixed encoding so they can be loaded without going through
+// the parser, rules and schemas are few so they are kept as source text:
+//   +rel_name count:data... adds a truth, -rel_name count:data... removes it
+//   :len:source adds a rule or a schema
+//   data is i<integer>; d<decimal>; n<float>; s<len>:<string> a<count>:<data...>
+//   r<count>:<len>:<key><data>... t f ~ (null) or _
+// entries are written in batches closed by a commit mark, a batch without its mark was never
+// committed
+#[derive(Debug)]
+pub struct Storage {
+    directory: PathBuf,
+    log: File,
+    logged_entries: usize,
+}
+
+fn encode_data(data: &Data) -> String {
+    match data {
+        Data::Integer(x) => format!("i{x};"),
+        Data::Decimal(x) => format!("d{x};"),
+        Data::Number(x) => format!("n{x};"),
+        Data::String(s) => format!("s{}:{s}", s.len()),
+        Data::Array(arr) => {
+            format!("a{}:", arr.len()) + &arr.iter().map(encode_data).collect::<String>()
+        }
+        Data::Record(fields) => {
+            format!("r{}:", fields.len())
+                + &fields
+                    .iter()
+                    .map(|(key, value)| format!("{}:{key}{}", key.len(), encode_data(value)))
+                    .collect::<String>()
+        }
+        Data::Bool(true) => "t".into(),
+        Data::Bool(false) => "f".into(),
+        Data::Null => "~".into(),
+        Data::Any => "_".into(),
+    }
+}
+
+fn encode_entry(entry: &Assumption) -> Result<String, String> {
+    match entry {
+        Assumption::RelationInmediate(rel) => Ok(format!(
+            "{}{} {}:{}\n",
+            if rel.negated { "-" } else { "+" },
+            rel.rel_name,
+            rel.args.len(),
+            rel.args.iter().map(encode_data).collect::<String>()
+        )),
+        Assumption::Conditional(_) | Assumption::Schema(_) => {
+            let source = format!("{entry}");
+            Ok(format!(":{}:{source}\n", source.len()))
+        }
+        other => Err(format!(
+            "only truths, rules and schemas are stored, found: {other}"
+        )),
+    }
+}
+
+struct Decoder<'a> {
+    raw: &'a str,
+    cursor: usize,
+}
+
+impl<'a> Decoder<'a> {
+    fn read_until(&mut self, delimiter: char) -> Result<&'a str, String> {
+        match self.raw[self.cursor..].find(delimiter) {
+            Some(len) => {
+                let ret = &self.raw[self.cursor..self.cursor + len];
+                self.cursor += len + delimiter.len_utf8();
+                Ok(ret)
+            }
+            None => Err(format!("missing {delimiter:?} at byte {}", self.cursor)),
+        }
+    }
+
+    fn read_len(&mut self, delimiter: char) -> Result<usize, String> {
+        let raw_len = self.read_until(delimiter)?;
+        raw_len
+            .parse::<usize>()
+            .map_err(|err| format!("bad length {raw_len:?}: {err}"))
+    }
+
+    fn read_exact(&mut self, len: usize) -> Result<&'a str, String> {
+        match self.raw.get(self.cursor..self.cursor + len) {
+            Some(ret) => {
+                self.cursor += len;
+                Ok(ret)
+            }
+            None => Err(format!("entry cut at byte {}", self.cursor)),
+        }
+    }
+
+    fn read_data(&mut self) -> Result<Data, String> {
+        match self.read_exact(1)? {
+            "i" => {
+                let raw_integer = self.read_until(';')?;
+                match raw_integer.parse::<i64>() {
+                    Ok(x) => Ok(Data::Integer(x)),
+                    Err(err) => Err(format!("bad integer {raw_integer:?}: {err}")),
+                }
+            }
+            "d" => {
+                let raw_decimal = self.read_until(';')?;
+                match Decimal::parse(raw_decimal) {
+                    Some(x) => Ok(Data::Decimal(x)),
+                    None => Err(format!("bad decimal {raw_decimal:?}")),
+                }
+            }
+            "n" => {
+                let raw_number = self.read_until(';')?;
+                match raw_number.parse::<f64>() {
+                    Ok(x) => Ok(Data::Number(x)),
+                    Err(err) => Err(format!("bad number {raw_number:?}: {err}")),
+                }
+            }
+            "s" => {
+                let len = self.read_len(':')?;
+                Ok(Data::String(self.read_exact(len)?.to_string()))
+            }
+            "a" => {
+                let count = self.read_len(':')?;
+                let mut ret = vec![];
+                for _ in 0..count {
+                    ret.push(self.read_data()?);
+                }
+                Ok(Data::Array(ret))
+            }
+            "r" => {
+                let count = self.read_len(':')?;
+                let mut ret = BTreeMap::new();
+                for _ in 0..count {
+                    let len = self.read_len(':')?;
+                    let key = self.read_exact(len)?.to_string();
+                    ret.insert(key, self.read_data()?);
+                }
+                Ok(Data::Record(ret))
+            }
+            "t" => Ok(Data::Bool(true)),
+            "f" => Ok(Data::Bool(false)),
+            "~" => Ok(Data::Null),
+            "_" => Ok(Data::Any),
+            other => Err(format!("unknown data tag {other:?}")),
+        }
+    }
+
+    fn read_entry(&mut self) -> Result<Assumption, String> {
+        let ret = match self.read_exact(1)? {
+            tag @ ("+" | "-") => {
+                let rel_name = self.read_until(' ')?.to_string();
+                let count = self.read_len(':')?;
+                let mut args = vec![];
+                for _ in 0..count {
+                    args.push(self.read_data()?);
+                }
+                Assumption::RelationInmediate(InmediateRelation {
+                    negated: tag == "-",
+                    rel_name,
+                    args,
+                    span: None,
+                    arg_spans: vec![],
+                })
+            }
+            ":" => {
+                let len = self.read_len(':')?;
+                let source = self.read_exact(len)?;
+                match get_lines_from_chars(source.to_string())?.pop() {
+                    Some(Line::Assumption(
+                        entry @ (Assumption::Conditional(_) | Assumption::Schema(_)),
+                    )) => entry,
+                    _ => return Err(format!("stored {source:?} is not a rule nor a schema")),
+                }
+            }
+            other => return Err(format!("unknown entry tag {other:?}")),
+        };
+        self.read_exact(1)?;
+        Ok(ret)
+    }
+}
+
+fn encode_batch(entries: &[Assumption]) -> Result<String, String> {
+    let mut ret = String::new();
+    for entry in entries {
+        ret += &encode_entry(entry)?;
+    }
+    Ok(ret + COMMIT_MARK)
+}
+
+// a write cut short by a crash can only leave a broken batch at the very end of a file, every
+// committed batch before it is kept and the caller is told the tail was torn
+fn decode_entries(raw: &str) -> (Vec<Assumption>, bool) {
+    let mut decoder = Decoder { raw, cursor: 0 };
+    let mut ret = vec![];
+    let mut batch = vec![];
+
+    while decoder.cursor < raw.len() {
+        if raw[decoder.cursor..].starts_with(COMMIT_MARK) {
+            decoder.cursor += COMMIT_MARK.len();
+            ret.append(&mut batch);
+            continue;
+        }
+        match decoder.read_entry() {
+            Ok(entry) => batch.push(entry),
+            Err(msg) => {
+                printdev!("discarding torn storage tail: {}", msg);
+                return (ret, true);
+            }
+        }
+    }
+
+    if !batch.is_empty() {
+        printdev!("discarding {} uncommitted entries", batch.len());
+        return (ret, true);
+    }
+    (ret, false)
+}
+
+fn read_if_exists(path: &Path) -> Result<String, String> {
+    // a torn write may split a multibyte char, which just makes the last batch fail to decode
+    match fs::read(path) {
+        Ok(raw) => Ok(String::from_utf8_lossy(&raw).into_owned()),
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(String::new()),
+        Err(err) => Err(format!("couldnt read {}: {err}", path.display())),
+    }
+}
+
+impl Storage {
+    // returns the storage along with every stored entry in the order they must be replayed, and
+    // whether the log ended on a torn write
+    pub fn open(directory: &Path) -> Result<(Storage, Vec<Assumption>, bool), String> {
+        fs::create_dir_all(directory)
+            .map_err(|err| format!("couldnt create {}: {err}", directory.display()))?;
+
+        let (mut entries, torn_snapshot) =
+            decode_entries(&read_if_exists(&directory.join(SNAPSHOT_FILE))?);
+        if torn_snapshot {
+            return Err(format!(
+                "the snapshot at {} is corrupted",
+                directory.display()
+            ));
+        }
+
+        let (log_entries, torn_log) = decode_entries(&read_if_exists(&directory.join(LOG_FILE))?);
+        let logged_entries = log_entries.len();
+        entries.extend(log_entries);
+
+        let log = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(directory.join(LOG_FILE))
+            .map_err(|err| format!("couldnt open the log: {err}"))?;
+
+        Ok((
+            Storage {
+                directory: directory.to_owned(),
+                log,
+                logged_entries,
+            },
+            entries,
+            torn_log,
+        ))
+    }
+
+    pub fn get_logged_entries(&self) -> usize {
+        self.logged_entries
+    }
+
+    // the batch is durable once this returns, and replayed whole or not at all
+    pub fn append(&mut self, entries: &[Assumption]) -> Result<(), String> {
+        if entries.is_empty() {
+            return Ok(());
+        }
+
+        let raw = encode_batch(entries)?;
+        self.log
+            .write_all(raw.as_bytes())
+            .and_then(|_| self.log.sync_data())
+            .map_err(|err| format!("couldnt append to the log: {err}"))?;
+
+        self.logged_entries += entries.len();
+        Ok(())
+    }
+
+    // the new snapshot replaces the old one atomically before the log is emptied, so a crash in
+    // between only means replaying a log whose entries are already in the snapshot
+    pub fn write_snapshot(&mut self, entries: &[Assumption]) -> Result<(), String> {
+        let raw = encode_batch(entries)?;
+
+        let temporary_path = self.directory.join(SNAPSHOT_FILE.to_string() + ".tmp");
+        let mut temporary = File::create(&temporary_path)
+            .map_err(|err| format!("couldnt create the snapshot: {err}"))?;
+        temporary
+            .write_all(raw.as_bytes())
+            .and_then(|_| temporary.sync_all())
+            .map_err(|err| format!("couldnt write the snapshot: {err}"))?;
+        fs::rename(&temporary_path, self.directory.join(SNAPSHOT_FILE))
+            .map_err(|err| format!("couldnt replace the snapshot: {err}"))?;
+
+        self.log
+            .set_len(0)
+            .and_then(|_| self.log.sync_all())
+            .map_err(|err| format!("couldnt empty the log: {err}"))?;
+        self.logged_entries = 0;
+        Ok(())
+    }
+}
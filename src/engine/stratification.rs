@@ -0,0 +1,126 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use crate::parser::HasRelId;
+
+use super::{Engine, RelId};
+
+#[derive(Debug, Clone, Default)]
+pub struct DependencyGraph {
+    // head -> (body relation, reached through a negation or an aggregate)
+    edges: BTreeMap<RelId, BTreeSet<(RelId, bool)>>,
+}
+
+impl DependencyGraph {
+    pub fn new() -> Self {
+        Self {
+            edges: BTreeMap::new(),
+        }
+    }
+
+    pub fn add_dependency(&mut self, head: &RelId, body: &RelId, negated: bool) {
+        self.add_relation(body);
+        self.edges
+            .entry(head.to_owned())
+            .or_default()
+            .insert((body.to_owned(), negated));
+    }
+
+    pub fn add_relation(&mut self, rel_id: &RelId) {
+        self.edges.entry(rel_id.to_owned()).or_default();
+    }
+
+    fn reaches(&self, from: &RelId, to: &RelId) -> bool {
+        let mut visited = BTreeSet::new();
+        let mut pending = vec![from.to_owned()];
+
+        while let Some(current) = pending.pop() {
+            if &current == to {
+                return true;
+            }
+            if visited.insert(current.to_owned()) {
+                if let Some(dependencies) = self.edges.get(&current) {
+                    for (dependency, _) in dependencies {
+                        pending.push(dependency.to_owned());
+                    }
+                }
+            }
+        }
+        false
+    }
+
+    fn negation_cycle(&self) -> Option<Vec<RelId>> {
+        for (head, dependencies) in &self.edges {
+            for (body, negated) in dependencies {
+                if *negated && self.reaches(body, head) {
+                    return Some(
+                        self.edges
+                            .keys()
+                            .filter(|rel_id| {
+                                self.reaches(head, rel_id) && self.reaches(rel_id, head)
+                            })
+                            .cloned()
+                            .collect(),
+                    );
+                }
+            }
+        }
+        None
+    }
+
+    // a relation lives on the same stratum as its positive dependencies and on a higher one than
+    // the relations it negates
+    pub fn strata(&self) -> Result<Vec<Vec<RelId>>, Vec<RelId>> {
+        if let Some(cycle) = self.negation_cycle() {
+            return Err(cycle);
+        }
+
+        let mut stratum_of: BTreeMap<RelId, usize> = self
+            .edges
+            .keys()
+            .map(|rel_id| (rel_id.to_owned(), 0))
+            .collect();
+
+        let mut changed = true;
+        while changed {
+            changed = false;
+            for (head, dependencies) in &self.edges {
+                for (body, negated) in dependencies {
+                    let required = stratum_of[body] + if *negated { 1 } else { 0 };
+                    if stratum_of[head] < required {
+                        stratum_of.insert(head.to_owned(), required);
+                        changed = true;
+                    }
+                }
+            }
+        }
+
+        let mut ret = vec![];
+        for (rel_id, stratum) in stratum_of {
+            while ret.len() <= stratum {
+                ret.push(vec![]);
+            }
+            ret[stratum].push(rel_id);
+        }
+        Ok(ret)
+    }
+}
+
+impl Engine {
+    pub fn get_dependency_graph(&self) -> DependencyGraph {
+        let mut ret = DependencyGraph::new();
+
+        for (rel_id, relation) in &self.tables {
+            ret.add_relation(rel_id);
+            for conditional in relation.get_conditions() {
+                for (body, negated) in conditional.get_condition().get_relations() {
+                    ret.add_dependency(rel_id, &body.get_rel_id(), negated);
+                }
+            }
+        }
+        ret
+    }
+
+    pub fn stratify(&self) -> Result<Vec<Vec<RelId>>, Vec<RelId>> {
+        self.get_dependency_graph().strata()
+    }
+}
@@ -37,4 +37,16 @@ impl TruthList {
     pub fn add(&mut self, truth: Truth) {
         self.truths.insert(truth);
     }
+
+    pub fn contains(&self, truth: &Truth) -> bool {
+        self.truths.contains(truth)
+    }
+
+    pub fn len(&self) -> usize {
+        self.truths.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.truths.is_empty()
+    }
 }
@@ -0,0 +1,429 @@
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fmt,
+};
+
+use crate::diagnostic::{self, Diagnostic, Severity, Span};
+use crate::parser::{
+    aggregate_token::AggregateFunction,
+    assumption_token::Assumption,
+    conditional_token::Conditional,
+    defered_relation_token::DeferedRelation,
+    expresion_token::{Expresion, ExpresionSemantics, VarName},
+    function_token::BuiltInFunction,
+    inmediate_relation_token::InmediateRelation,
+    line_token::Line,
+    schema_token::{ColumnType, Schema},
+    statement_token::{Comparison, Statement, StatementSemantics},
+    HasRelId, SourceLine,
+};
+
+use super::{relation::Relation, Engine, RelId};
+
+// passes over the rules until the column types stop changing, a bound in case they never do
+const MAX_INFERENCE_PASSES: usize = 100;
+
+#[derive(Debug, Clone, Default)]
+pub struct TypeInference {
+    pub column_types: BTreeMap<RelId, Vec<ColumnType>>,
+    // relations with a schema, whose facts are already rejected when they dont fit
+    declared: BTreeSet<RelId>,
+    // the fact or rule each column got its type from, so a conflict can name both sides
+    column_sources: BTreeMap<(RelId, usize), String>,
+    // the fact or rule being inferred
+    source: String,
+    // what didnt fit, along with the line it was found on
+    pub issues: Vec<(String, Span)>,
+}
+
+impl fmt::Display for TypeInference {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut ret = String::new();
+        for (rel_id, column_types) in &self.column_types {
+            let columns = column_types
+                .iter()
+                .map(|column_type| column_type.to_string())
+                .collect::<Vec<String>>()
+                .join(", ");
+            ret += &format!("{}({columns})\n", rel_id.identifier);
+        }
+        write!(f, "{ret}")
+    }
+}
+
+// the types of the variables of the rule being checked
+type TypeEnviroment = BTreeMap<String, ColumnType>;
+
+// the types a built in function takes and gives
+fn get_signature(function: BuiltInFunction) -> (Vec<ColumnType>, ColumnType) {
+    let string = || ColumnType::String;
+    match function {
+        BuiltInFunction::Len => (vec![ColumnType::Any], ColumnType::Number),
+        BuiltInFunction::Upper | BuiltInFunction::Lower => (vec![string()], string()),
+        BuiltInFunction::Substr => (
+            vec![string(), ColumnType::Number, ColumnType::Number],
+            string(),
+        ),
+        BuiltInFunction::Split => (
+            vec![string(), string()],
+            ColumnType::Array(Box::new(string())),
+        ),
+        BuiltInFunction::Concat => (vec![string()], string()),
+        BuiltInFunction::StartsWith => (vec![string(), string()], ColumnType::Bool),
+        BuiltInFunction::Contains => (vec![ColumnType::Any, ColumnType::Any], ColumnType::Bool),
+    }
+}
+
+impl TypeInference {
+    pub fn get_diagnostics(&self) -> Vec<Diagnostic> {
+        self.issues
+            .iter()
+            .map(|(issue, span)| Diagnostic {
+                span: Some(*span),
+                ..Diagnostic::new(Severity::Warning, diagnostic::TYPE_ISSUE, issue.to_owned())
+            })
+            .collect()
+    }
+
+    // the types held by the given tables, as if their schemas and facts were just learnt
+    pub fn of_tables(tables: &BTreeMap<RelId, Relation>) -> Self {
+        let mut ret = Self::default();
+        for relation in tables.values() {
+            if let Some(schema) = relation.get_schema() {
+                ret.learn_schema(schema);
+            }
+        }
+        for (rel_id, relation) in tables {
+            // the truths are kept unordered, so conflicting facts are learned in a fixed order
+            let mut truths = relation.get_literal_truths().collect::<Vec<_>>();
+            truths.sort_by(|a, b| a.get_data().cmp(b.get_data()));
+            for truth in truths {
+                ret.learn_fact(&InmediateRelation {
+                    negated: false,
+                    rel_name: rel_id.identifier.to_owned(),
+                    args: truth.get_data().to_owned(),
+                    span: None,
+                    arg_spans: vec![],
+                });
+            }
+        }
+        ret
+    }
+
+    pub fn learn_schema(&mut self, schema: &Schema) {
+        self.declared.insert(schema.get_rel_id());
+        self.column_types.insert(
+            schema.get_rel_id(),
+            schema
+                .columns
+                .iter()
+                .map(|(_, column_type)| column_type.to_owned())
+                .collect(),
+        );
+    }
+
+    // facts that dont fit were already warned about when they were given
+    pub fn learn_fact(&mut self, fact: &InmediateRelation) {
+        let _ = self.infer_fact(fact);
+    }
+
+    fn get_column_type(&self, rel_id: &RelId, column: usize) -> ColumnType {
+        match self.column_types.get(rel_id) {
+            Some(column_types) => column_types[column].to_owned(),
+            None => ColumnType::Any,
+        }
+    }
+
+    fn set_column_type(&mut self, rel_id: &RelId, column: usize, column_type: ColumnType) {
+        let column_types = self
+            .column_types
+            .entry(rel_id.to_owned())
+            .or_insert_with(|| vec![ColumnType::Any; rel_id.column_count]);
+        if column_types[column] != column_type {
+            column_types[column] = column_type;
+            self.column_sources
+                .insert((rel_id.to_owned(), column), self.source.to_owned());
+        }
+    }
+
+    fn infer_fact(&mut self, fact: &InmediateRelation) -> Result<(), String> {
+        self.source = fact.to_string();
+        let rel_id = fact.get_rel_id();
+        for (i, data) in fact.args.iter().enumerate() {
+            let column_type = self.get_column_type(&rel_id, i);
+            match column_type.unify(&ColumnType::of(data)) {
+                Some(unified) => self.set_column_type(&rel_id, i, unified),
+                None => {
+                    let source = match self.column_sources.get(&(rel_id.to_owned(), i)) {
+                        Some(source) => format!(" since {source}"),
+                        None => String::new(),
+                    };
+                    return Err(format!(
+                        "column {} of {} holds {column_type}{source}, found {}",
+                        i + 1,
+                        rel_id.identifier,
+                        data.to_string()
+                    ));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn infer_rule(&mut self, rule: &Conditional) -> Result<(), String> {
+        self.source = rule.to_string();
+        let mut enviroment = TypeEnviroment::new();
+        self.infer_statement(&mut enviroment, &rule.conditional)?;
+        self.infer_relation(&mut enviroment, &rule.relation)
+    }
+
+    fn infer_relation(
+        &mut self,
+        enviroment: &mut TypeEnviroment,
+        rel: &DeferedRelation,
+    ) -> Result<(), String> {
+        let rel_id = rel.get_rel_id();
+        for (i, arg) in rel.args.iter().enumerate() {
+            let column_type = self.get_column_type(&rel_id, i);
+            let inferred = self
+                .infer_expresion(enviroment, arg, &column_type)
+                .map_err(|msg| {
+                    let source = match self.column_sources.get(&(rel_id.to_owned(), i)) {
+                        Some(source) => format!(" ({column_type} since {source})"),
+                        None => String::new(),
+                    };
+                    format!(
+                        "on column {} of {}{source}, {msg}",
+                        i + 1,
+                        rel_id.identifier
+                    )
+                })?;
+            self.set_column_type(&rel_id, i, inferred);
+        }
+        Ok(())
+    }
+
+    fn infer_statement(
+        &mut self,
+        enviroment: &mut TypeEnviroment,
+        statement: &Statement,
+    ) -> Result<(), String> {
+        match statement.get_semantics() {
+            StatementSemantics::And(statement_a, statement_b)
+            | StatementSemantics::Or(statement_a, statement_b) => {
+                self.infer_statement(enviroment, statement_a)?;
+                self.infer_statement(enviroment, statement_b)
+            }
+            StatementSemantics::Not(statement) => self.infer_statement(enviroment, statement),
+            StatementSemantics::Relation(rel) => self.infer_relation(enviroment, rel),
+            StatementSemantics::ExpresionComparison(exp_a, exp_b, Comparison::Eq) => {
+                let type_a = self.infer_expresion(enviroment, exp_a, &ColumnType::Any)?;
+                let type_b = self.infer_expresion(enviroment, exp_b, &type_a)?;
+                self.infer_expresion(enviroment, exp_a, &type_b)?;
+                Ok(())
+            }
+            StatementSemantics::ExpresionComparison(exp_a, exp_b, _) => {
+                let type_a = self.infer_expresion(enviroment, exp_a, &ColumnType::Any)?;
+                let type_b = self.infer_expresion(enviroment, exp_b, &ColumnType::Any)?;
+                match type_a.unify(&type_b) {
+                    Some(_) => Ok(()),
+                    None => Err(format!("{statement} compares {type_a} with {type_b}")),
+                }
+            }
+            StatementSemantics::Matches(regex_match) => {
+                self.infer_expresion(enviroment, &regex_match.text, &ColumnType::String)?;
+                self.infer_expresion(enviroment, &regex_match.pattern, &ColumnType::String)?;
+                if let Some(captures) = &regex_match.captures {
+                    self.infer_expresion(
+                        enviroment,
+                        captures,
+                        &ColumnType::Array(Box::new(ColumnType::String)),
+                    )?;
+                }
+                Ok(())
+            }
+            StatementSemantics::Predicate(call) => {
+                self.infer_expresion(enviroment, call, &ColumnType::Bool)?;
+                Ok(())
+            }
+            StatementSemantics::True => Ok(()),
+        }
+    }
+
+    // the type of the expresion once it is known to fit the expected one
+    fn infer_expresion(
+        &mut self,
+        enviroment: &mut TypeEnviroment,
+        exp: &Expresion,
+        expected: &ColumnType,
+    ) -> Result<ColumnType, String> {
+        let inferred = match exp.get_semantics() {
+            ExpresionSemantics::Literal(data) => ColumnType::of(data),
+            ExpresionSemantics::Var(VarName::Direct(name))
+            | ExpresionSemantics::Var(VarName::ExplodeArray(name)) => {
+                let known = enviroment.get(name).cloned().unwrap_or(ColumnType::Any);
+                match known.unify(expected) {
+                    Some(unified) => {
+                        enviroment.insert(name.to_owned(), unified.to_owned());
+                        return Ok(unified);
+                    }
+                    None => return Err(format!("{exp} is used as {known} and as {expected}")),
+                }
+            }
+            ExpresionSemantics::Var(VarName::DestructuredArray(items)) => {
+                let mut item_type =
+                    match expected.unify(&ColumnType::Array(Box::new(ColumnType::Any))) {
+                        Some(ColumnType::Array(item_type)) => *item_type,
+                        _ => return Err(format!("{exp} destructures a {expected}")),
+                    };
+                for item in items {
+                    item_type = match item.get_semantics() {
+                        ExpresionSemantics::Var(VarName::ExplodeArray(_)) => {
+                            match self.infer_expresion(
+                                enviroment,
+                                item,
+                                &ColumnType::Array(Box::new(item_type)),
+                            )? {
+                                ColumnType::Array(item_type) => *item_type,
+                                _ => ColumnType::Any,
+                            }
+                        }
+                        _ => self.infer_expresion(enviroment, item, &item_type)?,
+                    }
+                }
+                ColumnType::Array(Box::new(item_type))
+            }
+            ExpresionSemantics::Var(VarName::DestructuredRecord(fields, rest)) => {
+                if expected.unify(&ColumnType::Record).is_none() {
+                    return Err(format!("{exp} destructures a {expected}"));
+                }
+                for (_, field) in fields {
+                    self.infer_expresion(enviroment, field, &ColumnType::Any)?;
+                }
+                if let Some(rest) = rest {
+                    self.infer_expresion(
+                        enviroment,
+                        &ExpresionSemantics::Var(VarName::Direct(rest.to_owned())).into(),
+                        &ColumnType::Record,
+                    )?;
+                }
+                ColumnType::Record
+            }
+            ExpresionSemantics::Arithmetic(exp_a, exp_b, op) if op.to_string == "+" => {
+                let type_a = self.infer_expresion(enviroment, exp_a, &ColumnType::Any)?;
+                let type_b = self.infer_expresion(enviroment, exp_b, &ColumnType::Any)?;
+                // numbers and strings add to their own kind, anything can be pushed to an array
+                match (&type_a, &type_b) {
+                    (ColumnType::Array(_), _) => type_a,
+                    (ColumnType::Any, ColumnType::Any) => ColumnType::Any,
+                    _ => {
+                        let operand_type = match type_a.unify(&type_b) {
+                            Some(operand_type) => operand_type,
+                            None => return Err(format!("{exp} adds {type_a} to {type_b}")),
+                        };
+                        self.infer_expresion(enviroment, exp_a, &operand_type)?;
+                        self.infer_expresion(enviroment, exp_b, &operand_type)?
+                    }
+                }
+            }
+            ExpresionSemantics::Negation(exp) => {
+                self.infer_expresion(enviroment, exp, &ColumnType::Number)?
+            }
+            ExpresionSemantics::Arithmetic(exp_a, exp_b, _) => {
+                self.infer_expresion(enviroment, exp_a, &ColumnType::Number)?;
+                self.infer_expresion(enviroment, exp_b, &ColumnType::Number)?;
+                ColumnType::Number
+            }
+            ExpresionSemantics::Function(call) => {
+                let (arg_types, return_type) = get_signature(call.function);
+                for (i, arg) in call.args.iter().enumerate() {
+                    // concat repeats the type of its last argument
+                    let arg_type = arg_types.get(i).or(arg_types.last());
+                    self.infer_expresion(enviroment, arg, arg_type.unwrap_or(&ColumnType::Any))?;
+                }
+                return_type
+            }
+            ExpresionSemantics::Aggregate(aggregate) => {
+                self.infer_statement(enviroment, &aggregate.condition)?;
+                match aggregate.function {
+                    AggregateFunction::Count => ColumnType::Number,
+                    AggregateFunction::Sum | AggregateFunction::Avg => {
+                        self.infer_expresion(enviroment, &aggregate.target, &ColumnType::Number)?
+                    }
+                    AggregateFunction::Min | AggregateFunction::Max => {
+                        self.infer_expresion(enviroment, &aggregate.target, expected)?
+                    }
+                }
+            }
+        };
+
+        match inferred.unify(expected) {
+            Some(unified) => Ok(unified),
+            None => Err(format!("{exp} is {inferred}, not {expected}")),
+        }
+    }
+}
+
+impl Engine {
+    // what the engine already holds shapes the types silently, only the new lines get reported.
+    // the types of its facts and schemas are kept as they are ingested, its rules are few enough
+    // to go over again
+    pub fn infer_types(&self, lines: &[SourceLine]) -> TypeInference {
+        let mut ret = self.types.as_ref().to_owned();
+
+        let known_rules: Vec<Conditional> = self
+            .tables
+            .values()
+            .flat_map(|relation| relation.get_conditions())
+            .map(|conditional| conditional.to_conditional())
+            .collect();
+
+        let mut new_facts = vec![];
+        let mut new_rules = vec![];
+        for SourceLine { line, span, .. } in lines {
+            match line {
+                Line::Assumption(Assumption::Schema(schema)) => ret.learn_schema(schema),
+                Line::Assumption(Assumption::RelationInmediate(fact)) if !fact.negated => {
+                    new_facts.push((fact, *span))
+                }
+                Line::Assumption(Assumption::Conditional(rule)) => new_rules.push((&**rule, *span)),
+                _ => (),
+            }
+        }
+
+        for _ in 0..MAX_INFERENCE_PASSES {
+            let previous_column_types = ret.column_types.to_owned();
+            for (fact, _) in &new_facts {
+                let _ = ret.infer_fact(fact);
+            }
+            for rule in known_rules
+                .iter()
+                .chain(new_rules.iter().map(|(rule, _)| *rule))
+            {
+                let _ = ret.infer_rule(rule);
+            }
+            if previous_column_types == ret.column_types {
+                break;
+            }
+        }
+
+        for (fact, span) in new_facts {
+            if ret.declared.contains(&fact.get_rel_id()) {
+                continue;
+            }
+            if let Err(msg) = ret.infer_fact(fact) {
+                ret.issues.push((format!("{fact}: {msg}"), span));
+            }
+        }
+        for (rule, span) in new_rules {
+            if let Err(msg) = ret.infer_rule(rule) {
+                ret.issues.push((format!("{rule}: {msg}"), span));
+            }
+        }
+        ret
+    }
+
+    pub fn type_diagnostics(&self, lines: &[SourceLine]) -> Vec<Diagnostic> {
+        self.infer_types(lines).get_diagnostics()
+    }
+}
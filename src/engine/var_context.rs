@@ -1,5 +1,5 @@
 use crate::parser::data_token::Data;
-use std::collections::BTreeMap;
+use std::collections::{BTreeMap, BTreeSet};
 use std::fmt;
 use std::hash::Hash;
 use std::hash::Hasher;
@@ -101,6 +101,14 @@ impl VarContext {
     pub fn len(&self) -> usize {
         self.map.len()
     }
+
+    pub fn get_bound_vars(&self) -> BTreeSet<String> {
+        self.map
+            .iter()
+            .filter(|(_, value)| !matches!(value, Data::Any))
+            .map(|(var, _)| var.to_owned())
+            .collect()
+    }
 }
 
 impl From<BTreeMap<String, Data>> for VarContext {
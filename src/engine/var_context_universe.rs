@@ -1,4 +1,7 @@
-use std::{collections::HashSet, fmt};
+use std::{
+    collections::{BTreeSet, HashSet},
+    fmt,
+};
 
 use print_macros::*;
 
@@ -106,4 +109,24 @@ impl VarContextUniverse {
     pub fn len(&self) -> usize {
         self.contents.len()
     }
+
+    pub fn is_empty(&self) -> bool {
+        self.contents.is_empty()
+    }
+
+    // the variables every context has a value for
+    pub fn get_bound_vars(&self) -> BTreeSet<String> {
+        let mut contexts = self.contents.iter();
+        let mut ret = match contexts.next() {
+            Some(context) => context.get_bound_vars(),
+            None => return BTreeSet::new(),
+        };
+        for context in contexts {
+            ret = ret
+                .intersection(&context.get_bound_vars())
+                .cloned()
+                .collect();
+        }
+        ret
+    }
 }
@@ -0,0 +1,325 @@
+use crate::diagnostic::Diagnostic;
+use crate::engine::{read_source, RelId};
+use crate::parser::{
+    assumption_token::Assumption,
+    conditional_token::Conditional,
+    data_token::{key_to_string, Data},
+    defered_relation_token::DeferedRelation,
+    expresion_token::{Expresion, ExpresionSemantics, VarName},
+    inmediate_relation_token::InmediateRelation,
+    line_token::Line,
+    statement_token::{Statement, StatementSemantics},
+    HasRelId, SourceLine,
+};
+
+// rules longer than this get their body broken, one conjunct or disjunct per line
+const MAX_WIDTH: usize = 100;
+const INDENT: &str = "    ";
+
+// the lines a blank line goes between
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Group {
+    Fact(RelId),
+    Rule(RelId),
+    Schema,
+    // comments on their own, kept apart so they dont end up above the line under them
+    Comment,
+    Other,
+}
+
+impl Group {
+    fn of(line: &Line) -> Group {
+        match line {
+            Line::Comment(_) => Group::Comment,
+            Line::Assumption(Assumption::RelationInmediate(rel)) if !rel.negated => {
+                Group::Fact(rel.get_rel_id())
+            }
+            Line::Assumption(Assumption::RelationDefered(rel)) if !rel.negated => {
+                Group::Fact(rel.get_rel_id())
+            }
+            Line::Assumption(Assumption::Conditional(cond)) => Group::Rule(cond.get_rel_id()),
+            Line::Assumption(Assumption::Schema(_)) => Group::Schema,
+            _ => Group::Other,
+        }
+    }
+}
+
+// the source written back canonically, as long as it has no errors
+pub fn format_source(source: &str) -> Result<String, Vec<Diagnostic>> {
+    let (_, lines, diagnostics) = read_source(source);
+    if !diagnostics.is_empty() {
+        return Err(diagnostics);
+    }
+    Ok(format_lines(&lines))
+}
+
+// the first comment after a line stays by its side, any other one goes above it
+pub fn format_lines(lines: &[SourceLine]) -> String {
+    let mut ret = String::new();
+    let mut last_group = None;
+    for source_line in group_facts(lines) {
+        let group = Group::of(&source_line.line);
+        if last_group.as_ref().is_some_and(|last| *last != group) {
+            ret += "\n";
+        }
+        let (beside, above) = match source_line.trailing_comments.split_first() {
+            Some((beside, above)) => (Some(beside), above),
+            None => (None, &[][..]),
+        };
+        for comment in source_line.leading_comments.iter().chain(above) {
+            ret += &format!("{comment}\n");
+        }
+        ret += &format_line(&source_line.line);
+        if let Some(comment) = beside {
+            ret += &format!(" {comment}");
+        }
+        ret += "\n";
+        last_group = Some(group);
+    }
+    ret
+}
+
+// facts are moved next to the others of their relation, but never past a line that isnt a fact, as
+// queries and rules in between see only the facts before them
+fn group_facts(lines: &[SourceLine]) -> Vec<&SourceLine> {
+    let mut ret = vec![];
+    let mut run = vec![];
+    for line in lines {
+        if let Group::Fact(_) = Group::of(&line.line) {
+            run.push(line);
+        } else {
+            ret.extend(sort_run(std::mem::take(&mut run)));
+            ret.push(line);
+        }
+    }
+    ret.extend(sort_run(run));
+    ret
+}
+
+// relations keep the order they first appear in, and the facts of each one their own order
+fn sort_run(mut run: Vec<&SourceLine>) -> Vec<&SourceLine> {
+    let mut order: Vec<Group> = vec![];
+    for line in &run {
+        let group = Group::of(&line.line);
+        if !order.contains(&group) {
+            order.push(group);
+        }
+    }
+    run.sort_by_key(|line| {
+        order
+            .iter()
+            .position(|group| *group == Group::of(&line.line))
+    });
+    run
+}
+
+pub fn format_line(line: &Line) -> String {
+    match line {
+        Line::Assumption(assumption) => format_assumption(assumption, true),
+        Line::Query(rel) => format!("{}?", format_relation(rel)),
+        Line::Why(rel) => format!("{}??", format_relation(rel)),
+        Line::Transaction(transaction) => format!("{transaction}"),
+        Line::Comment(comment) => format!("{comment}"),
+    }
+}
+
+fn format_assumption(assumption: &Assumption, break_long: bool) -> String {
+    match assumption {
+        Assumption::RelationInmediate(rel) => format_inmediate_relation(rel),
+        Assumption::RelationDefered(rel) => format_relation(rel),
+        Assumption::Conditional(cond) => format_conditional(cond, break_long),
+        Assumption::Update(update) => format!(
+            "{} -> {}",
+            format_relation(&update.filter),
+            format_relation(&update.goal)
+        ),
+        Assumption::Schema(schema) => format!("{schema}"),
+    }
+}
+
+// a rule with comments inside its body is always broken, so each comment stays above its operand
+fn format_conditional(cond: &Conditional, break_long: bool) -> String {
+    let head = format_relation(&cond.relation);
+    let items = flatten(&cond.conditional);
+    let commented = items
+        .iter()
+        .any(|(_, statement)| !statement.get_comments().is_empty());
+    let ret = format!("{head} :- {}", format_statement(&cond.conditional));
+    if !break_long || (!commented && ret.chars().count() <= MAX_WIDTH) {
+        return ret;
+    }
+
+    let comments = |statement: &Statement| {
+        statement
+            .get_comments()
+            .iter()
+            .map(|comment| format!("\n{INDENT}{comment}"))
+            .collect::<String>()
+    };
+    let mut ret = match comments(items[0].1).as_str() {
+        "" => format!("{head} :- {}", format_statement(items[0].1)),
+        above => format!("{head} :-{above}\n{INDENT}{}", format_statement(items[0].1)),
+    };
+    for (op, statement) in &items[1..] {
+        ret += &comments(statement);
+        ret += &format!("\n{INDENT}{op} {}", format_operand(statement));
+    }
+    ret
+}
+
+// statements are read left to right, so the chain of && and || hangs from the left operands
+fn flatten(statement: &Statement) -> Vec<(&'static str, &Statement)> {
+    match statement.get_semantics() {
+        StatementSemantics::And(sta, stb) => {
+            let mut ret = flatten(sta);
+            ret.push(("&&", stb));
+            ret
+        }
+        StatementSemantics::Or(sta, stb) => {
+            let mut ret = flatten(sta);
+            ret.push(("||", stb));
+            ret
+        }
+        _ => vec![("", statement)],
+    }
+}
+
+pub fn format_statement(statement: &Statement) -> String {
+    match statement.get_semantics() {
+        StatementSemantics::True => "true".into(),
+        StatementSemantics::And(sta, stb) => {
+            format!("{} && {}", format_statement(sta), format_operand(stb))
+        }
+        StatementSemantics::Or(sta, stb) => {
+            format!("{} || {}", format_statement(sta), format_operand(stb))
+        }
+        // a double negation cancels out unless it is written apart
+        StatementSemantics::Not(st) => match st.get_semantics() {
+            StatementSemantics::Not(_) => format!("!({})", format_statement(st)),
+            _ => format!("!{}", format_operand(st)),
+        },
+        StatementSemantics::ExpresionComparison(expa, expb, comparison) => format!(
+            "{} {comparison} {}",
+            format_expresion(expa),
+            format_expresion(expb)
+        ),
+        StatementSemantics::Matches(regex_match) => {
+            let mut args = vec![
+                format_expresion(&regex_match.text),
+                format_expresion(&regex_match.pattern),
+            ];
+            args.extend(regex_match.captures.as_ref().map(format_expresion));
+            format!("matches({})", args.join(", "))
+        }
+        StatementSemantics::Predicate(call) => format_expresion(call),
+        StatementSemantics::Relation(rel) => format_relation(rel),
+    }
+}
+
+// true is only read as such at the start of a statement
+fn format_operand(statement: &Statement) -> String {
+    match statement.get_semantics() {
+        StatementSemantics::And(_, _) | StatementSemantics::Or(_, _) | StatementSemantics::True => {
+            format!("({})", format_statement(statement))
+        }
+        _ => format_statement(statement),
+    }
+}
+
+pub fn format_expresion(exp: &Expresion) -> String {
+    match exp.get_semantics() {
+        ExpresionSemantics::Arithmetic(expa, expb, op) => {
+            let operand = |exp: &Expresion, on_the_right: bool| match op
+                .needs_parenthesis(exp, on_the_right)
+            {
+                true => format!("({})", format_expresion(exp)),
+                false => format_expresion(exp),
+            };
+            format!(
+                "{} {} {}",
+                operand(expa, false),
+                op.to_string,
+                operand(expb, true)
+            )
+        }
+        ExpresionSemantics::Literal(data) => format_data(data),
+        ExpresionSemantics::Var(var) => format_var(var),
+        ExpresionSemantics::Aggregate(aggregate) => format!(
+            "{}({} : {})",
+            aggregate.function,
+            format_expresion(&aggregate.target),
+            format_statement(&aggregate.condition)
+        ),
+        ExpresionSemantics::Function(call) => format!(
+            "{}({})",
+            call.function,
+            join(call.args.iter().map(format_expresion))
+        ),
+        ExpresionSemantics::Negation(exp) => match exp.get_semantics() {
+            ExpresionSemantics::Arithmetic(_, _, _) => format!("-({})", format_expresion(exp)),
+            _ => format!("-{}", format_expresion(exp)),
+        },
+    }
+}
+
+fn format_var(var: &VarName) -> String {
+    match var {
+        VarName::DestructuredArray(items) => {
+            format!("[{}]", join(items.iter().map(format_expresion)))
+        }
+        VarName::DestructuredRecord(fields, rest) => {
+            let fields = fields
+                .iter()
+                .map(|(key, exp)| format!("{}: {}", key_to_string(key), format_expresion(exp)));
+            let rest = rest.iter().map(|rest| format!("...{rest}"));
+            format!("{{{}}}", join(fields.chain(rest)))
+        }
+        VarName::Direct(name) if name.starts_with('#') => "_".into(),
+        VarName::Direct(name) => name.to_owned(),
+        VarName::ExplodeArray(name) => format!("...{name}"),
+    }
+}
+
+pub fn format_data(data: &Data) -> String {
+    match data {
+        Data::Array(items) => format!("[{}]", join(items.iter().map(format_data))),
+        Data::Record(fields) => {
+            let fields = fields
+                .iter()
+                .map(|(key, value)| format!("{}: {}", key_to_string(key), format_data(value)));
+            format!("{{{}}}", join(fields))
+        }
+        data => data.to_string(),
+    }
+}
+
+fn format_inmediate_relation(rel: &InmediateRelation) -> String {
+    format!(
+        "{}{}({})",
+        if rel.negated { "!" } else { "" },
+        rel.rel_name,
+        join(rel.args.iter().map(format_data))
+    )
+}
+
+fn format_relation(rel: &DeferedRelation) -> String {
+    let assumptions = if rel.assumptions.is_empty() {
+        String::new()
+    } else {
+        let assumptions = rel
+            .assumptions
+            .iter()
+            .map(|assumption| format_assumption(assumption, false));
+        format!("{{{}}} => ", join(assumptions))
+    };
+    format!(
+        "{assumptions}{}{}({})",
+        if rel.negated { "!" } else { "" },
+        rel.rel_name,
+        join(rel.args.iter().map(format_expresion))
+    )
+}
+
+fn join(items: impl Iterator<Item = String>) -> String {
+    items.collect::<Vec<String>>().join(", ")
+}
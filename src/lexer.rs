@@ -1,11 +1,12 @@
 use std::collections::BTreeMap;
 use std::io;
 
-use crate::utils::*;
+use crate::diagnostic::{self, Diagnostic, Severity, Span};
+use crate::parser::decimal::Decimal;
 
 #[derive(Debug, Clone)]
 pub enum LexogramType {
-    Comment,
+    Comment(String),
     RightParenthesis,
     LeftParenthesis,
     RightBracket,
@@ -15,14 +16,20 @@ pub enum LexogramType {
     DotDotDot,
     Coma,
     Identifier(String),
+    Integer(i64),
+    Decimal(Decimal),
     Number(f64),
     Word(String),
     OpLT,
     OpLTE,
     OpGT,
     OpGTE,
+    OpNotEq,
     OpMul,
     OpDiv,
+    OpMod,
+    OpFloorDiv,
+    OpPow,
     OpAdd,
     OpSub,
     TrueWhen,
@@ -36,10 +43,14 @@ pub enum LexogramType {
     WhiteSpace,
     CharEq,
     CharColon,
+    Colon,
     Any,
     Query,
+    Why,
     Update,
     True,
+    False,
+    Null,
 }
 #[derive(Debug, Clone)]
 pub struct Lexogram {
@@ -56,14 +67,19 @@ pub struct LexerError {
 }
 
 impl LexerError {
-    pub fn print(&self, original_string: &String) -> String {
-        let ret = format!(
-            "Lexer error breakdown: \n\"{:?}\" at: {}",
-            self.msg,
-            print_hilighted(original_string, self.pos_s, self.pos_f, "".into())
-        );
-
-        ret
+    pub fn to_diagnostic(&self) -> Diagnostic {
+        let message = match &self.msg {
+            LexerErrorMsg::IO(e) => format!("the input couldnt be read: {e}"),
+            LexerErrorMsg::Parse(e) => format!("a number couldnt be read: {e}"),
+            LexerErrorMsg::Custom(msg) => msg.to_owned(),
+        };
+        Diagnostic {
+            span: Some(Span {
+                start: self.pos_s,
+                end: self.pos_f,
+            }),
+            ..Diagnostic::new(Severity::Error, diagnostic::LEXER_ERROR, message)
+        }
     }
 }
 
@@ -90,17 +106,42 @@ fn parse(w: String) -> Option<LexogramType> {
     if w.len() == 0 {
         None
     } else {
-        if let Ok(ret) = w.parse::<f64>() {
+        // a word is whatever lies between reserved lexograms, so underscores are part of
+        // identifiers and only a lone _ is the wildcard
+        // literals are kept exact when they can be, floats are only for exponents and the like
+        if let Ok(ret) = w.parse::<i64>() {
+            Some(LexogramType::Integer(ret))
+        } else if let Some(ret) = Decimal::parse(&w) {
+            Some(LexogramType::Decimal(ret))
+        } else if let Ok(ret) = w.parse::<f64>() {
             Some(LexogramType::Number(ret))
+        } else if w == "_" {
+            Some(LexogramType::Any)
+        } else if w == "false" {
+            Some(LexogramType::False)
+        } else if w == "null" {
+            Some(LexogramType::Null)
         } else {
             Some(LexogramType::Identifier(w))
         }
     }
 }
 
+// true when the word lexes back as that same identifier, and not as a literal or keyword
+pub fn is_identifier(word: &str) -> bool {
+    match lex(&word.to_string()).as_deref() {
+        Ok(
+            [Lexogram {
+                l_type: LexogramType::Identifier(w),
+                ..
+            }],
+        ) => w == word,
+        _ => false,
+    }
+}
+
 fn check_tail(pos_s: usize, tail: &str) -> Result<Option<Vec<Lexogram>>, LexerErrorMsg> {
     let reserved_lexograms = BTreeMap::from([
-        ("#", LexogramType::Comment),
         ("true", LexogramType::True),
         (":", LexogramType::CharColon),
         (")", LexogramType::RightParenthesis),
@@ -112,11 +153,11 @@ fn check_tail(pos_s: usize, tail: &str) -> Result<Option<Vec<Lexogram>>, LexerEr
         ("...", LexogramType::DotDotDot),
         (",", LexogramType::Coma),
         ("<", LexogramType::OpLT),
-        ("<=", LexogramType::OpLTE),
         (">", LexogramType::OpGT),
-        (">=", LexogramType::OpGTE),
         ("*", LexogramType::OpMul),
         ("/", LexogramType::OpDiv),
+        ("%", LexogramType::OpMod),
+        ("^", LexogramType::OpPow),
         ("+", LexogramType::OpAdd),
         ("-", LexogramType::OpSub),
         ("=", LexogramType::CharEq),
@@ -126,7 +167,6 @@ fn check_tail(pos_s: usize, tail: &str) -> Result<Option<Vec<Lexogram>>, LexerEr
         ("!", LexogramType::OpNot),
         ("&&", LexogramType::OpAnd),
         ("||", LexogramType::OpOr),
-        ("_", LexogramType::Any),
         ("?", LexogramType::Query),
     ]);
 
@@ -196,6 +236,12 @@ fn compound_lexogram_analisis(simple: Vec<Lexogram>) -> Result<Vec<Lexogram>, Le
                     l_type: LexogramType::CharEq,
                 }] => (),
 
+                [Lexogram {
+                    pos_f: _,
+                    pos_s: _,
+                    l_type: LexogramType::OpSub,
+                }] => (),
+
                 [Lexogram {
                     pos_f: _,
                     pos_s,
@@ -257,6 +303,131 @@ fn compound_lexogram_analisis(simple: Vec<Lexogram>) -> Result<Vec<Lexogram>, Le
                     queue = vec![next.clone()];
                 }
 
+                [Lexogram {
+                    pos_f: _,
+                    pos_s: _,
+                    l_type: LexogramType::OpLT | LexogramType::OpGT | LexogramType::OpNot,
+                }] => (),
+
+                [first @ Lexogram {
+                    pos_f: _,
+                    pos_s,
+                    l_type: LexogramType::OpLT | LexogramType::OpGT | LexogramType::OpNot,
+                }, Lexogram {
+                    pos_f,
+                    pos_s: _,
+                    l_type: LexogramType::CharEq,
+                }] => {
+                    ret.push(Lexogram {
+                        pos_f: *pos_f,
+                        pos_s: *pos_s,
+                        l_type: match first.l_type {
+                            LexogramType::OpLT => LexogramType::OpLTE,
+                            LexogramType::OpGT => LexogramType::OpGTE,
+                            _ => LexogramType::OpNotEq,
+                        },
+                    });
+                    queue = vec![];
+                }
+
+                [single @ Lexogram {
+                    pos_f: _,
+                    pos_s: _,
+                    l_type: LexogramType::OpLT | LexogramType::OpGT | LexogramType::OpNot,
+                }, next] => {
+                    ret.push(single.clone());
+                    queue = vec![next.clone()];
+                    repeat_scan = true;
+                }
+
+                [Lexogram {
+                    pos_f: _,
+                    pos_s: _,
+                    l_type: LexogramType::OpMul | LexogramType::OpDiv,
+                }] => (),
+
+                // ** is the same as ^
+                [Lexogram {
+                    pos_f: _,
+                    pos_s,
+                    l_type: LexogramType::OpMul,
+                }, Lexogram {
+                    pos_f,
+                    pos_s: _,
+                    l_type: LexogramType::OpMul,
+                }] => {
+                    ret.push(Lexogram {
+                        pos_f: *pos_f,
+                        pos_s: *pos_s,
+                        l_type: LexogramType::OpPow,
+                    });
+                    queue = vec![];
+                }
+
+                [Lexogram {
+                    pos_f: _,
+                    pos_s,
+                    l_type: LexogramType::OpDiv,
+                }, Lexogram {
+                    pos_f,
+                    pos_s: _,
+                    l_type: LexogramType::OpDiv,
+                }] => {
+                    ret.push(Lexogram {
+                        pos_f: *pos_f,
+                        pos_s: *pos_s,
+                        l_type: LexogramType::OpFloorDiv,
+                    });
+                    queue = vec![];
+                }
+
+                [single @ Lexogram {
+                    pos_f: _,
+                    pos_s: _,
+                    l_type: LexogramType::OpMul | LexogramType::OpDiv,
+                }, next] => {
+                    ret.push(single.clone());
+                    queue = vec![next.clone()];
+                    repeat_scan = true;
+                }
+
+                [Lexogram {
+                    pos_f: _,
+                    pos_s: _,
+                    l_type: LexogramType::Query,
+                }] => (),
+
+                [Lexogram {
+                    pos_f: _,
+                    pos_s,
+                    l_type: LexogramType::Query,
+                }, Lexogram {
+                    pos_f,
+                    pos_s: _,
+                    l_type: LexogramType::Query,
+                }] => {
+                    ret.push(Lexogram {
+                        pos_f: *pos_f,
+                        pos_s: *pos_s,
+                        l_type: LexogramType::Why,
+                    });
+                    queue = vec![];
+                }
+
+                [Lexogram {
+                    pos_f,
+                    pos_s,
+                    l_type: LexogramType::Query,
+                }, next] => {
+                    ret.push(Lexogram {
+                        pos_f: *pos_f,
+                        pos_s: *pos_s,
+                        l_type: LexogramType::Query,
+                    });
+                    queue = vec![next.clone()];
+                    repeat_scan = true;
+                }
+
                 [Lexogram {
                     pos_f: _,
                     pos_s: _,
@@ -285,14 +456,14 @@ fn compound_lexogram_analisis(simple: Vec<Lexogram>) -> Result<Vec<Lexogram>, Le
                     pos_f,
                     pos_s,
                     l_type: LexogramType::CharColon,
-                }, _] => {
-                    return Err(LexerError {
-                        pos_s: *pos_s,
+                }, next] => {
+                    ret.push(Lexogram {
                         pos_f: *pos_f,
-                        msg: LexerErrorMsg::Custom(
-                            "compound lexogram analisis found a stray colon".into(),
-                        ),
-                    })
+                        pos_s: *pos_s,
+                        l_type: LexogramType::Colon,
+                    });
+                    repeat_scan = true;
+                    queue = vec![next.clone()];
                 }
 
                 [any_lex] => {
@@ -313,6 +484,18 @@ fn compound_lexogram_analisis(simple: Vec<Lexogram>) -> Result<Vec<Lexogram>, Le
         }
     }
 
+    // whatever was held waiting for a second char stands on its own at the end of the input
+    for l in queue {
+        ret.push(Lexogram {
+            l_type: match l.l_type {
+                LexogramType::CharEq => LexogramType::OpEq,
+                LexogramType::CharColon => LexogramType::Colon,
+                other => other,
+            },
+            ..l
+        });
+    }
+
     Ok(ret)
 }
 
@@ -320,25 +503,45 @@ fn simple_lexogram_analisis(str: &String) -> Result<Vec<Lexogram>, LexerError> {
     let mut ret: Vec<Lexogram> = vec![];
     let mut tail = String::new();
 
-    let mut c: u8 = 0;
+    // positions are in bytes, char_i is where the char after c starts
+    let mut c = '\0';
     let mut last_tail_reset = 0;
 
     let mut char_i = 0;
     let mut repeat = true;
 
-    match str.bytes().nth(char_i) {
+    match str[char_i..].chars().next() {
         Some(char) => c = char,
         None => repeat = false,
     }
 
     while repeat {
-        char_i += 1;
+        char_i += c.len_utf8();
 
-        if c == 34 /* " */ && tail.len() == 0 {
+        // a comment runs to the end of its line and keeps its text, without the #
+        if c == '#' {
+            if let Some(l_type) = parse(std::mem::take(&mut tail)) {
+                ret.push(Lexogram {
+                    pos_s: last_tail_reset,
+                    pos_f: char_i - 1,
+                    l_type,
+                });
+            }
+            let end = str[char_i..]
+                .find('\n')
+                .map_or(str.len(), |pos| char_i + pos);
+            ret.push(Lexogram {
+                pos_s: char_i - 1,
+                pos_f: end,
+                l_type: LexogramType::Comment(str[char_i..end].trim().to_string()),
+            });
+            char_i = end;
+            last_tail_reset = end;
+        } else if c == '"' && tail.len() == 0 {
             let mut inside_a_string = true;
             let mut scaping = false;
             while inside_a_string {
-                match (str.bytes().nth(char_i), scaping) {
+                match (str[char_i..].chars().next(), scaping) {
                     (None, _) => {
                         return Err(LexerError {
                             pos_s: last_tail_reset,
@@ -346,17 +549,17 @@ fn simple_lexogram_analisis(str: &String) -> Result<Vec<Lexogram>, LexerError> {
                             msg: LexerErrorMsg::Custom("Specting matching \" found EOF".into()),
                         })
                     }
-                    (Some(34 /* " */), false) => {
+                    (Some('"'), false) => {
                         char_i += 1;
                         inside_a_string = false
                     }
-                    (Some(92 /* \ */), false) => {
+                    (Some('\\'), false) => {
                         char_i += 1;
                         scaping = true
                     }
                     (Some(c), _) => {
-                        char_i += 1;
-                        tail.push(c as char);
+                        char_i += c.len_utf8();
+                        tail.push(c);
                         scaping = false;
                     }
                 }
@@ -368,8 +571,9 @@ fn simple_lexogram_analisis(str: &String) -> Result<Vec<Lexogram>, LexerError> {
                 l_type: LexogramType::Word(tail),
             });
             tail = String::new();
+            last_tail_reset = char_i;
         } else {
-            tail.push(c as char);
+            tail.push(c);
         }
 
         match check_tail(last_tail_reset, tail.as_str()) {
@@ -388,7 +592,7 @@ fn simple_lexogram_analisis(str: &String) -> Result<Vec<Lexogram>, LexerError> {
             }
         }
 
-        match str.bytes().nth(char_i) {
+        match str[char_i..].chars().next() {
             Some(char) => c = char,
             None => repeat = false,
         }
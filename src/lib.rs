@@ -0,0 +1,8 @@
+pub mod diagnostic;
+pub mod engine;
+pub mod formatter;
+pub mod lexer;
+pub mod parser;
+pub mod symbols;
+mod tests;
+mod utils;
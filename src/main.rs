@@ -1,14 +1,11 @@
-pub mod engine;
-mod lexer;
-mod parser;
-mod tests;
-mod utils;
-
 use std::fs::write;
-use std::{fs::read_to_string, io};
+use std::{env, fs::read_to_string, io, process};
 
-use crate::engine::Engine;
-use crate::parser::error::ParserError;
+use _datalang::diagnostic;
+use _datalang::engine::{self, Engine, EvaluationMode};
+use _datalang::formatter;
+use _datalang::lexer;
+use _datalang::parser::error::ParserError;
 
 #[derive(Debug)]
 enum DLErr {
@@ -43,12 +40,56 @@ impl From<std::io::Error> for DLErr {
 }
 const AUTO_RUN: bool = true;
 
+// the input is read once for both its type warnings and running it
+fn input(engine: &mut Engine, commands: String) -> String {
+    let (output, diagnostics) = engine.run(commands.to_owned());
+    output + &diagnostic::print(&diagnostics, &commands)
+}
+
 fn main() -> Result<(), DLErr> {
 
     let mut engine = Engine::new();
 
     engine.set_recursion_limit(4);
 
+    // run <file> [--json] goes through a file once, with the diagnostics apart on stderr
+    let args: Vec<String> = env::args().collect();
+    if let (Some("run"), Some(path)) = (args.get(1).map(String::as_str), args.get(2)) {
+        let commands = read_to_string(path)?;
+        let (output, diagnostics) = engine.run(commands.to_owned());
+        print!("{output}");
+        if args.iter().any(|arg| arg == "--json") {
+            eprintln!("{}", diagnostic::to_json(&diagnostics, &commands));
+        } else {
+            eprint!("{}", diagnostic::print(&diagnostics, &commands));
+        }
+        if diagnostics.iter().any(|diagnostic| diagnostic.is_error()) {
+            process::exit(1);
+        }
+        return Ok(());
+    }
+
+    // fmt <file> [--check] writes the file back canonically, or just tells if it already is
+    if let (Some("fmt"), Some(path)) = (args.get(1).map(String::as_str), args.get(2)) {
+        let commands = read_to_string(path)?;
+        let formatted = match formatter::format_source(&commands) {
+            Ok(formatted) => formatted,
+            Err(diagnostics) => {
+                eprint!("{}", diagnostic::print(&diagnostics, &commands));
+                process::exit(1);
+            }
+        };
+        if args.iter().any(|arg| arg == "--check") {
+            if formatted != commands {
+                eprintln!("{path} is not formatted");
+                process::exit(1);
+            }
+        } else if formatted != commands {
+            write(path, formatted)?;
+        }
+        return Ok(());
+    }
+
     let stdin = io::stdin();
 
     if AUTO_RUN {
@@ -76,7 +117,7 @@ fn main() -> Result<(), DLErr> {
                     .skip(1)
                     .collect();
                 match read_to_string(file_path.trim()) {
-                    Ok(commands) => println!("{}", engine.input(commands)),
+                    Ok(commands) => println!("{}", input(&mut engine, commands)),
                     Err(err) => println!(
                         "the file couldnt be read ({}), reason: {err}",
                         file_path.trim()
@@ -108,8 +149,42 @@ fn main() -> Result<(), DLErr> {
                     Err(err) => println!("error parsing argument: {err:?}"),
                 }
             }
+
+            if buffer.starts_with("/open") {
+                let path: String = buffer.chars().skip_while(|c| c != &' ').skip(1).collect();
+                match Engine::open(path.trim()) {
+                    Ok(opened) => engine = opened,
+                    Err(err) => println!("the storage couldnt be opened, reason: {err}"),
+                }
+            }
+
+            if buffer.starts_with("/snapshot") {
+                match engine.snapshot() {
+                    Ok(_) => println!("ok"),
+                    Err(err) => println!("snapshot failed due to: {err}"),
+                }
+            }
+
+            if buffer.starts_with("/explain") {
+                let query: String = buffer.chars().skip_while(|c| c != &' ').skip(1).collect();
+                println!("{}", engine.explain_input(query));
+            }
+
+            if buffer.starts_with("/types") {
+                print!("{}", engine.infer_types(&[]));
+            }
+
+            if buffer.starts_with("/set_evaluation_mode") {
+                let arg: String = buffer.chars().skip_while(|c| c != &' ').skip(1).collect();
+                match arg.trim() {
+                    "top_down" => engine.set_evaluation_mode(EvaluationMode::TopDown),
+                    "semi_naive" => engine.set_evaluation_mode(EvaluationMode::SemiNaive),
+                    "tabled" => engine.set_evaluation_mode(EvaluationMode::Tabled),
+                    other => println!("unknown evaluation mode: {other}"),
+                }
+            }
         } else {
-            println!("{}", engine.input(buffer));
+            println!("{}", input(&mut engine, buffer));
         }
     }
 
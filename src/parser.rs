@@ -1,19 +1,27 @@
+pub mod aggregate_token;
 pub mod assumption_token;
 pub mod conditional_token;
 pub mod data_token;
+pub mod decimal;
 pub mod defered_relation_token;
 pub mod destructuring_array_token;
+pub mod destructuring_record_token;
 pub mod expresion_token;
+pub mod function_token;
 pub mod inmediate_relation_token;
 pub mod line_token;
 pub mod list_token;
+pub mod matches_token;
+pub mod schema_token;
 pub mod statement_token;
+pub mod transaction_token;
 pub mod update_token;
 
 pub mod error;
 
+use crate::diagnostic::{self, Diagnostic, Severity, Span};
 use crate::engine::RelId;
-use crate::lexer;
+use crate::lexer::{self, LexogramType};
 use crate::parser::error::FailureExplanation;
 
 use self::error::ParserError;
@@ -23,24 +31,180 @@ pub trait HasRelId {
     fn get_rel_id(&self) -> RelId;
 }
 
-pub fn parse(lexograms: &Vec<lexer::Lexogram>) -> Result<Vec<Line>, ParserError> {
+// the lexograms a line can start with, after a mistake the parser picks up again at the first of
+// them that opens a source line
+fn starts_a_line(lex: &LexogramType) -> bool {
+    matches!(
+        lex,
+        LexogramType::Identifier(_) | LexogramType::OpNot | LexogramType::LeftKey
+    )
+}
+
+// a line with the bytes of the source it was read from and the comments that go with it
+#[derive(Debug, Clone)]
+pub struct SourceLine {
+    pub line: Line,
+    pub span: Span,
+    // the comments right above it, with no blank line in between
+    pub leading_comments: Vec<Comment>,
+    // the comments from its start to the end of the source line it ends on that no node of it holds
+    pub trailing_comments: Vec<Comment>,
+}
+
+impl From<(Line, Span)> for SourceLine {
+    fn from(value: (Line, Span)) -> Self {
+        let (line, span) = value;
+        Self {
+            line,
+            span,
+            leading_comments: vec![],
+            trailing_comments: vec![],
+        }
+    }
+}
+
+pub fn parse(
+    lexograms: &Vec<lexer::Lexogram>,
+    source: &str,
+) -> Result<Vec<SourceLine>, ParserError> {
+    let (lines, diagnostics) = parse_recovering(lexograms, source)?;
+    if diagnostics.is_empty() {
+        Ok(lines)
+    } else {
+        Err(ParserError::SyntaxErrors(diagnostics))
+    }
+}
+
+// the lines that could be read along with every mistake found, after one the parser skips to the
+// next source line a line can start on
+pub fn parse_recovering(
+    lexograms: &Vec<lexer::Lexogram>,
+    source: &str,
+) -> Result<(Vec<SourceLine>, Vec<Diagnostic>), ParserError> {
+    // comments can go anywhere, so they are put apart before reading the lines
+    let mut comments = vec![];
+    let mut code = vec![];
+    for lex in lexograms {
+        match &lex.l_type {
+            LexogramType::Comment(text) => comments.push(Comment {
+                text: text.to_owned(),
+                span: Span {
+                    start: lex.pos_s,
+                    end: lex.pos_f,
+                },
+            }),
+            _ => code.push(lex.to_owned()),
+        }
+    }
+    let lexograms = &code;
+
     let mut ret = vec![];
+    let mut diagnostics = vec![];
     let mut cursor = 0;
 
+    let mut source_line = 0;
+    let mut last_pos = 0;
+    let mut source_lines = vec![];
+    for lex in lexograms {
+        match source.get(last_pos..lex.pos_s) {
+            Some(between) => source_line += between.matches('\n').count(),
+            None => {
+                return Err(ParserError::SyntaxErrors(vec![Diagnostic {
+                    span: Some(Span {
+                        start: lex.pos_s,
+                        end: lex.pos_f,
+                    }),
+                    ..Diagnostic::new(
+                        Severity::Error,
+                        diagnostic::LEXER_ERROR,
+                        "a lexogram doesnt start where a char of the source does".into(),
+                    )
+                }]))
+            }
+        }
+        last_pos = lex.pos_s;
+        source_lines.push(source_line);
+    }
+
     for (i, _) in lexograms.iter().enumerate() {
         if cursor > i {
             continue;
         }
-        match read_line(&lexograms, i)? {
+        match read_line(lexograms, i)? {
             Ok((statement, jump_to)) => {
-                ret.push(statement);
+                let span = Span {
+                    start: lexograms[i].pos_s,
+                    end: lexograms[jump_to - 1].pos_f,
+                };
+                ret.push((statement, span));
                 cursor = jump_to;
             }
             Err(e) => {
-                return Err(ParserError::SyntaxError(e));
+                diagnostics.push(e.to_diagnostic(lexograms));
+                cursor = (e.get_furthest_pos().max(i) + 1..lexograms.len())
+                    .find(|&j| {
+                        source_lines[j] > source_lines[j - 1] && starts_a_line(&lexograms[j].l_type)
+                    })
+                    .unwrap_or(lexograms.len());
             }
         }
     }
 
-    Ok(ret)
+    Ok((attach_comments(ret, comments, source), diagnostics))
+}
+
+// a comment goes after the line it shares a source line with, else above the line under it when
+// there is no blank line in between, else it stands as a line on its own
+fn attach_comments(
+    lines: Vec<(Line, Span)>,
+    comments: Vec<Comment>,
+    source: &str,
+) -> Vec<SourceLine> {
+    let mut ret: Vec<SourceLine> = lines.into_iter().map(SourceLine::from).collect();
+    let mut standalone = vec![];
+    // going upwards, the start of the last comment left above a line and that line
+    let mut last_leading: Option<(usize, usize)> = None;
+    for comment in comments.into_iter().rev() {
+        if let Some(i) = ret
+            .iter()
+            .rposition(|line| line.span.start <= comment.span.start)
+        {
+            let end = ret[i].span.end;
+            let between = source.get(end..comment.span.start).unwrap_or_default();
+            if comment.span.start < end {
+                if let Err(comment) = ret[i].line.attach_comment(comment) {
+                    ret[i].trailing_comments.insert(0, comment);
+                }
+                continue;
+            }
+            if !between.contains('\n') {
+                ret[i].trailing_comments.insert(0, comment);
+                continue;
+            }
+        }
+        let below = ret
+            .iter()
+            .position(|line| line.span.start >= comment.span.end);
+        let leading = below.filter(|&j| {
+            let target = match last_leading {
+                Some((start, line)) if line == j => start,
+                _ => ret[j].span.start,
+            };
+            let gap = source.get(comment.span.end..target).unwrap_or_default();
+            gap.trim().is_empty() && gap.matches('\n').count() <= 1
+        });
+        match leading {
+            Some(j) => {
+                last_leading = Some((comment.span.start, j));
+                ret[j].leading_comments.insert(0, comment);
+            }
+            None => standalone.push(SourceLine::from((
+                Line::Comment(comment.to_owned()),
+                comment.span,
+            ))),
+        }
+    }
+    ret.extend(standalone);
+    ret.sort_by_key(|line| line.span.start);
+    ret
 }
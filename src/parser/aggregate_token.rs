@@ -0,0 +1,301 @@
+use std::collections::BTreeSet;
+use std::fmt;
+use std::hash::{Hash, Hasher};
+
+use print_macros::*;
+
+use crate::diagnostic::Span;
+use crate::engine::operations::{add_direct, divide_direct};
+use crate::engine::recursion_tally::RecursionTally;
+use crate::engine::var_context::VarContext;
+use crate::engine::var_context_universe::VarContextUniverse;
+use crate::engine::{DeductionError, Engine};
+use crate::lexer::{self, LexogramType::*};
+
+use super::data_token::Data;
+use super::error::{FailureExplanation, FailureReason, ParserError};
+use super::expresion_token::{read_expresion, Expresion};
+use super::statement_token::{read_statement, Statement};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum AggregateFunction {
+    Count,
+    Sum,
+    Min,
+    Max,
+    Avg,
+}
+
+impl AggregateFunction {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "count" => Some(AggregateFunction::Count),
+            "sum" => Some(AggregateFunction::Sum),
+            "min" => Some(AggregateFunction::Min),
+            "max" => Some(AggregateFunction::Max),
+            "avg" => Some(AggregateFunction::Avg),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for AggregateFunction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AggregateFunction::Count => write!(f, "count"),
+            AggregateFunction::Sum => write!(f, "sum"),
+            AggregateFunction::Min => write!(f, "min"),
+            AggregateFunction::Max => write!(f, "max"),
+            AggregateFunction::Avg => write!(f, "avg"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Eq)]
+pub struct Aggregate {
+    pub function: AggregateFunction,
+    pub target: Box<Expresion>,
+    pub condition: Box<Statement>,
+    // the variables shared with the rest of the rule, known once the whole rule is parsed
+    pub group_by: Vec<String>,
+    // where it was read from
+    pub span: Option<Span>,
+}
+
+impl PartialEq for Aggregate {
+    fn eq(&self, other: &Self) -> bool {
+        self.function == other.function
+            && self.target == other.target
+            && self.condition == other.condition
+            && self.group_by == other.group_by
+    }
+}
+
+impl Hash for Aggregate {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.function.hash(state);
+        self.target.hash(state);
+        self.condition.hash(state);
+        self.group_by.hash(state);
+    }
+}
+
+impl fmt::Display for Aggregate {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}({} : {})", self.function, self.target, self.condition)
+    }
+}
+
+impl Aggregate {
+    pub fn get_vars(&self) -> BTreeSet<String> {
+        let mut ret = self.target.get_vars();
+        ret.extend(self.condition.get_vars());
+        ret
+    }
+
+    pub fn scope(&self, outer_vars: &BTreeSet<String>) -> Aggregate {
+        let vars = self.get_vars();
+
+        let mut nested_outer_vars = outer_vars.to_owned();
+        nested_outer_vars.extend(vars.iter().cloned());
+
+        Aggregate {
+            function: self.function,
+            target: self.target.to_owned(),
+            condition: Box::new(self.condition.scope_aggregates(&nested_outer_vars)),
+            group_by: vars.intersection(outer_vars).cloned().collect(),
+            span: self.span,
+        }
+    }
+
+    // min, max and avg have no value over an empty group, so the context finds no match
+    pub fn evaluate(
+        &self,
+        engine: &Engine,
+        recursion_tally: &RecursionTally,
+        context: &VarContext,
+    ) -> Result<Option<Data>, DeductionError> {
+        printprocess!("aggregate {} on context {}", self, context);
+
+        let mut group_context = VarContext::new();
+        for var in &self.group_by {
+            match context.get(var) {
+                Some(Data::Any) | None => {
+                    return Err(format!("cant aggregate {self} while {var} is unbound").into())
+                }
+                Some(value) => group_context.set(var.to_owned(), value),
+            }
+        }
+
+        let mut universe = VarContextUniverse::new();
+        universe.insert(group_context);
+
+        // just like negations, the aggregated relations must be complete before being folded
+        let contexts = self.condition.to_owned().memo_get_posible_contexts(
+            &engine.get_lower_stratum(),
+            &recursion_tally.restart(),
+            &universe,
+        )?;
+
+        let mut values = vec![];
+        for context in contexts.iter() {
+            match (self.function, self.target.literalize(&context)?) {
+                (AggregateFunction::Count, value) => values.push(value),
+                (_, Data::Any) => return Err(format!("cant aggregate {self} over _").into()),
+                (_, value) => values.push(value),
+            }
+        }
+
+        let sum = |values: &Vec<Data>| {
+            let mut ret = Data::Integer(0);
+            for value in values {
+                if !value.is_number() {
+                    return Err(format!("cant add up the non numeric {value} on {self}"));
+                }
+                ret = add_direct(ret, value.to_owned())?;
+            }
+            Ok(ret)
+        };
+
+        Ok(match self.function {
+            AggregateFunction::Count => Some(Data::Integer(values.len() as i64)),
+            AggregateFunction::Sum => Some(sum(&values)?),
+            AggregateFunction::Avg if values.is_empty() => None,
+            AggregateFunction::Avg => Some(divide_direct(
+                sum(&values)?,
+                Data::Integer(values.len() as i64),
+            )?),
+            AggregateFunction::Min => values.into_iter().min(),
+            AggregateFunction::Max => values.into_iter().max(),
+        })
+    }
+}
+
+pub fn read_aggregate(
+    lexograms: &Vec<lexer::Lexogram>,
+    start_cursor: usize,
+) -> Result<Result<(Aggregate, usize), FailureExplanation>, ParserError> {
+    #[derive(Debug, Clone, Copy)]
+    enum AggregateParserStates {
+        SpectingFunction,
+        SpectingOpenParenthesis,
+        SpectingTarget,
+        SpectingColon,
+        SpectingCondition,
+        SpectingClosingParenthesis,
+    }
+    impl AggregateParserStates {
+        fn expected(&self) -> Vec<&'static str> {
+            match self {
+                SpectingFunction => vec!["an aggregate function"],
+                SpectingOpenParenthesis => vec!["`(`"],
+                SpectingTarget => vec!["an expresion"],
+                SpectingColon => vec!["`:`"],
+                SpectingCondition => vec!["a statement"],
+                SpectingClosingParenthesis => vec!["`)`"],
+            }
+        }
+    }
+    use AggregateParserStates::*;
+
+    printparse!("read_aggregate at {}", start_cursor);
+
+    let mut cursor = start_cursor;
+    let mut state = SpectingFunction;
+
+    let mut op_function = None;
+    let mut op_target = None;
+    let mut op_condition = None;
+
+    for (i, lex) in lexograms.iter().enumerate() {
+        if cursor > i {
+            continue;
+        }
+        match (lex.l_type.to_owned(), state) {
+            (Identifier(name), SpectingFunction) => match AggregateFunction::from_name(&name) {
+                Some(function) => {
+                    op_function = Some(function);
+                    state = SpectingOpenParenthesis;
+                }
+                None => {
+                    return Ok(Err(FailureExplanation {
+                        lex_pos: i,
+                        if_it_was: "aggregate".into(),
+                        failed_because: FailureReason::Other(format!(
+                            "{name} is not an aggregate function"
+                        )),
+                        parent_failure: vec![],
+                    }))
+                }
+            },
+            (LeftParenthesis, SpectingOpenParenthesis) => state = SpectingTarget,
+            (_, SpectingTarget) => match read_expresion(lexograms, i, false)? {
+                Ok((target, jump_to)) => {
+                    cursor = jump_to;
+                    op_target = Some(target);
+                    state = SpectingColon;
+                }
+                Err(e) => {
+                    return Ok(Err(FailureExplanation {
+                        lex_pos: i,
+                        if_it_was: "aggregate".into(),
+                        failed_because: FailureReason::Other(
+                            "specting aggregated expresion".into(),
+                        ),
+                        parent_failure: vec![e],
+                    }))
+                }
+            },
+            (Colon, SpectingColon) => state = SpectingCondition,
+            (_, SpectingCondition) => match read_statement(lexograms, i)? {
+                Ok((condition, jump_to)) => {
+                    cursor = jump_to;
+                    op_condition = Some(condition.name_anonymous_vars(&mut 0));
+                    state = SpectingClosingParenthesis;
+                }
+                Err(e) => {
+                    return Ok(Err(FailureExplanation {
+                        lex_pos: i,
+                        if_it_was: "aggregate".into(),
+                        failed_because: FailureReason::Other("specting aggregate condition".into()),
+                        parent_failure: vec![e],
+                    }))
+                }
+            },
+            (RightParenthesis, SpectingClosingParenthesis) => {
+                match (op_function, op_target, op_condition) {
+                    (Some(function), Some(target), Some(condition)) => {
+                        return Ok(Ok((
+                            Aggregate {
+                                function,
+                                target: Box::new(target),
+                                condition: Box::new(condition),
+                                group_by: vec![],
+                                span: Some(Span {
+                                    start: lexograms[start_cursor].pos_s,
+                                    end: lex.pos_f,
+                                }),
+                            },
+                            i + 1,
+                        )))
+                    }
+                    _ => unreachable!(),
+                }
+            }
+            _ => {
+                return Ok(Err(FailureExplanation {
+                    lex_pos: i,
+                    if_it_was: "aggregate".into(),
+                    failed_because: FailureReason::Specting(state.expected()),
+                    parent_failure: vec![],
+                }))
+            }
+        }
+    }
+    Ok(Err(FailureExplanation {
+        lex_pos: lexograms.len() - 1,
+        if_it_was: "aggregate".into(),
+        failed_because: FailureReason::FileEnded,
+        parent_failure: vec![],
+    }))
+}
@@ -3,8 +3,9 @@ use core::fmt;
 use super::{
     conditional_token::Conditional,
     defered_relation_token::{read_defered_relation, DeferedRelation},
-    error::{FailureExplanation, ParserError},
+    error::{FailureExplanation, FailureReason, ParserError},
     inmediate_relation_token::{read_inmediate_relation, InmediateRelation},
+    schema_token::{read_schema, Schema},
     update_token::{read_update, Update},
 };
 use crate::{
@@ -16,8 +17,9 @@ use crate::{
 pub enum Assumption {
     RelationInmediate(InmediateRelation),
     RelationDefered(DeferedRelation),
-    Conditional(Conditional),
+    Conditional(Box<Conditional>),
     Update(Update),
+    Schema(Schema),
 }
 
 impl fmt::Display for Assumption {
@@ -27,6 +29,7 @@ impl fmt::Display for Assumption {
             Assumption::RelationDefered(rel) => write!(f, "{rel}"),
             Assumption::Conditional(cond) => write!(f, "{cond}"),
             Assumption::Update(upd) => write!(f, "{upd}"),
+            Assumption::Schema(schema) => write!(f, "{schema}"),
         }
     }
 }
@@ -39,19 +42,26 @@ pub fn read_assumption(
     let b;
     let c;
     let d;
+    let e;
+    match read_schema(lexograms, start_cursor)? {
+        Ok((ret, jump_to)) => return Ok(Ok((Assumption::Schema(ret), jump_to))),
+        Err(err) => e = err,
+    }
+    match read_update(lexograms, start_cursor)? {
+        Ok((ret, jump_to)) => return Ok(Ok((Assumption::Update(ret), jump_to))),
+        Err(e) => c = e,
+    }
     match read_inmediate_relation(lexograms, start_cursor)? {
         Ok((i_rel, jump_to)) => return Ok(Ok((Assumption::RelationInmediate(i_rel), jump_to))),
         Err(e) => a = e,
     }
     match read_conditional(lexograms, start_cursor)? {
-        Ok((ret, jump_to)) => return Ok(Ok((Assumption::Conditional(ret), jump_to))),
+        Ok((ret, jump_to)) => return Ok(Ok((Assumption::Conditional(Box::new(ret)), jump_to))),
         Err(e) => b = e,
     }
-    match read_update(lexograms, start_cursor)? {
-        Ok((ret, jump_to)) => return Ok(Ok((Assumption::Update(ret), jump_to))),
-        Err(e) => c = e,
-    }
     match read_defered_relation(lexograms, start_cursor, false)? {
+        // a rule broken past its head is still a rule, not a relation followed by garbage
+        Ok((_, jump_to)) if b.get_furthest_pos() > jump_to => return Ok(Err(b)),
         Ok((d_rel, jump_to)) => return Ok(Ok((Assumption::RelationDefered(d_rel), jump_to))),
         Err(e) => d = e,
     }
@@ -59,7 +69,7 @@ pub fn read_assumption(
     Ok(Err(FailureExplanation {
         lex_pos: start_cursor,
         if_it_was: "assumption".into(),
-        failed_because: "wasnt any type of assumption".into(),
-        parent_failure: vec![a, b, c, d],
+        failed_because: FailureReason::Other("wasnt any type of assumption".into()),
+        parent_failure: vec![a, b, c, d, e],
     }))
 }
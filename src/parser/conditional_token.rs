@@ -2,12 +2,16 @@ use std::fmt;
 
 use print_macros::*;
 
+use crate::diagnostic::Span;
 use crate::engine::RelId;
 use crate::lexer::LexogramType::*;
 use crate::parser::statement_token::read_statement;
 use crate::{
     lexer,
-    parser::{defered_relation_token::read_defered_relation, error::FailureExplanation},
+    parser::{
+        defered_relation_token::read_defered_relation,
+        error::{FailureExplanation, FailureReason},
+    },
 };
 
 use super::defered_relation_token::DeferedRelation;
@@ -27,6 +31,19 @@ impl fmt::Display for Conditional {
     }
 }
 
+impl Conditional {
+    // from the head to the end of the body
+    pub fn get_span(&self) -> Option<Span> {
+        match (self.relation.span, self.conditional.get_span()) {
+            (Some(head), Some(body)) => Some(Span {
+                start: head.start,
+                end: body.end,
+            }),
+            _ => None,
+        }
+    }
+}
+
 impl HasRelId for Conditional {
     fn get_rel_id(&self) -> RelId {
         self.relation.get_rel_id()
@@ -43,6 +60,15 @@ pub fn read_conditional(
         SpectingTrueWhen,
         SpectingCondition,
     }
+    impl IntensionalParserStates {
+        fn expected(&self) -> Vec<&'static str> {
+            match self {
+                SpectingDeferedRelation => vec!["a relation"],
+                SpectingTrueWhen => vec!["`:-`"],
+                SpectingCondition => vec!["a statement"],
+            }
+        }
+    }
     use IntensionalParserStates::*;
 
     printparse!("read_intensional at {}", start_cursor);
@@ -61,7 +87,7 @@ pub fn read_conditional(
                     return Ok(Err(FailureExplanation {
                         lex_pos: i,
                         if_it_was: "conditional".into(),
-                        failed_because: "specting relation".into(),
+                        failed_because: FailureReason::Other("specting relation".into()),
                         parent_failure: (vec![e]),
                     }))
                 }
@@ -77,31 +103,30 @@ pub fn read_conditional(
                     return Ok(Err(FailureExplanation {
                         lex_pos: i,
                         if_it_was: "conditional".into(),
-                        failed_because: "specting statement".into(),
+                        failed_because: FailureReason::Other("specting statement".into()),
                         parent_failure: (vec![e]),
                     }))
                 }
                 (Ok((cond, jump_to)), Some(def_rel)) => {
+                    let mut outer_vars = def_rel.get_vars();
+                    outer_vars.extend(cond.get_vars());
+
                     return Ok(Ok((
                         Conditional {
+                            conditional: cond.scope_aggregates(&outer_vars),
                             relation: def_rel,
-                            conditional: cond,
                         },
                         jump_to,
-                    )))
+                    )));
                 }
                 _ => unreachable!(),
             },
 
-            (lex, _) => {
+            _ => {
                 return Ok(Err(FailureExplanation {
                     lex_pos: i,
                     if_it_was: "conditional".into(),
-                    failed_because: format!(
-                        "pattern missmatch on {:#?} state reading {lex:?}",
-                        state
-                    )
-                    .into(),
+                    failed_because: FailureReason::Specting(state.expected()),
                     parent_failure: vec![],
                 }))
             }
@@ -110,7 +135,7 @@ pub fn read_conditional(
     Ok(Err(FailureExplanation {
         lex_pos: lexograms.len() - 1,
         if_it_was: "conditional".into(),
-        failed_because: "file ended".into(),
+        failed_because: FailureReason::FileEnded,
         parent_failure: vec![],
     }))
 }
@@ -1,28 +1,40 @@
-use std::{fmt, hash};
+use std::{collections::BTreeMap, fmt, hash};
 
 use print_macros::*;
 
+use super::decimal::Decimal;
 use super::error::ParserError;
 use crate::engine::var_context::VarContext;
 use crate::lexer::{self, LexogramType::*};
-use crate::parser::error::FailureExplanation;
+use crate::parser::error::{FailureExplanation, FailureReason};
 use crate::parser::expresion_token::read_expresion;
 
-#[derive(Clone, Debug, PartialOrd)]
+pub type RecordFields = BTreeMap<String, Data>;
+
+#[derive(Clone, Debug)]
 pub enum Data {
+    Integer(i64),
+    Decimal(Decimal),
+    // floats are approximate, they come from exponents on literals and operations on other floats
     Number(f64),
     String(String),
     Array(Vec<Data>),
+    Record(RecordFields),
+    Bool(bool),
+    // an explicit missing value, unlike _ it is a value of its own and only equals itself
+    Null,
     Any,
 }
 
 impl PartialEq for Data {
     fn eq(&self, other: &Self) -> bool {
         match (self, other) {
-            (Self::Number(l0), Self::Number(r0)) => l0 == r0,
             (Self::String(l0), Self::String(r0)) => l0 == r0,
             (Self::Array(l0), Self::Array(r0)) => l0 == r0,
-            _ => false,
+            (Self::Record(l0), Self::Record(r0)) => l0 == r0,
+            (Self::Bool(l0), Self::Bool(r0)) => l0 == r0,
+            (Self::Null, Self::Null) => true,
+            _ => self.cmp_numbers(other) == Some(std::cmp::Ordering::Equal),
         }
     }
 }
@@ -30,6 +42,10 @@ impl PartialEq for Data {
 impl fmt::Display for Data {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
+            Data::Integer(x) => write!(f, "{x}"),
+            Data::Decimal(x) => write!(f, "{x}"),
+            // a float always shows a fraction, so 2.0 isnt read back as an integer
+            Data::Number(x) if x.is_finite() && x.fract() == 0.0 => write!(f, "{x:.1}"),
             Data::Number(x) => write!(f, "{x}"),
             Data::String(x) => write!(f, "{x}"),
             Data::Array(x) => {
@@ -45,6 +61,16 @@ impl fmt::Display for Data {
 
                 write!(f, "{arr}")
             }
+            Data::Record(fields) => {
+                let fields = fields
+                    .iter()
+                    .map(|(key, value)| format!("{}: {value}", key_to_string(key)))
+                    .collect::<Vec<String>>()
+                    .join(", ");
+                write!(f, "{{{fields}}}")
+            }
+            Data::Bool(x) => write!(f, "{x}"),
+            Data::Null => write!(f, "null"),
             Data::Any => write!(f, "_"),
         }
     }
@@ -55,42 +81,40 @@ impl Eq for Data {}
 impl Ord for Data {
     fn cmp(&self, other: &Self) -> std::cmp::Ordering {
         match (self, other) {
-            (Data::Number(x), Data::Number(y)) => {
-                if x == y {
-                    std::cmp::Ordering::Equal
-                } else if x < y {
-                    std::cmp::Ordering::Less
-                } else {
-                    std::cmp::Ordering::Greater
-                }
-            }
-            (Data::Number(_), Data::String(_)) => std::cmp::Ordering::Less,
-            (Data::Number(_), Data::Array(_)) => std::cmp::Ordering::Less,
-            (Data::String(_), Data::Number(_)) => std::cmp::Ordering::Greater,
             (Data::String(x), Data::String(y)) => x.cmp(y),
-            (Data::String(_), Data::Array(_)) => std::cmp::Ordering::Less,
-            (Data::Array(_), Data::Number(_)) => std::cmp::Ordering::Greater,
-            (Data::Array(_), Data::String(_)) => std::cmp::Ordering::Greater,
             (Data::Array(x), Data::Array(y)) => x.cmp(y),
-            (Data::Number(_), Data::Any) => std::cmp::Ordering::Less,
-            (Data::String(_), Data::Any) => std::cmp::Ordering::Less,
-            (Data::Array(_), Data::Any) => std::cmp::Ordering::Less,
-            (Data::Any, Data::Number(_)) => std::cmp::Ordering::Greater,
-            (Data::Any, Data::String(_)) => std::cmp::Ordering::Greater,
-            (Data::Any, Data::Array(_)) => std::cmp::Ordering::Greater,
+            (Data::Record(x), Data::Record(y)) => x.cmp(y),
+            (Data::Bool(x), Data::Bool(y)) => x.cmp(y),
+            (Data::Null, Data::Null) => std::cmp::Ordering::Equal,
             (Data::Any, Data::Any) => std::cmp::Ordering::Greater,
+            _ => match self.cmp_numbers(other) {
+                Some(ordering) => ordering,
+                // NaN
+                None if self.is_number() && other.is_number() => std::cmp::Ordering::Greater,
+                None => self.get_kind_order().cmp(&other.get_kind_order()),
+            },
         }
     }
 }
 
+impl PartialOrd for Data {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
 impl hash::Hash for Data {
     fn hash<H>(&self, state: &mut H)
     where
         H: hash::Hasher,
     {
         match self {
-            Data::Number(n) => {
-                if n.is_finite() {
+            // equal numbers of any kind round to the same float, so they hash alike
+            Data::Integer(_) | Data::Decimal(_) | Data::Number(_) => {
+                let n = self.to_f64().unwrap_or(f64::NAN);
+                if n == 0.0 {
+                    0f64.to_bits().hash(state)
+                } else if n.is_finite() {
                     n.to_bits().hash(state)
                 } else if n.is_infinite() {
                     f64::INFINITY.to_bits().hash(state)
@@ -100,16 +124,98 @@ impl hash::Hash for Data {
             }
             Data::String(str) => str.hash(state),
             Data::Array(array) => array.hash(state),
+            Data::Record(fields) => fields.hash(state),
+            Data::Bool(x) => x.hash(state),
+            Data::Null => "null".hash(state),
             Data::Any => "_".hash(state),
         }
     }
 }
 
 impl Data {
+    // values of different kinds sort as numbers, strings, arrays, records, bools, nulls and then _
+    fn get_kind_order(&self) -> usize {
+        match self {
+            Data::Integer(_) | Data::Decimal(_) | Data::Number(_) => 0,
+            Data::String(_) => 1,
+            Data::Array(_) => 2,
+            Data::Record(_) => 3,
+            Data::Bool(_) => 4,
+            Data::Null => 5,
+            Data::Any => 6,
+        }
+    }
+
+    // the order <, >, <= and >= go by: numbers by value, strings by their unicode code points,
+    // arrays item by item with a prefix before the longer array and false before true. Any other
+    // pair, nulls and records included, cant be ordered and every comparison on them is false
+    pub fn compare(&self, other: &Data) -> Option<std::cmp::Ordering> {
+        match (self, other) {
+            (Data::String(x), Data::String(y)) => Some(x.cmp(y)),
+            (Data::Array(x), Data::Array(y)) => {
+                for (item_x, item_y) in x.iter().zip(y) {
+                    match item_x.compare(item_y)? {
+                        std::cmp::Ordering::Equal => (),
+                        ordering => return Some(ordering),
+                    }
+                }
+                Some(x.len().cmp(&y.len()))
+            }
+            (Data::Bool(x), Data::Bool(y)) => Some(x.cmp(y)),
+            _ => self.cmp_numbers(other),
+        }
+    }
+
+    pub fn is_number(&self) -> bool {
+        matches!(self, Data::Integer(_) | Data::Decimal(_) | Data::Number(_))
+    }
+
+    pub fn to_f64(&self) -> Option<f64> {
+        match self {
+            Data::Integer(x) => Some(*x as f64),
+            Data::Decimal(x) => Some(x.to_f64()),
+            Data::Number(x) => Some(*x),
+            _ => None,
+        }
+    }
+
+    pub fn to_decimal(&self) -> Option<Decimal> {
+        match self {
+            Data::Integer(x) => Some(Decimal::from_integer(*x)),
+            Data::Decimal(x) => Some(*x),
+            _ => None,
+        }
+    }
+
+    // numbers of different kinds compare by value, exactly unless a float is involved
+    pub fn cmp_numbers(&self, other: &Data) -> Option<std::cmp::Ordering> {
+        match (self, other) {
+            (Data::Integer(x), Data::Integer(y)) => Some(x.cmp(y)),
+            (Data::Number(x), Data::Number(y)) => x.partial_cmp(y),
+            (Data::Number(x), exact) | (exact, Data::Number(x)) if exact.is_number() => {
+                let exact_f64 = exact.to_f64()?;
+                // a float holding a whole number is compared exactly against a whole number
+                let ordering = match (exact, exact.to_decimal()?.to_integer()) {
+                    (_, Some(whole)) if x.fract() == 0.0 && x.abs() < i64::MAX as f64 => {
+                        Some(whole.cmp(&(*x as i64)))
+                    }
+                    _ => exact_f64.partial_cmp(x),
+                }?;
+                match self {
+                    Data::Number(_) => Some(ordering.reverse()),
+                    _ => Some(ordering),
+                }
+            }
+            _ => Some(self.to_decimal()?.cmp(&other.to_decimal()?)),
+        }
+    }
+
     pub fn to_string(&self) -> String {
         match self {
-            Data::Number(n) => format!("{n}").into(),
-            Data::String(s) => format!("\"{s}\"").into(),
+            Data::Integer(n) => format!("{n}"),
+            Data::Decimal(n) => format!("{n}"),
+            Data::Number(_) => format!("{self}"),
+            Data::String(s) => format!("\"{}\"", s.replace('\\', "\\\\").replace('"', "\\\"")),
             Data::Array(arr) => {
                 "[".to_string()
                     + &arr
@@ -119,11 +225,33 @@ impl Data {
                         .join(",")
                     + &"]".to_string()
             }
+            Data::Record(fields) => {
+                "{".to_string()
+                    + &fields
+                        .iter()
+                        .map(|(key, value)| {
+                            format!("{}: {}", key_to_string(key), value.to_string())
+                        })
+                        .collect::<Vec<String>>()
+                        .join(", ")
+                    + "}"
+            }
+            Data::Bool(x) => format!("{x}"),
+            Data::Null => "null".into(),
             Data::Any => "_".into(),
         }
     }
 }
 
+// keys that would not lex back as an identifier are written quoted
+pub fn key_to_string(key: &str) -> String {
+    if lexer::is_identifier(key) {
+        key.to_string()
+    } else {
+        Data::String(key.to_string()).to_string()
+    }
+}
+
 pub fn read_data(
     lexograms: &Vec<lexer::Lexogram>,
     start_cursor: usize,
@@ -131,14 +259,28 @@ pub fn read_data(
     printparse!("read_data at {}", start_cursor);
 
     match lexograms[start_cursor].l_type.clone() {
+        Integer(n) => Ok(Ok((Data::Integer(n), start_cursor + 1))),
+        lexer::LexogramType::Decimal(n) => Ok(Ok((Data::Decimal(n), start_cursor + 1))),
         Number(n) => Ok(Ok((Data::Number(n), start_cursor + 1))),
         Word(n) => Ok(Ok((Data::String(n), start_cursor + 1))),
+        True => Ok(Ok((Data::Bool(true), start_cursor + 1))),
+        False => Ok(Ok((Data::Bool(false), start_cursor + 1))),
+        Null => Ok(Ok((Data::Null, start_cursor + 1))),
         LeftBracket => match read_data_array(lexograms, start_cursor)? {
             Ok((ret, jump_to)) => Ok(Ok((Data::Array(ret), jump_to))),
             Err(explanation) => Ok(Err(FailureExplanation {
                 lex_pos: start_cursor,
                 if_it_was: "data".into(),
-                failed_because: "was not an array".into(),
+                failed_because: FailureReason::Other("was not an array".into()),
+                parent_failure: (vec![explanation]),
+            })),
+        },
+        LeftKey => match read_data_record(lexograms, start_cursor)? {
+            Ok((ret, jump_to)) => Ok(Ok((Data::Record(ret), jump_to))),
+            Err(explanation) => Ok(Err(FailureExplanation {
+                lex_pos: start_cursor,
+                if_it_was: "data".into(),
+                failed_because: FailureReason::Other("was not a record".into()),
                 parent_failure: (vec![explanation]),
             })),
         },
@@ -147,7 +289,7 @@ pub fn read_data(
         _ => Ok(Err(FailureExplanation {
             lex_pos: start_cursor,
             if_it_was: "data".into(),
-            failed_because: "pattern missmatch trying to read item".into(),
+            failed_because: FailureReason::Specting(vec!["a literal"]),
             parent_failure: vec![],
         })),
     }
@@ -164,6 +306,16 @@ pub fn read_data_array(
         SpectingComaOrEnd,
         SpectingStart,
     }
+    impl ArrayParserStates {
+        fn expected(&self) -> Vec<&'static str> {
+            match self {
+                SpectingItemOrEnd => vec!["a literal", "`]`"],
+                SpectingItem => vec!["a literal"],
+                SpectingComaOrEnd => vec!["`,`", "`]`"],
+                SpectingStart => vec!["`[`"],
+            }
+        }
+    }
     use ArrayParserStates::*;
 
     printparse!("read_data_array at {}", start_cursor);
@@ -193,7 +345,7 @@ pub fn read_data_array(
                         return Ok(Err(FailureExplanation {
                             lex_pos: i,
                             if_it_was: "data_array".into(),
-                            failed_because: "specting item".into(),
+                            failed_because: FailureReason::Other("specting item".into()),
                             parent_failure: vec![e],
                         }))
                     }
@@ -204,8 +356,9 @@ pub fn read_data_array(
                                 return Ok(Err(FailureExplanation {
                                     lex_pos: i,
                                     if_it_was: "data_array".into(),
-                                    failed_because: format!("unliteralizable expresion: {err}")
-                                        .into(),
+                                    failed_because: FailureReason::Other(
+                                        format!("unliteralizable expresion: {err}").into(),
+                                    ),
                                     parent_failure: vec![],
                                 }))
                             }
@@ -220,7 +373,7 @@ pub fn read_data_array(
                 return Ok(Err(FailureExplanation {
                     lex_pos: i,
                     if_it_was: "data_array".into(),
-                    failed_because: format!("pattern missmatch on {:#?} state", state).into(),
+                    failed_because: FailureReason::Specting(state.expected()),
                     parent_failure: vec![],
                 }))
             }
@@ -229,7 +382,109 @@ pub fn read_data_array(
     Ok(Err(FailureExplanation {
         lex_pos: lexograms.len() - 1,
         if_it_was: "data_array".into(),
-        failed_because: "file ended".into(),
+        failed_because: FailureReason::FileEnded,
+        parent_failure: vec![],
+    }))
+}
+
+pub fn read_data_record(
+    lexograms: &Vec<lexer::Lexogram>,
+    start_cursor: usize,
+) -> Result<Result<(RecordFields, usize), FailureExplanation>, ParserError> {
+    #[derive(Debug, Clone, Copy)]
+    enum RecordParserStates {
+        SpectingStart,
+        SpectingKeyOrEnd,
+        SpectingKey,
+        SpectingColon,
+        SpectingValue,
+        SpectingComaOrEnd,
+    }
+    impl RecordParserStates {
+        fn expected(&self) -> Vec<&'static str> {
+            match self {
+                SpectingStart => vec!["`{`"],
+                SpectingKeyOrEnd => vec!["a key", "`}`"],
+                SpectingKey => vec!["a key"],
+                SpectingColon => vec!["`:`"],
+                SpectingValue => vec!["a literal"],
+                SpectingComaOrEnd => vec!["`,`", "`}`"],
+            }
+        }
+    }
+    use RecordParserStates::*;
+
+    printparse!("read_data_record at {}", start_cursor);
+
+    let mut cursor = start_cursor;
+
+    let mut ret = BTreeMap::new();
+    let mut op_key = None;
+    let mut state = SpectingStart;
+
+    for (i, lex) in lexograms.iter().enumerate() {
+        if cursor > i {
+            continue;
+        }
+        match (lex.l_type.to_owned(), state) {
+            (LeftKey, SpectingStart) => state = SpectingKeyOrEnd,
+            (Identifier(key) | Word(key), SpectingKeyOrEnd | SpectingKey) => {
+                if ret.contains_key(&key) {
+                    return Ok(Err(FailureExplanation {
+                        lex_pos: i,
+                        if_it_was: "data_record".into(),
+                        failed_because: FailureReason::Other(format!("the key {key} is repeated")),
+                        parent_failure: vec![],
+                    }));
+                }
+                op_key = Some(key);
+                state = SpectingColon;
+            }
+            (Colon, SpectingColon) => state = SpectingValue,
+            (_, SpectingValue) => match (read_expresion(lexograms, i, true)?, &op_key) {
+                (Ok((expresion, jump_to)), Some(key)) => {
+                    match expresion.literalize(&VarContext::new()) {
+                        Ok(data) => ret.insert(key.to_owned(), data),
+                        Err(err) => {
+                            return Ok(Err(FailureExplanation {
+                                lex_pos: i,
+                                if_it_was: "data_record".into(),
+                                failed_because: FailureReason::Other(format!(
+                                    "unliteralizable expresion: {err}"
+                                )),
+                                parent_failure: vec![],
+                            }))
+                        }
+                    };
+                    cursor = jump_to;
+                    state = SpectingComaOrEnd;
+                }
+                (Err(e), _) => {
+                    return Ok(Err(FailureExplanation {
+                        lex_pos: i,
+                        if_it_was: "data_record".into(),
+                        failed_because: FailureReason::Other("specting value".into()),
+                        parent_failure: vec![e],
+                    }))
+                }
+                _ => unreachable!(),
+            },
+            (Coma, SpectingComaOrEnd) => state = SpectingKey,
+            (RightKey, SpectingComaOrEnd | SpectingKeyOrEnd) => return Ok(Ok((ret, i + 1))),
+            _ => {
+                return Ok(Err(FailureExplanation {
+                    lex_pos: i,
+                    if_it_was: "data_record".into(),
+                    failed_because: FailureReason::Specting(state.expected()),
+                    parent_failure: vec![],
+                }))
+            }
+        }
+    }
+    Ok(Err(FailureExplanation {
+        lex_pos: lexograms.len() - 1,
+        if_it_was: "data_record".into(),
+        failed_because: FailureReason::FileEnded,
         parent_failure: vec![],
     }))
 }
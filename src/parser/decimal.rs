@@ -0,0 +1,252 @@
+use std::{cmp::Ordering, fmt};
+
+// digits kept after the point when a division doesnt end, and at most after a multiplication
+pub const DIVISION_SCALE: u32 = 20;
+pub const MAX_SCALE: u32 = 28;
+
+// an exact base ten number, mantissa * 10^-scale, kept without trailing zeros so equal values
+// always share the same representation
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Decimal {
+    mantissa: i128,
+    scale: u32,
+}
+
+fn power_of_ten(exponent: u32) -> Option<i128> {
+    10i128.checked_pow(exponent)
+}
+
+// rounds half away from zero, as money is usually rounded
+fn divide_rounding(numerator: i128, denominator: i128) -> i128 {
+    let quotient = numerator / denominator;
+    let remainder = (numerator % denominator).unsigned_abs();
+    if remainder >= denominator.unsigned_abs() - remainder {
+        if (numerator < 0) == (denominator < 0) {
+            quotient + 1
+        } else {
+            quotient - 1
+        }
+    } else {
+        quotient
+    }
+}
+
+impl Decimal {
+    pub fn new(mantissa: i128, scale: u32) -> Decimal {
+        let mut ret = Decimal { mantissa, scale };
+        while ret.scale > 0 && ret.mantissa % 10 == 0 {
+            ret.mantissa /= 10;
+            ret.scale -= 1;
+        }
+        if ret.mantissa == 0 {
+            ret.scale = 0;
+        }
+        ret
+    }
+
+    pub fn from_integer(x: i64) -> Decimal {
+        Decimal::new(x as i128, 0)
+    }
+
+    // digits with an optional point and sign, the way they are written on the source and storage
+    pub fn parse(raw: &str) -> Option<Decimal> {
+        let (negative, unsigned) = match raw.strip_prefix('-') {
+            Some(unsigned) => (true, unsigned),
+            None => (false, raw),
+        };
+        let (integer_part, fraction_part) = match unsigned.split_once('.') {
+            Some((integer_part, fraction_part)) => (integer_part, fraction_part),
+            None => (unsigned, ""),
+        };
+        if integer_part.len() + fraction_part.len() == 0
+            || !integer_part.chars().all(|c| c.is_ascii_digit())
+            || !fraction_part.chars().all(|c| c.is_ascii_digit())
+        {
+            return None;
+        }
+
+        let mut mantissa: i128 = 0;
+        for c in integer_part.chars().chain(fraction_part.chars()) {
+            mantissa = mantissa
+                .checked_mul(10)?
+                .checked_add(c.to_digit(10)? as i128)?;
+        }
+        let scale = fraction_part.len() as u32;
+        if scale > MAX_SCALE {
+            return None;
+        }
+        Some(Decimal::new(
+            if negative { -mantissa } else { mantissa },
+            scale,
+        ))
+    }
+
+    pub fn is_zero(&self) -> bool {
+        self.mantissa == 0
+    }
+
+    pub fn to_integer(self) -> Option<i64> {
+        match self.scale {
+            0 => i64::try_from(self.mantissa).ok(),
+            _ => None,
+        }
+    }
+
+    // the nearest float, as parsing the written digits would give
+    pub fn to_f64(self) -> f64 {
+        if self.mantissa.unsigned_abs() < 1 << 53 && self.scale <= 22 {
+            // both sides are exact so the single division rounds correctly
+            self.mantissa as f64 / 10f64.powi(self.scale as i32)
+        } else {
+            self.to_string().parse().unwrap_or(f64::NAN)
+        }
+    }
+
+    fn rescale(&self, scale: u32) -> Option<i128> {
+        self.mantissa
+            .checked_mul(power_of_ten(scale.checked_sub(self.scale)?)?)
+    }
+
+    fn from_scaled(mantissa: i128, scale: i64) -> Result<Decimal, String> {
+        if scale >= 0 {
+            Ok(Decimal::new(mantissa, scale as u32))
+        } else {
+            match power_of_ten((-scale) as u32).and_then(|factor| mantissa.checked_mul(factor)) {
+                Some(mantissa) => Ok(Decimal::new(mantissa, 0)),
+                None => Err("decimal overflow".into()),
+            }
+        }
+    }
+
+    pub fn checked_add(&self, other: &Decimal) -> Result<Decimal, String> {
+        let scale = self.scale.max(other.scale);
+        match (self.rescale(scale), other.rescale(scale)) {
+            (Some(a), Some(b)) => match a.checked_add(b) {
+                Some(mantissa) => Ok(Decimal::new(mantissa, scale)),
+                None => Err("decimal overflow".into()),
+            },
+            _ => Err("decimal overflow".into()),
+        }
+    }
+
+    pub fn checked_sub(&self, other: &Decimal) -> Result<Decimal, String> {
+        self.checked_add(&Decimal::new(-other.mantissa, other.scale))
+    }
+
+    pub fn checked_mul(&self, other: &Decimal) -> Result<Decimal, String> {
+        let mantissa = match self.mantissa.checked_mul(other.mantissa) {
+            Some(mantissa) => mantissa,
+            None => return Err("decimal overflow".into()),
+        };
+        let scale = self.scale + other.scale;
+        if scale <= MAX_SCALE {
+            return Ok(Decimal::new(mantissa, scale));
+        }
+        match power_of_ten(scale - MAX_SCALE) {
+            Some(excess) => Ok(Decimal::new(divide_rounding(mantissa, excess), MAX_SCALE)),
+            None => Ok(Decimal::new(0, 0)),
+        }
+    }
+
+    // exact whenever the quotient ends within DIVISION_SCALE digits, rounded on that digit if not
+    pub fn checked_div(&self, other: &Decimal) -> Result<Decimal, String> {
+        if other.is_zero() {
+            return Err("cant divide by zero".into());
+        }
+
+        let mut shift = (DIVISION_SCALE + other.scale).saturating_sub(self.scale);
+        let numerator = loop {
+            match power_of_ten(shift).and_then(|factor| self.mantissa.checked_mul(factor)) {
+                Some(numerator) => break numerator,
+                None => shift -= 1,
+            }
+        };
+
+        Decimal::from_scaled(
+            divide_rounding(numerator, other.mantissa),
+            self.scale as i64 + shift as i64 - other.scale as i64,
+        )
+    }
+
+    // the whole number of times other fits in self, rounded towards minus infinity
+    pub fn checked_floor_div(&self, other: &Decimal) -> Result<Decimal, String> {
+        if other.is_zero() {
+            return Err("cant divide by zero".into());
+        }
+        let scale = self.scale.max(other.scale);
+        match (self.rescale(scale), other.rescale(scale)) {
+            (Some(a), Some(b)) => match a.checked_div_euclid(b) {
+                // euclid rounds down for positive divisors and up for negative ones
+                Some(quotient) if b < 0 && a.rem_euclid(b) != 0 => {
+                    Ok(Decimal::new(quotient + 1, 0))
+                }
+                Some(quotient) => Ok(Decimal::new(quotient, 0)),
+                None => Err("decimal overflow".into()),
+            },
+            _ => Err("decimal overflow".into()),
+        }
+    }
+
+    // what is left after the floor division, so it takes the sign of other
+    pub fn checked_rem(&self, other: &Decimal) -> Result<Decimal, String> {
+        self.checked_sub(&self.checked_floor_div(other)?.checked_mul(other)?)
+    }
+
+    // by squaring, so big exponents take a few multiplications before overflowing
+    pub fn checked_pow(&self, exponent: i64) -> Result<Decimal, String> {
+        let mut ret = Decimal::from_integer(1);
+        let mut base = *self;
+        let mut remaining = exponent.unsigned_abs();
+        while remaining > 0 {
+            if remaining % 2 == 1 {
+                ret = ret.checked_mul(&base)?;
+            }
+            remaining /= 2;
+            if remaining > 0 {
+                base = base.checked_mul(&base)?;
+            }
+        }
+        if exponent < 0 {
+            Decimal::from_integer(1).checked_div(&ret)
+        } else {
+            Ok(ret)
+        }
+    }
+}
+
+impl Ord for Decimal {
+    fn cmp(&self, other: &Self) -> Ordering {
+        let scale = self.scale.max(other.scale);
+        match (self.rescale(scale), other.rescale(scale)) {
+            (Some(a), Some(b)) => a.cmp(&b),
+            // too far apart to share a scale, so the floats tell them apart
+            _ => self
+                .to_f64()
+                .partial_cmp(&other.to_f64())
+                .unwrap_or(Ordering::Equal),
+        }
+    }
+}
+
+impl PartialOrd for Decimal {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl fmt::Display for Decimal {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let digits = self.mantissa.unsigned_abs().to_string();
+        let sign = if self.mantissa < 0 { "-" } else { "" };
+        let scale = self.scale as usize;
+
+        if scale == 0 {
+            write!(f, "{sign}{digits}")
+        } else if digits.len() > scale {
+            let (integer_part, fraction_part) = digits.split_at(digits.len() - scale);
+            write!(f, "{sign}{integer_part}.{fraction_part}")
+        } else {
+            write!(f, "{sign}0.{digits:0>scale$}")
+        }
+    }
+}
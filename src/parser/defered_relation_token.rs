@@ -1,8 +1,10 @@
 use core::fmt;
+use std::collections::BTreeSet;
 use std::{hash, vec};
 
 use print_macros::*;
 
+use crate::diagnostic::Span;
 use crate::engine::relation::truth::Truth;
 use crate::engine::var_context::VarContext;
 use crate::engine::RelId;
@@ -12,9 +14,9 @@ use crate::parser::assumption_token::read_assumption;
 use crate::{lexer, parser::list_token::read_list};
 
 use super::assumption_token::Assumption;
-use super::error::ParserError;
+use super::error::{FailureReason, ParserError};
 use super::{FailureExplanation, HasRelId};
-use crate::parser::expresion_token::Expresion;
+use crate::parser::expresion_token::{Expresion, ExpresionSemantics};
 
 #[derive(Debug, Clone, Eq)]
 pub struct DeferedRelation {
@@ -22,6 +24,8 @@ pub struct DeferedRelation {
     pub assumptions: Vec<Assumption>,
     pub rel_name: String,
     pub args: Vec<Expresion>,
+    // where it was read from, relations made up by the engine have none
+    pub span: Option<Span>,
 }
 
 impl PartialEq for DeferedRelation {
@@ -38,7 +42,8 @@ impl hash::Hash for DeferedRelation {
     where
         H: hash::Hasher,
     {
-        format!("{self:?}").hash(state);
+        // the span isnt part of what the relation is
+        format!("{self}").hash(state);
     }
 }
 
@@ -60,11 +65,15 @@ impl DeferedRelation {
         Ok(Truth::from(&(literal_vec, self.get_rel_id())))
     }
 
+    pub fn get_vars(&self) -> BTreeSet<String> {
+        self.args.iter().flat_map(|exp| exp.get_vars()).collect()
+    }
+
     pub fn clone_and_apply(&self, context: &VarContext) -> DeferedRelation {
         let mut literalized_vec = vec![];
         for exp in &self.args {
             literalized_vec.push(match exp.literalize(context) {
-                Ok(data) => Expresion::Literal(data),
+                Ok(data) => ExpresionSemantics::Literal(data).into(),
                 Err(_) => exp.clone(),
             })
         }
@@ -120,6 +129,7 @@ impl From<(&String, Vec<Expresion>)> for DeferedRelation {
             assumptions: vec![],
             rel_name: rel_name.to_owned(),
             args,
+            span: None,
         }
     }
 }
@@ -134,12 +144,30 @@ pub fn read_defered_relation(
         SpectingStatementIdentifierOrNegation,
         SpectingStatementIdentifier,
         SpectingAssuming,
-        SpectingStatementIdentifierOrassumptionOrNegation,
-        Spectingassumption,
-        SpectingComaBetweenassumptionsOrEndOfassumptions,
+        SpectingStatementIdentifierOrAssumptionOrNegation,
+        SpectingAssumption,
+        SpectingComaOrEndOfAssumptions,
         SpectingStatementList,
         SpectingQuery,
     }
+    impl RelationParserStates {
+        fn expected(&self) -> Vec<&'static str> {
+            match self {
+                RelationParserStates::SpectingStatementIdentifierOrNegation => {
+                    vec!["a relation name", "`!`"]
+                }
+                SpectingStatementIdentifier => vec!["a relation name"],
+                SpectingAssuming => vec!["`=>`"],
+                RelationParserStates::SpectingStatementIdentifierOrAssumptionOrNegation => {
+                    vec!["a relation name", "`{`", "`!`"]
+                }
+                SpectingAssumption => vec!["an assumption"],
+                SpectingComaOrEndOfAssumptions => vec!["`,`", "`}`"],
+                SpectingStatementList => vec!["`(`"],
+                SpectingQuery => vec!["`?`"],
+            }
+        }
+    }
     use RelationParserStates::*;
 
     printparse!("read_defered_relation at {}", start_cursor);
@@ -149,7 +177,7 @@ pub fn read_defered_relation(
     let mut op_rel_name = None;
     let mut args = vec![];
     let mut assumptions = vec![];
-    let mut state = SpectingStatementIdentifierOrassumptionOrNegation;
+    let mut state = SpectingStatementIdentifierOrAssumptionOrNegation;
 
     for (i, lex) in lexograms.iter().enumerate() {
         if cursor > i {
@@ -158,13 +186,13 @@ pub fn read_defered_relation(
         match (lex.l_type.to_owned(), state) {
             (
                 OpNot,
-                SpectingStatementIdentifierOrassumptionOrNegation
+                SpectingStatementIdentifierOrAssumptionOrNegation
                 | SpectingStatementIdentifierOrNegation,
             ) => {
                 negated = true;
                 state = SpectingStatementIdentifier;
             }
-            (_, Spectingassumption) => {
+            (_, SpectingAssumption) => {
                 match read_assumption(lexograms, i)? {
                     Ok((assumption, jump_to)) => {
                         cursor = jump_to;
@@ -174,27 +202,29 @@ pub fn read_defered_relation(
                         return Ok(Err(FailureExplanation {
                             lex_pos: i,
                             if_it_was: "defered relation".into(),
-                            failed_because: format!("specting assumption").into(),
+                            failed_because: FailureReason::Other(
+                                format!("specting assumption").into(),
+                            ),
                             parent_failure: vec![err],
                         }))
                     }
                 }
-                state = SpectingComaBetweenassumptionsOrEndOfassumptions
+                state = SpectingComaOrEndOfAssumptions
             }
-            (LeftKey, SpectingStatementIdentifierOrassumptionOrNegation) => {
-                state = Spectingassumption;
+            (LeftKey, SpectingStatementIdentifierOrAssumptionOrNegation) => {
+                state = SpectingAssumption;
             }
-            (RightKey, SpectingComaBetweenassumptionsOrEndOfassumptions) => {
+            (RightKey, SpectingComaOrEndOfAssumptions) => {
                 state = SpectingAssuming;
             }
-            (Coma, SpectingComaBetweenassumptionsOrEndOfassumptions) => {
-                state = Spectingassumption;
+            (Coma, SpectingComaOrEndOfAssumptions) => {
+                state = SpectingAssumption;
             }
             (Assuming, SpectingAssuming) => state = SpectingStatementIdentifierOrNegation,
             (
                 Identifier(str),
                 SpectingStatementIdentifier
-                | SpectingStatementIdentifierOrassumptionOrNegation
+                | SpectingStatementIdentifierOrAssumptionOrNegation
                 | SpectingStatementIdentifierOrNegation,
             ) => {
                 op_rel_name = Some(str);
@@ -205,7 +235,7 @@ pub fn read_defered_relation(
                     return Ok(Err(FailureExplanation {
                         lex_pos: i,
                         if_it_was: "defered relation".into(),
-                        failed_because: "specting list".into(),
+                        failed_because: FailureReason::Other("specting list".into()),
                         parent_failure: (vec![e]),
                     }))
                 }
@@ -222,6 +252,10 @@ pub fn read_defered_relation(
                                     assumptions,
                                     rel_name,
                                     args,
+                                    span: Some(Span {
+                                        start: lexograms[start_cursor].pos_s,
+                                        end: lexograms[jump_to - 1].pos_f,
+                                    }),
                                 },
                                 jump_to,
                             )));
@@ -239,6 +273,10 @@ pub fn read_defered_relation(
                             assumptions,
                             rel_name,
                             args,
+                            span: Some(Span {
+                                start: lexograms[start_cursor].pos_s,
+                                end: lex.pos_f,
+                            }),
                         },
                         i + 1,
                     )));
@@ -250,7 +288,7 @@ pub fn read_defered_relation(
                 return Ok(Err(FailureExplanation {
                     lex_pos: i,
                     if_it_was: "defered relation".into(),
-                    failed_because: format!("pattern missmatch on {:#?} state", state).into(),
+                    failed_because: FailureReason::Specting(state.expected()),
                     parent_failure: vec![],
                 }))
             }
@@ -259,7 +297,7 @@ pub fn read_defered_relation(
     Ok(Err(FailureExplanation {
         lex_pos: lexograms.len() - 1,
         if_it_was: "defered relation".into(),
-        failed_because: "file ended".into(),
+        failed_because: FailureReason::FileEnded,
         parent_failure: vec![],
     }))
 }
@@ -1,8 +1,8 @@
 use print_macros::*;
 
-use super::error::{FailureExplanation, ParserError};
+use super::error::{FailureExplanation, FailureReason, ParserError};
 use crate::lexer::LexogramType::*;
-use crate::parser::expresion_token::{read_expresion, Expresion, VarName};
+use crate::parser::expresion_token::{read_expresion, ExpresionSemantics, VarName};
 
 use crate::lexer;
 
@@ -18,6 +18,17 @@ pub fn read_destructuring_array(
         SpectingComaOrEnd,
         SpectingStart,
     }
+    impl ArrayParserStates {
+        fn expected(&self) -> Vec<&'static str> {
+            match self {
+                SpectingItemOrEnd => vec!["an item", "`]`"],
+                SpectingIdentifierAfterDotDotDot => vec!["a variable"],
+                SpectingItemOrDotDotDot => vec!["an item", "`...`"],
+                SpectingComaOrEnd => vec!["`,`", "`]`"],
+                SpectingStart => vec!["`[`"],
+            }
+        }
+    }
     use ArrayParserStates::*;
     printparse!("read_destructuring_array at {}", start_cursor);
 
@@ -39,7 +50,7 @@ pub fn read_destructuring_array(
                 state = SpectingIdentifierAfterDotDotDot;
             }
             (Identifier(str), SpectingIdentifierAfterDotDotDot) => {
-                ret.push(Expresion::Var(VarName::ExplodeArray(str)));
+                ret.push(ExpresionSemantics::Var(VarName::ExplodeArray(str)).into());
                 state = SpectingItemOrEnd;
             }
             (Coma, SpectingComaOrEnd) => state = SpectingItemOrDotDotDot,
@@ -52,7 +63,7 @@ pub fn read_destructuring_array(
                         return Ok(Err(FailureExplanation {
                             lex_pos: i,
                             if_it_was: "destructuring_array".into(),
-                            failed_because: "specting item".into(),
+                            failed_because: FailureReason::Other("specting item".into()),
                             parent_failure: vec![err],
                         }))
                     }
@@ -68,7 +79,7 @@ pub fn read_destructuring_array(
                 return Ok(Err(FailureExplanation {
                     lex_pos: i,
                     if_it_was: "destructuring_array".into(),
-                    failed_because: format!("pattern missmatch on {:#?} state", state).into(),
+                    failed_because: FailureReason::Specting(state.expected()),
                     parent_failure: vec![],
                 }))
             }
@@ -77,7 +88,7 @@ pub fn read_destructuring_array(
     Ok(Err(FailureExplanation {
         lex_pos: lexograms.len() - 1,
         if_it_was: "destructuring_array".into(),
-        failed_because: "file ended".into(),
+        failed_because: FailureReason::FileEnded,
         parent_failure: vec![],
     }))
 }
@@ -0,0 +1,109 @@
+use print_macros::*;
+
+use super::error::{FailureExplanation, FailureReason, ParserError};
+use crate::lexer::LexogramType::*;
+use crate::parser::expresion_token::{read_expresion, VarName};
+
+use crate::lexer;
+
+pub fn read_destructuring_record(
+    lexograms: &Vec<lexer::Lexogram>,
+    start_cursor: usize,
+) -> Result<Result<(VarName, usize), FailureExplanation>, ParserError> {
+    #[derive(Debug, Clone, Copy)]
+    enum RecordParserStates {
+        SpectingStart,
+        SpectingKeyOrEnd,
+        SpectingKeyOrDotDotDot,
+        SpectingColon,
+        SpectingValue,
+        SpectingIdentifierAfterDotDotDot,
+        SpectingComaOrEnd,
+        SpectingEnd,
+    }
+    impl RecordParserStates {
+        fn expected(&self) -> Vec<&'static str> {
+            match self {
+                SpectingStart => vec!["`{`"],
+                SpectingKeyOrEnd => vec!["a key", "`}`"],
+                SpectingKeyOrDotDotDot => vec!["a key", "`...`"],
+                SpectingColon => vec!["`:`"],
+                SpectingValue => vec!["a value"],
+                SpectingIdentifierAfterDotDotDot => vec!["a variable"],
+                SpectingComaOrEnd => vec!["`,`", "`}`"],
+                SpectingEnd => vec!["`}`"],
+            }
+        }
+    }
+    use RecordParserStates::*;
+    printparse!("read_destructuring_record at {}", start_cursor);
+
+    let mut cursor = start_cursor;
+
+    let mut fields = vec![];
+    let mut rest = None;
+    let mut op_key = None;
+    let mut state = SpectingStart;
+
+    for (i, lex) in lexograms.iter().enumerate() {
+        if cursor > i {
+            continue;
+        }
+        match (lex.l_type.to_owned(), state) {
+            (LeftKey, SpectingStart) => state = SpectingKeyOrEnd,
+            (Identifier(key) | Word(key), SpectingKeyOrEnd | SpectingKeyOrDotDotDot) => {
+                if fields.iter().any(|(field_key, _)| field_key == &key) {
+                    return Ok(Err(FailureExplanation {
+                        lex_pos: i,
+                        if_it_was: "destructuring_record".into(),
+                        failed_because: FailureReason::Other(format!("the key {key} is repeated")),
+                        parent_failure: vec![],
+                    }));
+                }
+                op_key = Some(key);
+                state = SpectingColon;
+            }
+            (Colon, SpectingColon) => state = SpectingValue,
+            (_, SpectingValue) => match (read_expresion(lexograms, i, false)?, &op_key) {
+                (Ok((expresion, jump_to)), Some(key)) => {
+                    fields.push((key.to_owned(), expresion));
+                    cursor = jump_to;
+                    state = SpectingComaOrEnd;
+                }
+                (Err(err), _) => {
+                    return Ok(Err(FailureExplanation {
+                        lex_pos: i,
+                        if_it_was: "destructuring_record".into(),
+                        failed_because: FailureReason::Other("specting value".into()),
+                        parent_failure: vec![err],
+                    }))
+                }
+                _ => unreachable!(),
+            },
+            // the rest of the fields can only be gathered at the end
+            (DotDotDot, SpectingKeyOrDotDotDot) => state = SpectingIdentifierAfterDotDotDot,
+            (Identifier(name), SpectingIdentifierAfterDotDotDot) => {
+                rest = Some(name);
+                state = SpectingEnd;
+            }
+            (Coma, SpectingComaOrEnd) => state = SpectingKeyOrDotDotDot,
+            (RightKey, SpectingComaOrEnd | SpectingKeyOrEnd | SpectingEnd) => {
+                return Ok(Ok((VarName::DestructuredRecord(fields, rest), i + 1)));
+            }
+            _ => {
+                return Ok(Err(FailureExplanation {
+                    lex_pos: i,
+                    if_it_was: "destructuring_record".into(),
+                    failed_because: FailureReason::Specting(state.expected()),
+                    parent_failure: vec![],
+                }))
+            }
+        }
+    }
+    Ok(Err(FailureExplanation {
+        lex_pos: lexograms.len() - 1,
+        if_it_was: "destructuring_record".into(),
+        failed_because: FailureReason::FileEnded,
+        parent_failure: vec![],
+    }))
+}
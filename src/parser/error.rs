@@ -1,56 +1,201 @@
-use crate::{lexer::Lexogram, utils::print_hilighted};
+use std::fmt;
+
+use crate::{
+    diagnostic::{self, Diagnostic, Severity, Span},
+    lexer::{Lexogram, LexogramType},
+};
 
 #[derive(Debug)]
 
 pub struct FailureExplanation {
     pub lex_pos: usize,
     pub if_it_was: String,
-    pub failed_because: String,
+    pub failed_because: FailureReason,
     pub parent_failure: Vec<FailureExplanation>,
 }
 
+#[derive(Debug)]
+pub enum FailureReason {
+    // the lexogram didnt fit the state of the parser, which was waiting for any of these
+    Specting(Vec<&'static str>),
+    FileEnded,
+    Other(String),
+}
+
+impl fmt::Display for LexogramType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let symbol = match self {
+            LexogramType::Identifier(name) => return write!(f, "{name}"),
+            LexogramType::Word(word) => return write!(f, "{word:?}"),
+            LexogramType::Integer(x) => return write!(f, "{x}"),
+            LexogramType::Decimal(x) => return write!(f, "{x}"),
+            LexogramType::Number(x) => return write!(f, "{x}"),
+            LexogramType::Comment(text) => return write!(f, "#{text}"),
+            LexogramType::RightParenthesis => ")",
+            LexogramType::LeftParenthesis => "(",
+            LexogramType::RightBracket => "]",
+            LexogramType::LeftBracket => "[",
+            LexogramType::RightKey => "}",
+            LexogramType::LeftKey => "{",
+            LexogramType::DotDotDot => "...",
+            LexogramType::Coma => ",",
+            LexogramType::Colon | LexogramType::CharColon => ":",
+            LexogramType::TrueWhen => ":-",
+            LexogramType::Assuming => "=>",
+            LexogramType::Update => "->",
+            LexogramType::Query => "?",
+            LexogramType::Why => "??",
+            LexogramType::OpEq | LexogramType::CharEq => "=",
+            LexogramType::OpNotEq => "!=",
+            LexogramType::OpLT => "<",
+            LexogramType::OpLTE => "<=",
+            LexogramType::OpGT => ">",
+            LexogramType::OpGTE => ">=",
+            LexogramType::OpMul => "*",
+            LexogramType::OpDiv => "/",
+            LexogramType::OpMod => "%",
+            LexogramType::OpFloorDiv => "//",
+            LexogramType::OpPow => "^",
+            LexogramType::OpAdd => "+",
+            LexogramType::OpSub => "-",
+            LexogramType::OpNot => "!",
+            LexogramType::OpAnd => "&&",
+            LexogramType::OpOr => "||",
+            LexogramType::Any => "_",
+            LexogramType::True => "true",
+            LexogramType::False => "false",
+            LexogramType::Null => "null",
+            LexogramType::CharNewLine => "new line",
+            LexogramType::CharCarriageReturn => "carriage return",
+            LexogramType::WhiteSpace => "white space",
+        };
+        write!(f, "{symbol}")
+    }
+}
+
 impl FailureExplanation {
-    pub fn print(
-        self,
-        lex_list: &Vec<Lexogram>,
-        original_string: &String,
-        indentation: String,
-    ) -> String {
-        let mut ret = format!(
-            "{indentation}Error trying to read a \x1b[1m{}\x1b[0m failed because:\n",
-            self.if_it_was,
-        );
-        let error_lex = &lex_list[self.lex_pos];
-
-        if !self.parent_failure.is_empty() {
-            for parent in self.parent_failure {
-                ret += &parent.print(
-                    lex_list,
-                    original_string,
-                    indentation.to_owned() + "\x1b[90m| \x1b[0m".into(),
-                );
-            }
+    fn get_leaves(&self) -> Vec<&FailureExplanation> {
+        if self.parent_failure.is_empty() {
+            vec![self]
         } else {
-            ret += &format!(
-                "{indentation}\x1b[1m{}\x1b[0m starting at:\n{indentation}{}\n{indentation}\n",
-                self.failed_because,
-                print_hilighted(
-                    original_string,
-                    error_lex.pos_s,
-                    error_lex.pos_f,
-                    indentation.clone(),
+            self.parent_failure
+                .iter()
+                .flat_map(|parent| parent.get_leaves())
+                .collect()
+        }
+    }
+
+    // the lexogram the parser got furthest to before failing, the likeliest place of the mistake
+    pub fn get_furthest_pos(&self) -> usize {
+        self.get_leaves()
+            .iter()
+            .map(|leaf| leaf.lex_pos)
+            .max()
+            .unwrap_or(self.lex_pos)
+    }
+
+    pub fn to_diagnostic(&self, lex_list: &[Lexogram]) -> Diagnostic {
+        let furthest_pos = self.get_furthest_pos();
+        let furthest_leaves: Vec<&FailureExplanation> = self
+            .get_leaves()
+            .into_iter()
+            .filter(|leaf| leaf.lex_pos == furthest_pos)
+            .collect();
+        let error_lex = &lex_list[furthest_pos];
+        let span = Span {
+            start: error_lex.pos_s,
+            end: error_lex.pos_f,
+        };
+
+        // nothing could be read from the start of the line
+        if furthest_pos == self.lex_pos {
+            return Diagnostic {
+                span: Some(span),
+                expected: vec!["a fact, a rule or a query".into()],
+                hint: Some(match error_lex.l_type {
+                    LexogramType::Colon => "rules are written as head :- body".into(),
+                    _ => "the line before may be unfinished".into(),
+                }),
+                ..Diagnostic::new(
+                    Severity::Error,
+                    diagnostic::INVALID_LINE_START,
+                    format!("a line cant start with `{}`", error_lex.l_type),
+                )
+            };
+        }
+
+        // a leaf not complaining about the shape of the input explains the mistake by itself
+        let specific = furthest_leaves
+            .iter()
+            .find_map(|leaf| match &leaf.failed_because {
+                FailureReason::Other(msg) => Some(msg),
+                _ => None,
+            });
+        if let Some(msg) = specific {
+            return Diagnostic {
+                span: Some(span),
+                ..Diagnostic::new(
+                    Severity::Error,
+                    diagnostic::INVALID_CONSTRUCT,
+                    msg.to_owned(),
                 )
-            );
+            };
+        }
+
+        let mut expected = vec![];
+        for leaf in &furthest_leaves {
+            let leaf_expected = match &leaf.failed_because {
+                FailureReason::Specting(items) => {
+                    items.iter().map(|item| item.to_string()).collect()
+                }
+                _ => vec![leaf.if_it_was.to_owned()],
+            };
+            for item in leaf_expected {
+                if !expected.contains(&item) {
+                    expected.push(item);
+                }
+            }
+        }
+
+        let reading = &furthest_leaves[0].if_it_was;
+        let ended = furthest_leaves
+            .iter()
+            .all(|leaf| matches!(leaf.failed_because, FailureReason::FileEnded));
+        let (code, message) = if ended {
+            (
+                diagnostic::UNFINISHED_INPUT,
+                format!("the input ended in the middle of a {reading}"),
+            )
+        } else {
+            (
+                diagnostic::UNEXPECTED_LEXOGRAM,
+                format!("unexpected `{}` on a {reading}", error_lex.l_type),
+            )
         };
 
-        ret
+        Diagnostic {
+            span: Some(span),
+            hint: get_hint(&error_lex.l_type, ended),
+            expected,
+            ..Diagnostic::new(Severity::Error, code, message)
+        }
+    }
+}
+
+fn get_hint(found: &LexogramType, ended: bool) -> Option<String> {
+    match found {
+        _ if ended => Some("the last line seems unfinished".into()),
+        LexogramType::RightParenthesis | LexogramType::RightBracket | LexogramType::RightKey => {
+            Some("check that every bracket opened is closed".into())
+        }
+        _ => None,
     }
 }
 
 #[derive(Debug)]
 pub enum ParserError {
     Custom(String),
-    SyntaxError(FailureExplanation),
+    SyntaxErrors(Vec<Diagnostic>),
 }
 
 impl From<String> for ParserError {
@@ -59,10 +204,14 @@ impl From<String> for ParserError {
     }
 }
 impl ParserError {
-    pub fn print(self, lexic: &Vec<Lexogram>, commands: &String) -> String {
+    pub fn into_diagnostics(self) -> Vec<Diagnostic> {
         match self {
-            ParserError::Custom(str) => format!("custom error on parsing: {str}"),
-            ParserError::SyntaxError(e) => e.print(lexic, commands, "".into()),
+            ParserError::Custom(str) => vec![Diagnostic::new(
+                Severity::Error,
+                diagnostic::PARSER_INTERNAL,
+                format!("custom error on parsing: {str}"),
+            )],
+            ParserError::SyntaxErrors(diagnostics) => diagnostics,
         }
     }
 }
@@ -1,19 +1,29 @@
+use std::collections::{BTreeMap, BTreeSet};
 use std::fmt::{self};
+use std::hash::{Hash, Hasher};
 
 use print_macros::*;
 
+use crate::diagnostic::Span;
+use crate::engine::recursion_tally::RecursionTally;
 use crate::engine::var_context::VarContext;
+use crate::engine::{DeductionError, Engine};
 use crate::lexer;
 use crate::lexer::LexogramType::*;
 
-use super::data_token::{read_data, Data};
-use super::error::{FailureExplanation, ParserError};
+use super::aggregate_token::{read_aggregate, Aggregate, AggregateFunction};
+use super::data_token::{key_to_string, read_data, Data};
+use super::error::{FailureExplanation, FailureReason, ParserError};
+use super::function_token::{read_function_call, BuiltInFunction, FunctionCall};
 use crate::engine::operations::*;
 use crate::parser::destructuring_array_token::read_destructuring_array;
+use crate::parser::destructuring_record_token::read_destructuring_record;
 
 #[derive(Debug, Clone, Hash, PartialEq, Eq)]
 pub enum VarName {
     DestructuredArray(Vec<Expresion>),
+    // the fields to match and the variable gathering the rest of them, if any
+    DestructuredRecord(Vec<(String, Expresion)>, Option<String>),
     Direct(String),
     ExplodeArray(String),
 }
@@ -34,6 +44,18 @@ impl fmt::Display for VarName {
                 ret += &"]";
                 write!(f, "{ret}")
             }
+            VarName::DestructuredRecord(fields, rest) => {
+                let mut items = fields
+                    .iter()
+                    .map(|(key, exp)| format!("{}: {exp}", key_to_string(key)))
+                    .collect::<Vec<String>>();
+                if let Some(rest) = rest {
+                    items.push(format!("...{rest}"));
+                }
+                write!(f, "{{{}}}", items.join(", "))
+            }
+            // hidden variables stand for the _ they were given to
+            VarName::Direct(name) if name.starts_with('#') => write!(f, "_"),
             VarName::Direct(name) => write!(f, "{name}"),
             VarName::ExplodeArray(name) => write!(f, "...{name}"),
         }
@@ -48,42 +70,288 @@ pub struct Operation<Op, Res> {
     pub to_string: String,
 }
 
+impl<Op, Res> Operation<Op, Res> {
+    // ^ binds the tightest, then * / // %, and + - the loosest
+    pub fn precedence(&self) -> u8 {
+        match self.to_string.as_str() {
+            "^" => 3,
+            "+" | "-" => 1,
+            _ => 2,
+        }
+    }
+
+    pub fn is_right_associative(&self) -> bool {
+        self.to_string == "^"
+    }
+
+    // whether an operand needs parenthesis to be read back as this operation's operand
+    pub fn needs_parenthesis(&self, operand: &Expresion, on_the_right: bool) -> bool {
+        match &operand.semantics {
+            ExpresionSemantics::Arithmetic(_, _, inner) => {
+                inner.precedence() < self.precedence()
+                    || (inner.precedence() == self.precedence()
+                        && on_the_right != self.is_right_associative())
+            }
+            _ => false,
+        }
+    }
+}
+
 #[derive(Debug, Clone, Eq, PartialEq, Hash)]
-pub enum Expresion {
+pub enum ExpresionSemantics {
     // resolvable to a value
     Arithmetic(Box<Expresion>, Box<Expresion>, Operation<Data, Data>),
     Literal(Data),
     Var(VarName),
+    Aggregate(Aggregate),
+    Function(FunctionCall),
+    Negation(Box<Expresion>),
+}
+
+#[derive(Debug, Clone, Eq)]
+pub struct Expresion {
+    semantics: ExpresionSemantics,
+    // where it was read from, expresions made up by the engine have none
+    span: Option<Span>,
+}
+
+impl From<ExpresionSemantics> for Expresion {
+    fn from(value: ExpresionSemantics) -> Self {
+        Self {
+            semantics: value,
+            span: None,
+        }
+    }
+}
+
+impl PartialEq for Expresion {
+    fn eq(&self, other: &Self) -> bool {
+        self.semantics == other.semantics
+    }
+}
+
+impl Hash for Expresion {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.semantics.hash(state);
+    }
 }
 
 impl fmt::Display for Expresion {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        match self {
-            Expresion::Arithmetic(expa, expb, op) => write!(f, "{expa}{}{expb}", op.to_string),
-            Expresion::Literal(l) => write!(f, "{l}"),
-            Expresion::Var(v) => write!(f, "{v}"),
+        match &self.semantics {
+            ExpresionSemantics::Arithmetic(expa, expb, op) => {
+                match op.needs_parenthesis(expa, false) {
+                    true => write!(f, "({expa})")?,
+                    false => write!(f, "{expa}")?,
+                }
+                write!(f, "{}", op.to_string)?;
+                match op.needs_parenthesis(expb, true) {
+                    true => write!(f, "({expb})"),
+                    false => write!(f, "{expb}"),
+                }
+            }
+            ExpresionSemantics::Literal(l) => write!(f, "{}", l.to_string()),
+            ExpresionSemantics::Var(v) => write!(f, "{v}"),
+            ExpresionSemantics::Aggregate(aggregate) => write!(f, "{aggregate}"),
+            ExpresionSemantics::Function(call) => write!(f, "{call}"),
+            ExpresionSemantics::Negation(exp) => match exp.semantics {
+                ExpresionSemantics::Arithmetic(_, _, _) => write!(f, "-({exp})"),
+                _ => write!(f, "-{exp}"),
+            },
         }
     }
 }
 
 impl Expresion {
+    pub fn get_semantics(&self) -> &ExpresionSemantics {
+        &self.semantics
+    }
+
+    pub fn get_span(&self) -> Option<Span> {
+        self.span
+    }
+
+    pub fn with_span(mut self, span: Span) -> Expresion {
+        self.span = Some(span);
+        self
+    }
+
+    // the variables of an aggregate belong to its own scope
+    pub fn get_vars(&self) -> BTreeSet<String> {
+        match &self.semantics {
+            ExpresionSemantics::Arithmetic(a, b, _) => {
+                let mut ret = a.get_vars();
+                ret.extend(b.get_vars());
+                ret
+            }
+            ExpresionSemantics::Var(VarName::Direct(name))
+            | ExpresionSemantics::Var(VarName::ExplodeArray(name)) => {
+                BTreeSet::from([name.to_owned()])
+            }
+            ExpresionSemantics::Var(VarName::DestructuredArray(exp_vec)) => {
+                exp_vec.iter().flat_map(|exp| exp.get_vars()).collect()
+            }
+            ExpresionSemantics::Var(VarName::DestructuredRecord(fields, rest)) => fields
+                .iter()
+                .flat_map(|(_, exp)| exp.get_vars())
+                .chain(rest.to_owned())
+                .collect(),
+            ExpresionSemantics::Function(call) => {
+                call.args.iter().flat_map(|arg| arg.get_vars()).collect()
+            }
+            ExpresionSemantics::Negation(exp) => exp.get_vars(),
+            ExpresionSemantics::Literal(_) | ExpresionSemantics::Aggregate(_) => BTreeSet::new(),
+        }
+    }
+
+    pub fn get_aggregates(&self) -> Vec<&Aggregate> {
+        match &self.semantics {
+            ExpresionSemantics::Arithmetic(a, b, _) => {
+                let mut ret = a.get_aggregates();
+                ret.extend(b.get_aggregates());
+                ret
+            }
+            ExpresionSemantics::Aggregate(aggregate) => vec![aggregate],
+            ExpresionSemantics::Negation(exp) => exp.get_aggregates(),
+            ExpresionSemantics::Function(call) => call
+                .args
+                .iter()
+                .flat_map(|arg| arg.get_aggregates())
+                .collect(),
+            ExpresionSemantics::Literal(_) | ExpresionSemantics::Var(_) => vec![],
+        }
+    }
+
+    // whether an unknown value inside can be found out from the value of the whole expresion
+    pub fn is_solvable(&self) -> bool {
+        match &self.semantics {
+            // % and // have no reverse operation
+            ExpresionSemantics::Arithmetic(_, _, op)
+                if op.to_string == "%" || op.to_string == "//" =>
+            {
+                false
+            }
+            ExpresionSemantics::Arithmetic(a, b, _) => a.is_solvable() && b.is_solvable(),
+            ExpresionSemantics::Negation(exp) => exp.is_solvable(),
+            ExpresionSemantics::Var(VarName::DestructuredArray(exp_vec)) => {
+                exp_vec.iter().all(|exp| exp.is_solvable())
+            }
+            ExpresionSemantics::Var(VarName::DestructuredRecord(fields, _)) => {
+                fields.iter().all(|(_, exp)| exp.is_solvable())
+            }
+            ExpresionSemantics::Function(call) => {
+                matches!(
+                    call.function,
+                    BuiltInFunction::Split | BuiltInFunction::Concat
+                ) && call.args.iter().all(|arg| arg.is_solvable())
+            }
+            ExpresionSemantics::Literal(_)
+            | ExpresionSemantics::Var(_)
+            | ExpresionSemantics::Aggregate(_) => true,
+        }
+    }
+
+    pub fn scope_aggregates(&self, outer_vars: &BTreeSet<String>) -> Expresion {
+        let semantics = match &self.semantics {
+            ExpresionSemantics::Arithmetic(a, b, op) => ExpresionSemantics::Arithmetic(
+                Box::new(a.scope_aggregates(outer_vars)),
+                Box::new(b.scope_aggregates(outer_vars)),
+                op.to_owned(),
+            ),
+            ExpresionSemantics::Aggregate(aggregate) => {
+                ExpresionSemantics::Aggregate(aggregate.scope(outer_vars))
+            }
+            ExpresionSemantics::Negation(exp) => {
+                ExpresionSemantics::Negation(Box::new(exp.scope_aggregates(outer_vars)))
+            }
+            ExpresionSemantics::Function(call) => ExpresionSemantics::Function(FunctionCall {
+                function: call.function,
+                args: call
+                    .args
+                    .iter()
+                    .map(|arg| arg.scope_aggregates(outer_vars))
+                    .collect(),
+            }),
+            _ => return self.to_owned(),
+        };
+        Expresion {
+            semantics,
+            span: self.span,
+        }
+    }
+
+    // folds every aggregate into the literal it evaluates to on the given context, none when one
+    // of them has no value there
+    pub fn resolve_aggregates(
+        &self,
+        engine: &Engine,
+        recursion_tally: &RecursionTally,
+        context: &VarContext,
+    ) -> Result<Option<Expresion>, DeductionError> {
+        let resolve = |exp: &Expresion| exp.resolve_aggregates(engine, recursion_tally, context);
+        let semantics = match &self.semantics {
+            ExpresionSemantics::Arithmetic(a, b, op) => match (resolve(a)?, resolve(b)?) {
+                (Some(a), Some(b)) => {
+                    ExpresionSemantics::Arithmetic(Box::new(a), Box::new(b), op.to_owned())
+                }
+                _ => return Ok(None),
+            },
+            ExpresionSemantics::Aggregate(aggregate) => {
+                match aggregate.evaluate(engine, recursion_tally, context)? {
+                    Some(value) => ExpresionSemantics::Literal(value),
+                    None => return Ok(None),
+                }
+            }
+            ExpresionSemantics::Negation(exp) => match resolve(exp)? {
+                Some(exp) => ExpresionSemantics::Negation(Box::new(exp)),
+                None => return Ok(None),
+            },
+            ExpresionSemantics::Function(call) => {
+                let mut args = vec![];
+                for arg in &call.args {
+                    match resolve(arg)? {
+                        Some(arg) => args.push(arg),
+                        None => return Ok(None),
+                    }
+                }
+                ExpresionSemantics::Function(FunctionCall {
+                    function: call.function,
+                    args,
+                })
+            }
+            _ => return Ok(Some(self.to_owned())),
+        };
+        Ok(Some(Expresion {
+            semantics,
+            span: self.span,
+        }))
+    }
+
     pub fn literalize(self: &Expresion, context: &VarContext) -> Result<Data, String> {
-        let ret = match self.to_owned() {
-            Expresion::Arithmetic(a, b, f) => {
+        let ret = match self.semantics.to_owned() {
+            ExpresionSemantics::Arithmetic(a, b, f) => {
                 Ok((f.forward)(a.literalize(context)?, b.literalize(context)?)?)
             }
-            Expresion::Literal(e) => Ok(e),
-            Expresion::Var(VarName::Direct(str)) => match context.get(&str) {
+            ExpresionSemantics::Function(call) => {
+                let mut args = vec![];
+                for arg in &call.args {
+                    args.push(arg.literalize(context)?);
+                }
+                call.function.apply(&args)
+            }
+            ExpresionSemantics::Negation(exp) => negate_direct(exp.literalize(context)?),
+            ExpresionSemantics::Literal(e) => Ok(e),
+            ExpresionSemantics::Var(VarName::Direct(str)) => match context.get(&str) {
                 Some(value) => Ok(value.to_owned()),
                 None => Err(format!(
                     "literalize error: var {str} not defined on context {context}"
                 )),
             },
-            Expresion::Var(VarName::DestructuredArray(exp_vec)) => {
+            ExpresionSemantics::Var(VarName::DestructuredArray(exp_vec)) => {
                 let mut datas = vec![];
                 for e in exp_vec.iter() {
-                    match e {
-                        Expresion::Var(VarName::ExplodeArray(var_name)) => {
+                    match &e.semantics {
+                        ExpresionSemantics::Var(VarName::ExplodeArray(var_name)) => {
                             let var_value = match context.get(var_name){
                                 Some(ret) => ret,
                                 None =>return Err(format!(
@@ -101,6 +369,28 @@ impl Expresion {
 
                 Ok(Data::Array(datas))
             }
+            ExpresionSemantics::Var(VarName::DestructuredRecord(fields, rest)) => {
+                let mut ret = match rest {
+                    Some(rest) => match context.get(&rest) {
+                        Some(Data::Record(rest_fields)) => rest_fields.to_owned(),
+                        _ => {
+                            return Err(format!(
+                            "cant literalize {self} on the context {context} because of ...{rest}"
+                        ))
+                        }
+                    },
+                    None => BTreeMap::new(),
+                };
+                for (key, exp) in fields.iter() {
+                    if ret
+                        .insert(key.to_owned(), exp.literalize(context)?)
+                        .is_some()
+                    {
+                        return Err(format!("the rest of {self} holds {key} again"));
+                    }
+                }
+                Ok(Data::Record(ret))
+            }
             _ => Err(format!(
                 "no se ha podido literalizar: {self} en el contexto {context}"
             )),
@@ -117,8 +407,8 @@ impl Expresion {
         // return Ok significa que goal y self han podido ser evaluadas a lo mismo
 
         let ret = match self.literalize(&caller_context) {
-            Ok(Data::Any) => match &self {
-                Expresion::Var(VarName::Direct(name)) => {
+            Ok(Data::Any) => match &self.semantics {
+                ExpresionSemantics::Var(VarName::Direct(name)) => {
                     let mut new_context = caller_context.to_owned();
                     new_context.set(name.to_owned(), goal.to_owned());
                     new_context
@@ -126,8 +416,8 @@ impl Expresion {
                 _ => caller_context.to_owned(),
             },
 
-            Err(_) => match self {
-                Expresion::Arithmetic(a, b, func) => {
+            Err(_) => match &self.semantics {
+                ExpresionSemantics::Arithmetic(a, b, func) => {
                     let literalize_a = a.literalize(&caller_context);
                     let literalize_b = b.literalize(&caller_context);
 
@@ -148,13 +438,13 @@ impl Expresion {
                         }
                     }
                 }
-                Expresion::Literal(_) => unreachable!(),
-                Expresion::Var(VarName::Direct(name)) => {
+                ExpresionSemantics::Literal(_) => unreachable!(),
+                ExpresionSemantics::Var(VarName::Direct(name)) => {
                     let mut new_context = caller_context.to_owned();
                     new_context.set(name.to_owned(), goal.to_owned());
                     new_context
                 }
-                Expresion::Var(VarName::DestructuredArray(template_arr)) => {
+                ExpresionSemantics::Var(VarName::DestructuredArray(template_arr)) => {
                     if let Data::Array(goal_arr) = goal {
                         if goal_arr.len() < template_arr.len() {
                             return Err("unmatchable arrays".into());
@@ -165,9 +455,13 @@ impl Expresion {
 
                         for (i, array_position) in template_arr.iter().enumerate() {
                             last_i = i;
-                            if let Expresion::Var(VarName::ExplodeArray(x)) = array_position {
-                                match Expresion::Var(VarName::Direct(x.to_owned()))
-                                    .solve(&Data::Array(goal_arr[i..].to_vec()), &new_context)
+                            if let ExpresionSemantics::Var(VarName::ExplodeArray(x)) =
+                                &array_position.semantics
+                            {
+                                match Expresion::from(ExpresionSemantics::Var(VarName::Direct(
+                                    x.to_owned(),
+                                )))
+                                .solve(&Data::Array(goal_arr[i..].to_vec()), &new_context)
                                 {
                                     Ok(newer_context) => new_context = newer_context,
                                     Err(msg) => {
@@ -193,7 +487,66 @@ impl Expresion {
                         return Err("cant destructure a non array goal to an array".into());
                     }
                 }
-                Expresion::Var(VarName::ExplodeArray(_)) => unreachable!(),
+                ExpresionSemantics::Var(VarName::DestructuredRecord(fields, rest)) => {
+                    let goal_fields = match goal {
+                        Data::Record(goal_fields) => goal_fields,
+                        _ => return Err("cant destructure a non record goal to a record".into()),
+                    };
+
+                    let mut new_context = caller_context.to_owned();
+                    let mut rest_fields = goal_fields.to_owned();
+                    for (key, exp) in fields {
+                        match rest_fields.remove(key) {
+                            Some(value) => match exp.solve(&value, &new_context) {
+                                Ok(newer_context) => new_context = newer_context,
+                                Err(msg) => {
+                                    return Err(format!("at record key {key} error: {msg}"))
+                                }
+                            },
+                            None => return Err(format!("the record has no key {key}")),
+                        }
+                    }
+                    match rest {
+                        Some(rest) => Expresion::from(ExpresionSemantics::Var(VarName::Direct(
+                            rest.to_owned(),
+                        )))
+                        .solve(&Data::Record(rest_fields), &new_context)?,
+                        None if rest_fields.is_empty() => new_context,
+                        None => return Err("cant destructure a record with unmatching keys".into()),
+                    }
+                }
+                ExpresionSemantics::Function(call) => {
+                    let args = call
+                        .args
+                        .iter()
+                        .map(|arg| match arg.literalize(caller_context) {
+                            Ok(Data::Any) | Err(_) => None,
+                            Ok(data) => Some(data),
+                        })
+                        .collect::<Vec<Option<Data>>>();
+                    let unknowns = (0..args.len())
+                        .filter(|i| args[*i].is_none())
+                        .collect::<Vec<usize>>();
+
+                    match unknowns[..] {
+                        [unknown] => {
+                            let new_goal = call.function.solve_arg(&args, unknown, goal)?;
+                            call.args[unknown].solve(&new_goal, caller_context)?
+                        }
+                        _ => {
+                            return Err(format!(
+                                "{call} can only be solved with a single unknown argument"
+                            ))
+                        }
+                    }
+                }
+                ExpresionSemantics::Negation(exp) => {
+                    exp.solve(&negate_direct(goal.to_owned())?, caller_context)?
+                }
+                ExpresionSemantics::Var(VarName::ExplodeArray(_)) => unreachable!(),
+                ExpresionSemantics::Aggregate(_) => {
+                    return Err("aggregates must be resolved before solving".into())
+                }
             },
             Ok(d) => {
                 if d == goal.to_owned() {
@@ -210,6 +563,20 @@ impl Expresion {
     }
 }
 
+// negative literals are folded right away
+fn negate(exp: Expresion) -> Expresion {
+    let semantics = match &exp.semantics {
+        ExpresionSemantics::Literal(data) if data.is_number() => {
+            match negate_direct(data.to_owned()) {
+                Ok(data) => ExpresionSemantics::Literal(data),
+                Err(_) => ExpresionSemantics::Negation(Box::new(exp)),
+            }
+        }
+        _ => ExpresionSemantics::Negation(Box::new(exp)),
+    };
+    semantics.into()
+}
+
 pub fn read_expresion(
     lexograms: &Vec<lexer::Lexogram>,
     start_cursor: usize,
@@ -223,12 +590,25 @@ pub fn read_expresion(
         SpectingOperatorOrEnd,
         SpectingClosingParenthesis,
     }
+    impl ExpressionParserStates {
+        fn expected(&self) -> Vec<&'static str> {
+            match self {
+                SpectingItemOrOpenParenthesis => vec!["a value", "`(`"],
+                SpectingOperatorOrEnd => vec!["an operator"],
+                SpectingClosingParenthesis => vec!["`)`"],
+            }
+        }
+    }
     use ExpressionParserStates::*;
     let mut cursor = start_cursor;
     let mut state = SpectingItemOrOpenParenthesis;
 
-    let mut op_ret = None;
-    let mut append_mode: Option<Operation<Data, Data>> = None;
+    // the operands and the operations between them, folded by precedence once all are read
+    let mut operands = vec![];
+    let mut operations: Vec<Operation<Data, Data>> = vec![];
+    let mut negate_next = false;
+    // where the operand being read starts, the minus and parenthesis before it included
+    let mut operand_start = None;
 
     for (i, lex) in lexograms.iter().enumerate() {
         if cursor > i {
@@ -237,7 +617,7 @@ pub fn read_expresion(
 
         match (lex.l_type.to_owned(), state, only_literals.to_owned()) {
             (OpAdd, SpectingOperatorOrEnd, _) => {
-                append_mode = Some(Operation {
+                operations.push(Operation {
                     forward: add_direct,
                     reverse_op1: add_reverse_op1,
                     reverse_op2: add_reverse_op2,
@@ -246,7 +626,7 @@ pub fn read_expresion(
                 state = SpectingItemOrOpenParenthesis;
             }
             (OpSub, SpectingOperatorOrEnd, _) => {
-                append_mode = Some(Operation {
+                operations.push(Operation {
                     forward: substract_direct,
                     reverse_op1: substract_reverse_op1,
                     reverse_op2: substract_reverse_op2,
@@ -255,7 +635,7 @@ pub fn read_expresion(
                 state = SpectingItemOrOpenParenthesis;
             }
             (OpMul, SpectingOperatorOrEnd, _) => {
-                append_mode = Some(Operation {
+                operations.push(Operation {
                     forward: multiply_direct,
                     reverse_op1: multiply_reverse_op1,
                     reverse_op2: multiply_reverse_op2,
@@ -264,7 +644,7 @@ pub fn read_expresion(
                 state = SpectingItemOrOpenParenthesis;
             }
             (OpDiv, SpectingOperatorOrEnd, _) => {
-                append_mode = Some(Operation {
+                operations.push(Operation {
                     forward: divide_direct,
                     reverse_op1: divide_reverse_op1,
                     reverse_op2: divide_reverse_op2,
@@ -272,23 +652,54 @@ pub fn read_expresion(
                 });
                 state = SpectingItemOrOpenParenthesis;
             }
+            (OpMod, SpectingOperatorOrEnd, _) => {
+                operations.push(Operation {
+                    forward: modulo_direct,
+                    reverse_op1: not_reversible_op,
+                    reverse_op2: not_reversible_op,
+                    to_string: "%".into(),
+                });
+                state = SpectingItemOrOpenParenthesis;
+            }
+            (OpFloorDiv, SpectingOperatorOrEnd, _) => {
+                operations.push(Operation {
+                    forward: floor_divide_direct,
+                    reverse_op1: not_reversible_op,
+                    reverse_op2: not_reversible_op,
+                    to_string: "//".into(),
+                });
+                state = SpectingItemOrOpenParenthesis;
+            }
+            (OpPow, SpectingOperatorOrEnd, _) => {
+                operations.push(Operation {
+                    forward: power_direct,
+                    reverse_op1: power_reverse_op1,
+                    reverse_op2: power_reverse_op2,
+                    to_string: "^".into(),
+                });
+                state = SpectingItemOrOpenParenthesis;
+            }
+            // a minus before an item negates it, so x - -y reads too
+            (OpSub, SpectingItemOrOpenParenthesis, _) => {
+                operand_start.get_or_insert(lex.pos_s);
+                negate_next = !negate_next
+            }
             (LeftParenthesis, SpectingItemOrOpenParenthesis, _) => {
-                match read_expresion(lexograms, i, only_literals)? {
+                operand_start.get_or_insert(lex.pos_s);
+                match read_expresion(lexograms, i + 1, only_literals)? {
                     Ok((e, jump_to)) => {
+                        let e = if negate_next { negate(e) } else { e };
+                        negate_next = false;
                         cursor = jump_to;
-                        op_ret = Some(match (&append_mode, op_ret) {
-                            (Some(op), Some(ret)) => {
-                                Expresion::Arithmetic(Box::new(ret), Box::new(e), op.clone())
-                            }
-                            _ => e,
-                        });
-                        append_mode = None
+                        operands.push(e);
                     }
                     Err(e) => {
                         return Ok(Err(FailureExplanation {
                             lex_pos: i,
                             if_it_was: "expresion".into(),
-                            failed_because: "specting nested expresion".into(),
+                            failed_because: FailureReason::Other(
+                                "specting nested expresion".into(),
+                            ),
                             parent_failure: (vec![e]),
                         }))
                     }
@@ -297,25 +708,39 @@ pub fn read_expresion(
                 state = SpectingClosingParenthesis
             }
 
-            (RightParenthesis, SpectingClosingParenthesis, _) => state = SpectingOperatorOrEnd,
+            (RightParenthesis, SpectingClosingParenthesis, _) => {
+                if let (Some(operand), Some(start)) = (operands.last_mut(), operand_start.take()) {
+                    operand.span = Some(Span {
+                        start,
+                        end: lex.pos_f,
+                    });
+                }
+                state = SpectingOperatorOrEnd
+            }
 
             (_, SpectingItemOrOpenParenthesis, _) => {
                 match read_expresion_item(lexograms, i, only_literals)? {
                     Ok((e, jump_to)) => {
-                        cursor = jump_to;
-                        op_ret = Some(match (&append_mode, op_ret) {
-                            (Some(op), Some(ret)) => {
-                                Expresion::Arithmetic(Box::new(ret), Box::new(e), op.clone())
-                            }
-                            _ => e,
+                        let end = lexograms[jump_to - 1].pos_f;
+                        let e = e.with_span(Span {
+                            start: lex.pos_s,
+                            end,
                         });
-                        append_mode = None
+                        let e = if negate_next { negate(e) } else { e };
+                        negate_next = false;
+                        cursor = jump_to;
+                        operands.push(e.with_span(Span {
+                            start: operand_start.take().unwrap_or(lex.pos_s),
+                            end,
+                        }));
                     }
                     Err(e) => {
                         return Ok(Err(FailureExplanation {
                             lex_pos: i,
                             if_it_was: "expresion".into(),
-                            failed_because: format!("Specting expresion item").into(),
+                            failed_because: FailureReason::Other(
+                                format!("Specting expresion item").into(),
+                            ),
                             parent_failure: (vec![e]),
                         }))
                     }
@@ -324,29 +749,72 @@ pub fn read_expresion(
             }
 
             (_, SpectingOperatorOrEnd, _) => {
-                return Ok(Ok((op_ret.unwrap_or_else(|| unreachable!()), i)))
+                return Ok(Ok((fold_operations(operands, operations), i)))
             }
             _ => {
                 return Ok(Err(FailureExplanation {
                     lex_pos: i,
                     if_it_was: "expresion".into(),
-                    failed_because: format!("pattern missmatch on {:#?} state", state).into(),
+                    failed_because: FailureReason::Specting(state.expected()),
                     parent_failure: vec![],
                 }))
             }
         }
     }
-    match (state, op_ret) {
-        (SpectingOperatorOrEnd, Some(ret)) => Ok(Ok((ret, lexograms.len()))),
+    match state {
+        SpectingOperatorOrEnd => Ok(Ok((fold_operations(operands, operations), lexograms.len()))),
         _ => Ok(Err(FailureExplanation {
             lex_pos: lexograms.len() - 1,
             if_it_was: "expresion".into(),
-            failed_because: "file ended".into(),
+            failed_because: FailureReason::FileEnded,
             parent_failure: vec![],
         })),
     }
 }
 
+// there is always one operand more than operations
+fn fold_operations(operands: Vec<Expresion>, operations: Vec<Operation<Data, Data>>) -> Expresion {
+    let mut operands = operands.into_iter();
+    let mut output = vec![operands.next().unwrap_or_else(|| unreachable!())];
+    let mut pending: Vec<Operation<Data, Data>> = vec![];
+
+    let reduce = |output: &mut Vec<Expresion>, op: Operation<Data, Data>| {
+        let b = output.pop().unwrap_or_else(|| unreachable!());
+        let a = output.pop().unwrap_or_else(|| unreachable!());
+        // an operation spans from its first operand to its last
+        let span = match (a.span, b.span) {
+            (Some(a_span), Some(b_span)) => Some(Span {
+                start: a_span.start,
+                end: b_span.end,
+            }),
+            _ => None,
+        };
+        output.push(Expresion {
+            semantics: ExpresionSemantics::Arithmetic(Box::new(a), Box::new(b), op),
+            span,
+        });
+    };
+
+    for (op, operand) in operations.into_iter().zip(operands) {
+        while let Some(top) = pending.last() {
+            if top.precedence() > op.precedence()
+                || (top.precedence() == op.precedence() && !op.is_right_associative())
+            {
+                let top = pending.pop().unwrap_or_else(|| unreachable!());
+                reduce(&mut output, top);
+            } else {
+                break;
+            }
+        }
+        pending.push(op);
+        output.push(operand);
+    }
+    while let Some(op) = pending.pop() {
+        reduce(&mut output, op);
+    }
+    output.pop().unwrap_or_else(|| unreachable!())
+}
+
 pub fn read_expresion_item(
     lexograms: &Vec<lexer::Lexogram>,
     start_cursor: usize,
@@ -355,29 +823,86 @@ pub fn read_expresion_item(
     printparse!("read_item at {}", start_cursor);
 
     match (lexograms[start_cursor].l_type.clone(), only_literals) {
-        (Identifier(str), false) => {
-            Ok(Ok((Expresion::Var(VarName::Direct(str)), start_cursor + 1)))
+        (Identifier(str), false)
+            if AggregateFunction::from_name(&str).is_some()
+                && matches!(
+                    lexograms.get(start_cursor + 1),
+                    Some(lexer::Lexogram {
+                        l_type: LeftParenthesis,
+                        ..
+                    })
+                ) =>
+        {
+            match read_aggregate(lexograms, start_cursor)? {
+                Ok((aggregate, jump_to)) => Ok(Ok((
+                    ExpresionSemantics::Aggregate(aggregate).into(),
+                    jump_to,
+                ))),
+                Err(e) => Ok(Err(FailureExplanation {
+                    lex_pos: start_cursor,
+                    if_it_was: "expresion_item".into(),
+                    failed_because: FailureReason::Other("specting aggregate".into()),
+                    parent_failure: vec![e],
+                })),
+            }
+        }
+        (Identifier(str), false)
+            if BuiltInFunction::from_name(&str).is_some()
+                && matches!(
+                    lexograms.get(start_cursor + 1),
+                    Some(lexer::Lexogram {
+                        l_type: LeftParenthesis,
+                        ..
+                    })
+                ) =>
+        {
+            match read_function_call(lexograms, start_cursor)? {
+                Ok((call, jump_to)) => Ok(Ok((ExpresionSemantics::Function(call).into(), jump_to))),
+                Err(e) => Ok(Err(FailureExplanation {
+                    lex_pos: start_cursor,
+                    if_it_was: "expresion_item".into(),
+                    failed_because: FailureReason::Other("specting function call".into()),
+                    parent_failure: vec![e],
+                })),
+            }
         }
+        (Identifier(str), false) => Ok(Ok((
+            ExpresionSemantics::Var(VarName::Direct(str)).into(),
+            start_cursor + 1,
+        ))),
+        (LeftKey, false) => match read_data(lexograms, start_cursor)? {
+            Ok((ret, jump_to)) => Ok(Ok((ExpresionSemantics::Literal(ret).into(), jump_to))),
+            Err(a) => match read_destructuring_record(lexograms, start_cursor)? {
+                Ok((ret, jump_to)) => Ok(Ok((ExpresionSemantics::Var(ret).into(), jump_to))),
+
+                Err(b) => Ok(Err(FailureExplanation {
+                    lex_pos: start_cursor,
+                    if_it_was: "expresion_item".into(),
+                    failed_because: FailureReason::Other("specting some record".into()),
+                    parent_failure: vec![a, b],
+                })),
+            },
+        },
         (LeftBracket, false) => match read_data(lexograms, start_cursor)? {
-            Ok((ret, jump_to)) => Ok(Ok((Expresion::Literal(ret), jump_to))),
+            Ok((ret, jump_to)) => Ok(Ok((ExpresionSemantics::Literal(ret).into(), jump_to))),
             Err(a) => match read_destructuring_array(lexograms, start_cursor)? {
-                Ok((ret, jump_to)) => Ok(Ok((Expresion::Var(ret), jump_to))),
+                Ok((ret, jump_to)) => Ok(Ok((ExpresionSemantics::Var(ret).into(), jump_to))),
 
                 Err(b) => Ok(Err(FailureExplanation {
                     lex_pos: start_cursor,
                     if_it_was: "expresion_item".into(),
-                    failed_because: "specting some array".into(),
+                    failed_because: FailureReason::Other("specting some array".into()),
                     parent_failure: vec![a, b],
                 })),
             },
         },
 
         (_, _) => match read_data(lexograms, start_cursor)? {
-            Ok((value, jump_to)) => Ok(Ok((Expresion::Literal(value), jump_to))),
+            Ok((value, jump_to)) => Ok(Ok((ExpresionSemantics::Literal(value).into(), jump_to))),
             Err(err) => Ok(Err(FailureExplanation {
                 lex_pos: start_cursor,
                 if_it_was: "expresion_item".into(),
-                failed_because: "specting some array".into(),
+                failed_because: FailureReason::Other("specting some array".into()),
                 parent_failure: vec![err],
             })),
         },
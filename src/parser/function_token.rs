@@ -0,0 +1,225 @@
+use std::fmt;
+
+use print_macros::*;
+
+use crate::engine::operations::*;
+use crate::lexer::{self, LexogramType::*};
+
+use super::data_token::Data;
+use super::error::{FailureExplanation, FailureReason, ParserError};
+use super::expresion_token::{read_expresion, Expresion};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum BuiltInFunction {
+    Len,
+    Upper,
+    Lower,
+    Substr,
+    Split,
+    Concat,
+    StartsWith,
+    Contains,
+}
+
+impl BuiltInFunction {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "len" => Some(BuiltInFunction::Len),
+            "upper" => Some(BuiltInFunction::Upper),
+            "lower" => Some(BuiltInFunction::Lower),
+            "substr" => Some(BuiltInFunction::Substr),
+            "split" => Some(BuiltInFunction::Split),
+            "concat" => Some(BuiltInFunction::Concat),
+            "starts_with" => Some(BuiltInFunction::StartsWith),
+            "contains" => Some(BuiltInFunction::Contains),
+            _ => None,
+        }
+    }
+
+    // the ones giving a bool, which stand as statements on their own
+    pub fn is_predicate(&self) -> bool {
+        matches!(
+            self,
+            BuiltInFunction::StartsWith | BuiltInFunction::Contains
+        )
+    }
+
+    // the least and most arguments it takes, concat takes as many as wanted
+    pub fn get_arity(&self) -> (usize, Option<usize>) {
+        match self {
+            BuiltInFunction::Len | BuiltInFunction::Upper | BuiltInFunction::Lower => (1, Some(1)),
+            BuiltInFunction::Substr => (3, Some(3)),
+            BuiltInFunction::Split | BuiltInFunction::StartsWith | BuiltInFunction::Contains => {
+                (2, Some(2))
+            }
+            BuiltInFunction::Concat => (2, None),
+        }
+    }
+
+    pub fn apply(&self, args: &[Data]) -> Result<Data, String> {
+        match self {
+            BuiltInFunction::Len => len_direct(args),
+            BuiltInFunction::Upper => upper_direct(args),
+            BuiltInFunction::Lower => lower_direct(args),
+            BuiltInFunction::Substr => substr_direct(args),
+            BuiltInFunction::Split => split_direct(args),
+            BuiltInFunction::Concat => concat_direct(args),
+            BuiltInFunction::StartsWith => starts_with_direct(args),
+            BuiltInFunction::Contains => contains_direct(args),
+        }
+    }
+
+    // the value the unknown argument must take for the function to give the goal, the rest of
+    // the arguments being known
+    pub fn solve_arg(
+        &self,
+        args: &[Option<Data>],
+        unknown: usize,
+        goal: &Data,
+    ) -> Result<Data, String> {
+        match self {
+            BuiltInFunction::Split => split_reverse(args, unknown, goal),
+            BuiltInFunction::Concat => concat_reverse(args, unknown, goal),
+            _ => Err(format!("{self} cant be solved for its arguments")),
+        }
+    }
+}
+
+impl fmt::Display for BuiltInFunction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BuiltInFunction::Len => write!(f, "len"),
+            BuiltInFunction::Upper => write!(f, "upper"),
+            BuiltInFunction::Lower => write!(f, "lower"),
+            BuiltInFunction::Substr => write!(f, "substr"),
+            BuiltInFunction::Split => write!(f, "split"),
+            BuiltInFunction::Concat => write!(f, "concat"),
+            BuiltInFunction::StartsWith => write!(f, "starts_with"),
+            BuiltInFunction::Contains => write!(f, "contains"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct FunctionCall {
+    pub function: BuiltInFunction,
+    pub args: Vec<Expresion>,
+}
+
+impl fmt::Display for FunctionCall {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let args = self
+            .args
+            .iter()
+            .map(|arg| format!("{arg}"))
+            .collect::<Vec<String>>()
+            .join(",");
+        write!(f, "{}({args})", self.function)
+    }
+}
+
+pub fn read_function_call(
+    lexograms: &Vec<lexer::Lexogram>,
+    start_cursor: usize,
+) -> Result<Result<(FunctionCall, usize), FailureExplanation>, ParserError> {
+    #[derive(Debug, Clone, Copy)]
+    enum FunctionParserStates {
+        SpectingFunction,
+        SpectingOpenParenthesis,
+        SpectingArgument,
+        SpectingComaOrClosingParenthesis,
+    }
+    impl FunctionParserStates {
+        fn expected(&self) -> Vec<&'static str> {
+            match self {
+                SpectingFunction => vec!["a function"],
+                SpectingOpenParenthesis => vec!["`(`"],
+                SpectingArgument => vec!["an argument"],
+                SpectingComaOrClosingParenthesis => vec!["`,`", "`)`"],
+            }
+        }
+    }
+    use FunctionParserStates::*;
+
+    printparse!("read_function_call at {}", start_cursor);
+
+    let mut cursor = start_cursor;
+    let mut state = SpectingFunction;
+
+    let mut op_function = None;
+    let mut args = vec![];
+
+    for (i, lex) in lexograms.iter().enumerate() {
+        if cursor > i {
+            continue;
+        }
+        match (lex.l_type.to_owned(), state) {
+            (Identifier(name), SpectingFunction) => match BuiltInFunction::from_name(&name) {
+                Some(function) => {
+                    op_function = Some(function);
+                    state = SpectingOpenParenthesis;
+                }
+                None => {
+                    return Ok(Err(FailureExplanation {
+                        lex_pos: i,
+                        if_it_was: "function call".into(),
+                        failed_because: FailureReason::Other(format!(
+                            "{name} is not a built in function"
+                        )),
+                        parent_failure: vec![],
+                    }))
+                }
+            },
+            (LeftParenthesis, SpectingOpenParenthesis) => state = SpectingArgument,
+            (_, SpectingArgument) => match read_expresion(lexograms, i, false)? {
+                Ok((arg, jump_to)) => {
+                    cursor = jump_to;
+                    args.push(arg);
+                    state = SpectingComaOrClosingParenthesis;
+                }
+                Err(e) => {
+                    return Ok(Err(FailureExplanation {
+                        lex_pos: i,
+                        if_it_was: "function call".into(),
+                        failed_because: FailureReason::Other("specting argument".into()),
+                        parent_failure: vec![e],
+                    }))
+                }
+            },
+            (Coma, SpectingComaOrClosingParenthesis) => state = SpectingArgument,
+            (RightParenthesis, SpectingComaOrClosingParenthesis) => {
+                let function = match op_function {
+                    Some(function) => function,
+                    None => unreachable!(),
+                };
+                let (least, most) = function.get_arity();
+                if args.len() < least || most.is_some_and(|most| args.len() > most) {
+                    return Ok(Err(FailureExplanation {
+                        lex_pos: i,
+                        if_it_was: "function call".into(),
+                        failed_because: FailureReason::Other(format!(
+                            "{function} cant take {} arguments",
+                            args.len()
+                        )),
+                        parent_failure: vec![],
+                    }));
+                }
+                return Ok(Ok((FunctionCall { function, args }, i + 1)));
+            }
+            _ => {
+                return Ok(Err(FailureExplanation {
+                    lex_pos: i,
+                    if_it_was: "function call".into(),
+                    failed_because: FailureReason::Specting(state.expected()),
+                    parent_failure: vec![],
+                }))
+            }
+        }
+    }
+    Ok(Err(FailureExplanation {
+        lex_pos: lexograms.len() - 1,
+        if_it_was: "function call".into(),
+        failed_because: FailureReason::FileEnded,
+        parent_failure: vec![],
+    }))
+}
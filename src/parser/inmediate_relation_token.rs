@@ -3,6 +3,7 @@ use std::fmt;
 use print_macros::*;
 
 use crate::{
+    diagnostic::Span,
     engine::{var_context::VarContext, RelId},
     lexer::{self, LexogramType::*},
     parser::list_token::read_list,
@@ -10,15 +11,25 @@ use crate::{
 
 use super::{
     data_token::Data,
-    error::{FailureExplanation, ParserError},
+    error::{FailureExplanation, FailureReason, ParserError},
+    expresion_token::Expresion,
     HasRelId,
 };
 
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Debug, Clone, Eq)]
 pub struct InmediateRelation {
     pub negated: bool,
     pub rel_name: String,
     pub args: Vec<Data>,
+    // where it and each of its args were read from, relations made up by the engine have none
+    pub span: Option<Span>,
+    pub arg_spans: Vec<Span>,
+}
+
+impl PartialEq for InmediateRelation {
+    fn eq(&self, other: &Self) -> bool {
+        self.negated == other.negated && self.rel_name == other.rel_name && self.args == other.args
+    }
 }
 
 impl fmt::Display for InmediateRelation {
@@ -27,7 +38,7 @@ impl fmt::Display for InmediateRelation {
 
         args += &"(";
         for (i, d) in self.args.iter().enumerate() {
-            args += &format!("{d}");
+            args += &d.to_string();
             if i != self.args.len() - 1 {
                 args += &",";
             }
@@ -62,6 +73,17 @@ pub fn read_inmediate_relation(
         SpectingStatementIdentifier,
         SpectingStatementList,
     }
+    impl RelationParserStates {
+        fn expected(&self) -> Vec<&'static str> {
+            match self {
+                RelationParserStates::SpectingStatementIdentifierOrNegation => {
+                    vec!["a relation name", "`!`"]
+                }
+                SpectingStatementIdentifier => vec!["a relation name"],
+                SpectingStatementList => vec!["`(`"],
+            }
+        }
+    }
     use RelationParserStates::*;
 
     printparse!("read_inmediate_relation at {}", start_cursor);
@@ -93,13 +115,13 @@ pub fn read_inmediate_relation(
                     (Err(e), _) => Ok(Err(FailureExplanation {
                         lex_pos: i,
                         if_it_was: "inmediate relation".into(),
-                        failed_because: "specting list".into(),
+                        failed_because: FailureReason::Other("specting list".into()),
                         parent_failure: (vec![e]),
                     })),
                     (Ok((args, new_cursor)), Some(rel_name)) => {
                         let mut literal_vec = vec![];
 
-                        for exp in args {
+                        for exp in &args {
                             literal_vec.push(exp.literalize(&VarContext::new())?);
                         }
 
@@ -108,6 +130,11 @@ pub fn read_inmediate_relation(
                                 args: literal_vec,
                                 negated,
                                 rel_name,
+                                span: Some(Span {
+                                    start: lexograms[start_cursor].pos_s,
+                                    end: lexograms[new_cursor - 1].pos_f,
+                                }),
+                                arg_spans: args.iter().flat_map(Expresion::get_span).collect(),
                             },
                             new_cursor,
                         )))
@@ -119,7 +146,7 @@ pub fn read_inmediate_relation(
                 return Ok(Err(FailureExplanation {
                     lex_pos: i,
                     if_it_was: "inmediate relation".into(),
-                    failed_because: format!("pattern missmatch on {:#?} state", state).into(),
+                    failed_because: FailureReason::Specting(state.expected()),
                     parent_failure: vec![],
                 }))
             }
@@ -128,7 +155,7 @@ pub fn read_inmediate_relation(
     Ok(Err(FailureExplanation {
         lex_pos: lexograms.len() - 1,
         if_it_was: "inmediate relation".into(),
-        failed_because: "file ended".into(),
+        failed_because: FailureReason::FileEnded,
         parent_failure: vec![],
     }))
 }
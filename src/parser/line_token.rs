@@ -4,14 +4,35 @@ use super::{
     assumption_token::{read_assumption, Assumption},
     defered_relation_token::{read_defered_relation, DeferedRelation},
     error::*,
+    transaction_token::{read_transaction, Transaction},
 };
+use crate::diagnostic::Span;
 use crate::lexer::{self, LexogramType};
 
+// the text after a # up to the end of its source line
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Comment {
+    pub text: String,
+    pub span: Span,
+}
+
+impl fmt::Display for Comment {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.text.as_str() {
+            "" => write!(f, "#"),
+            text => write!(f, "# {text}"),
+        }
+    }
+}
+
 #[derive(Debug, Clone)]
 pub enum Line {
     Assumption(Assumption),
     Query(DeferedRelation),
-    Comment(Box<Line>),
+    Why(DeferedRelation),
+    Transaction(Transaction),
+    // a comment that doesnt go with any line
+    Comment(Comment),
 }
 
 impl fmt::Display for Line {
@@ -19,7 +40,21 @@ impl fmt::Display for Line {
         match self {
             Line::Assumption(ass) => write!(f, "{ass}"),
             Line::Query(que) => write!(f, "{que}"),
-            Line::Comment(line) => write!(f, "#{line}"),
+            Line::Why(que) => write!(f, "{que}??"),
+            Line::Transaction(transaction) => write!(f, "{transaction}"),
+            Line::Comment(comment) => write!(f, "{comment}"),
+        }
+    }
+}
+
+impl Line {
+    // only the body of a rule holds comments, anything else gives them back
+    pub fn attach_comment(&mut self, comment: Comment) -> Result<(), Comment> {
+        match self {
+            Line::Assumption(Assumption::Conditional(cond)) => {
+                cond.conditional.attach_comment(comment)
+            }
+            _ => Err(comment),
         }
     }
 }
@@ -28,35 +63,35 @@ pub fn read_line(
     lexograms: &Vec<lexer::Lexogram>,
     start_cursor: usize,
 ) -> Result<Result<(Line, usize), FailureExplanation>, ParserError> {
-    if let LexogramType::Comment = lexograms[start_cursor].l_type {
-        match read_line(lexograms, start_cursor + 1)? {
-            Ok((line, jump_to)) => return Ok(Ok((Line::Comment(Box::new(line)), jump_to))),
-            Err(e) => {
-                return Ok(Err(FailureExplanation {
-                    lex_pos: start_cursor,
-                    if_it_was: "comment".into(),
-                    failed_because: "a valid line wasnt found".into(),
-                    parent_failure: vec![e],
-                }))
-            }
-        }
-    } else {
-        let a;
-        let b;
+    let a;
+    let b;
 
-        match read_defered_relation(lexograms, start_cursor, true)? {
-            Ok((defered_rel, jump_to)) => return Ok(Ok((Line::Query(defered_rel), jump_to))),
-            Err(e) => a = e,
-        }
-        match read_assumption(lexograms, start_cursor)? {
-            Ok((defered_rel, jump_to)) => return Ok(Ok((Line::Assumption(defered_rel), jump_to))),
-            Err(e) => b = e,
+    if let Ok((transaction, jump_to)) = read_transaction(lexograms, start_cursor)? {
+        return Ok(Ok((Line::Transaction(transaction), jump_to)));
+    }
+    match read_defered_relation(lexograms, start_cursor, true)? {
+        Ok((defered_rel, jump_to)) => return Ok(Ok((Line::Query(defered_rel), jump_to))),
+        Err(e) => a = e,
+    }
+    if let Ok((defered_rel, jump_to)) = read_defered_relation(lexograms, start_cursor, false)? {
+        if let Some(lexer::Lexogram {
+            l_type: LexogramType::Why,
+            ..
+        }) = lexograms.get(jump_to)
+        {
+            return Ok(Ok((Line::Why(defered_rel), jump_to + 1)));
         }
-        Ok(Err(FailureExplanation {
-            lex_pos: start_cursor,
-            if_it_was: "line".into(),
-            failed_because: "wasnt neither an extensional nor an intensional statement".into(),
-            parent_failure: (vec![a, b]),
-        }))
     }
+    match read_assumption(lexograms, start_cursor)? {
+        Ok((defered_rel, jump_to)) => return Ok(Ok((Line::Assumption(defered_rel), jump_to))),
+        Err(e) => b = e,
+    }
+    Ok(Err(FailureExplanation {
+        lex_pos: start_cursor,
+        if_it_was: "line".into(),
+        failed_because: FailureReason::Other(
+            "wasnt neither an extensional nor an intensional statement".into(),
+        ),
+        parent_failure: (vec![a, b]),
+    }))
 }
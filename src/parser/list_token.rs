@@ -7,7 +7,10 @@ use crate::{
     parser::{expresion_token::read_expresion, FailureExplanation},
 };
 
-use super::{error::ParserError, expresion_token::Expresion};
+use super::{
+    error::{FailureReason, ParserError},
+    expresion_token::Expresion,
+};
 
 pub fn read_list(
     lexograms: &Vec<lexer::Lexogram>,
@@ -20,6 +23,15 @@ pub fn read_list(
         SpectingComaOrClosingParenthesis,
         SpectingOpenParenthesis,
     }
+    impl ListParserStates {
+        fn expected(&self) -> Vec<&'static str> {
+            match self {
+                SpectingItem => vec!["an item"],
+                SpectingComaOrClosingParenthesis => vec!["`,`", "`)`"],
+                SpectingOpenParenthesis => vec!["`(`"],
+            }
+        }
+    }
 
     printparse!("read_list at {}", start_cursor);
 
@@ -49,7 +61,7 @@ pub fn read_list(
                         return Ok(Err(FailureExplanation {
                             lex_pos: i,
                             if_it_was: "list".into(),
-                            failed_because: "Specting item".into(),
+                            failed_because: FailureReason::Other("Specting item".into()),
                             parent_failure: (vec![e]),
                         }))
                     }
@@ -64,7 +76,7 @@ pub fn read_list(
                 return Ok(Err(FailureExplanation {
                     lex_pos: i,
                     if_it_was: "list".into(),
-                    failed_because: format!("pattern missmatch on {:#?} state", state).into(),
+                    failed_because: FailureReason::Specting(state.expected()),
                     parent_failure: vec![],
                 }));
             }
@@ -73,7 +85,7 @@ pub fn read_list(
     return Ok(Err(FailureExplanation {
         lex_pos: lexograms.len() - 1,
         if_it_was: "list".into(),
-        failed_because: "file ended".into(),
+        failed_because: FailureReason::FileEnded,
         parent_failure: vec![],
     }));
 }
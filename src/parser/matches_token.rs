@@ -0,0 +1,201 @@
+use std::collections::{BTreeSet, HashMap};
+use std::fmt;
+
+use print_macros::*;
+use regex::Regex;
+
+use crate::engine::var_context::VarContext;
+use crate::lexer::{self, LexogramType::*};
+
+use super::data_token::Data;
+use super::error::{FailureExplanation, FailureReason, ParserError};
+use super::expresion_token::{read_expresion, Expresion, ExpresionSemantics};
+
+// matches(text, pattern) holds when the pattern is found on the text, and an optional third
+// argument gets the capture groups as an array, null standing for the groups that didnt take part
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct RegexMatch {
+    pub text: Expresion,
+    pub pattern: Expresion,
+    pub captures: Option<Expresion>,
+}
+
+impl fmt::Display for RegexMatch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.captures {
+            Some(captures) => write!(f, "matches({},{},{captures})", self.text, self.pattern),
+            None => write!(f, "matches({},{})", self.text, self.pattern),
+        }
+    }
+}
+
+impl RegexMatch {
+    pub fn get_vars(&self) -> BTreeSet<String> {
+        let mut ret = self.text.get_vars();
+        ret.extend(self.pattern.get_vars());
+        if let Some(captures) = &self.captures {
+            ret.extend(captures.get_vars());
+        }
+        ret
+    }
+
+    // the variables that must be bound before matching, the captures get bound by it
+    pub fn get_needed_vars(&self) -> BTreeSet<String> {
+        let mut ret = self.text.get_vars();
+        ret.extend(self.pattern.get_vars());
+        ret
+    }
+
+    // the context with the captures bound, None if the text doesnt match, the compiled patterns
+    // are kept on the cache so each one is compiled once per evaluation
+    pub fn fit(
+        &self,
+        context: &VarContext,
+        cache: &mut HashMap<String, Regex>,
+    ) -> Result<Option<VarContext>, String> {
+        let text = match self.text.literalize(context) {
+            Ok(Data::String(text)) => text,
+            _ => return Ok(None),
+        };
+        let pattern = match self.pattern.literalize(context) {
+            Ok(Data::String(pattern)) => pattern,
+            Ok(data) => return Err(format!("the pattern of {self} is {data}, not a string")),
+            Err(_) => return Ok(None),
+        };
+        if !cache.contains_key(&pattern) {
+            let regex =
+                Regex::new(&pattern).map_err(|err| format!("invalid pattern on {self}: {err}"))?;
+            cache.insert(pattern.to_owned(), regex);
+        }
+
+        let groups = match cache[&pattern].captures(&text) {
+            Some(groups) => groups,
+            None => return Ok(None),
+        };
+        match &self.captures {
+            Some(captures) => {
+                let captured = groups
+                    .iter()
+                    .skip(1)
+                    .map(|group| match group {
+                        Some(group) => Data::String(group.as_str().to_owned()),
+                        None => Data::Null,
+                    })
+                    .collect();
+                Ok(captures.solve(&Data::Array(captured), context).ok())
+            }
+            None => Ok(Some(context.to_owned())),
+        }
+    }
+}
+
+pub fn read_matches(
+    lexograms: &Vec<lexer::Lexogram>,
+    start_cursor: usize,
+) -> Result<Result<(RegexMatch, usize), FailureExplanation>, ParserError> {
+    #[derive(Debug, Clone, Copy)]
+    enum MatchesParserStates {
+        SpectingMatchesKeyword,
+        SpectingOpenParenthesis,
+        SpectingArgument,
+        SpectingComaOrClosingParenthesis,
+    }
+    impl MatchesParserStates {
+        fn expected(&self) -> Vec<&'static str> {
+            match self {
+                SpectingMatchesKeyword => vec!["`matches`"],
+                SpectingOpenParenthesis => vec!["`(`"],
+                SpectingArgument => vec!["an argument"],
+                SpectingComaOrClosingParenthesis => vec!["`,`", "`)`"],
+            }
+        }
+    }
+    use MatchesParserStates::*;
+
+    printparse!("read_matches at {}", start_cursor);
+
+    let mut cursor = start_cursor;
+    let mut state = SpectingMatchesKeyword;
+
+    let mut args = vec![];
+
+    for (i, lex) in lexograms.iter().enumerate() {
+        if cursor > i {
+            continue;
+        }
+        match (lex.l_type.to_owned(), state) {
+            (Identifier(keyword), SpectingMatchesKeyword) if keyword == "matches" => {
+                state = SpectingOpenParenthesis
+            }
+            (LeftParenthesis, SpectingOpenParenthesis) => state = SpectingArgument,
+            (_, SpectingArgument) => match read_expresion(lexograms, i, false)? {
+                Ok((arg, jump_to)) => {
+                    cursor = jump_to;
+                    args.push(arg);
+                    state = SpectingComaOrClosingParenthesis;
+                }
+                Err(e) => {
+                    return Ok(Err(FailureExplanation {
+                        lex_pos: i,
+                        if_it_was: "matches".into(),
+                        failed_because: FailureReason::Other("specting argument".into()),
+                        parent_failure: vec![e],
+                    }))
+                }
+            },
+            (Coma, SpectingComaOrClosingParenthesis) => state = SpectingArgument,
+            (RightParenthesis, SpectingComaOrClosingParenthesis) => {
+                // a literal pattern can be checked before running anything
+                if let [_, pattern] | [_, pattern, _] = &args[..] {
+                    if let ExpresionSemantics::Literal(Data::String(pattern)) =
+                        pattern.get_semantics()
+                    {
+                        if Regex::new(pattern).is_err() {
+                            return Ok(Err(FailureExplanation {
+                                lex_pos: i,
+                                if_it_was: "matches".into(),
+                                failed_because: FailureReason::Other(format!(
+                                    "{pattern:?} is not a valid pattern"
+                                )),
+                                parent_failure: vec![],
+                            }));
+                        }
+                    }
+                }
+                let mut args = args.into_iter();
+                return match (args.next(), args.next(), args.next(), args.next()) {
+                    (Some(text), Some(pattern), captures, None) => Ok(Ok((
+                        RegexMatch {
+                            text,
+                            pattern,
+                            captures,
+                        },
+                        i + 1,
+                    ))),
+                    _ => Ok(Err(FailureExplanation {
+                        lex_pos: i,
+                        if_it_was: "matches".into(),
+                        failed_because: FailureReason::Other(
+                            "matches takes a text, a pattern and optionaly the captures".into(),
+                        ),
+                        parent_failure: vec![],
+                    })),
+                };
+            }
+            _ => {
+                return Ok(Err(FailureExplanation {
+                    lex_pos: i,
+                    if_it_was: "matches".into(),
+                    failed_because: FailureReason::Specting(state.expected()),
+                    parent_failure: vec![],
+                }))
+            }
+        }
+    }
+    Ok(Err(FailureExplanation {
+        lex_pos: lexograms.len() - 1,
+        if_it_was: "matches".into(),
+        failed_because: FailureReason::FileEnded,
+        parent_failure: vec![],
+    }))
+}
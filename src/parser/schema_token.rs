@@ -0,0 +1,341 @@
+use core::fmt;
+use std::hash::{Hash, Hasher};
+
+use print_macros::*;
+
+use crate::{
+    diagnostic::Span,
+    engine::RelId,
+    lexer::{self, LexogramType::*},
+};
+
+use super::{
+    data_token::Data,
+    defered_relation_token::DeferedRelation,
+    error::{FailureExplanation, FailureReason, ParserError},
+    expresion_token::{ExpresionSemantics, VarName},
+    HasRelId,
+};
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum ColumnType {
+    Number,
+    String,
+    Bool,
+    Array(Box<ColumnType>),
+    Record,
+    Any,
+}
+
+impl fmt::Display for ColumnType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ColumnType::Number => write!(f, "number"),
+            ColumnType::String => write!(f, "string"),
+            ColumnType::Bool => write!(f, "bool"),
+            ColumnType::Array(item) => write!(f, "[{item}]"),
+            ColumnType::Record => write!(f, "record"),
+            ColumnType::Any => write!(f, "any"),
+        }
+    }
+}
+
+impl ColumnType {
+    // arrays mixing types just hold any, and null says nothing about the type of its column
+    pub fn of(data: &Data) -> ColumnType {
+        match data {
+            Data::Integer(_) | Data::Decimal(_) | Data::Number(_) => ColumnType::Number,
+            Data::String(_) => ColumnType::String,
+            Data::Bool(_) => ColumnType::Bool,
+            Data::Array(arr) => ColumnType::Array(Box::new(
+                arr.iter()
+                    .try_fold(ColumnType::Any, |acc, data| {
+                        acc.unify(&ColumnType::of(data))
+                    })
+                    .unwrap_or(ColumnType::Any),
+            )),
+            Data::Record(_) => ColumnType::Record,
+            Data::Null | Data::Any => ColumnType::Any,
+        }
+    }
+
+    // the most specific type fitting both, if there is any
+    pub fn unify(&self, other: &ColumnType) -> Option<ColumnType> {
+        match (self, other) {
+            (ColumnType::Any, column_type) | (column_type, ColumnType::Any) => {
+                Some(column_type.to_owned())
+            }
+            (ColumnType::Array(item_a), ColumnType::Array(item_b)) => {
+                Some(ColumnType::Array(Box::new(item_a.unify(item_b)?)))
+            }
+            (column_type_a, column_type_b) if column_type_a == column_type_b => {
+                Some(column_type_a.to_owned())
+            }
+            _ => None,
+        }
+    }
+
+    // _ stands for an unknown value and every column is nullable, so both fit any type
+    pub fn admits(&self, data: &Data) -> bool {
+        match (self, data) {
+            (ColumnType::Any, _) | (_, Data::Any | Data::Null) => true,
+            (ColumnType::Number, data) if data.is_number() => true,
+            (ColumnType::String, Data::String(_)) => true,
+            (ColumnType::Bool, Data::Bool(_)) => true,
+            (ColumnType::Record, Data::Record(_)) => true,
+            (ColumnType::Array(item), Data::Array(arr)) => arr.iter().all(|data| item.admits(data)),
+            _ => false,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Eq)]
+pub struct Schema {
+    pub rel_name: String,
+    pub columns: Vec<(String, ColumnType)>,
+    // where it was read from
+    pub span: Option<Span>,
+}
+
+impl PartialEq for Schema {
+    fn eq(&self, other: &Self) -> bool {
+        self.rel_name == other.rel_name && self.columns == other.columns
+    }
+}
+
+impl Hash for Schema {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.rel_name.hash(state);
+        self.columns.hash(state);
+    }
+}
+
+impl fmt::Display for Schema {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let columns = self
+            .columns
+            .iter()
+            .map(|(name, column_type)| format!("{name}: {column_type}"))
+            .collect::<Vec<String>>()
+            .join(", ");
+
+        write!(f, "schema {}({columns})", self.rel_name)
+    }
+}
+
+impl HasRelId for Schema {
+    fn get_rel_id(&self) -> RelId {
+        RelId {
+            identifier: self.rel_name.to_owned(),
+            column_count: self.columns.len(),
+        }
+    }
+}
+
+impl Schema {
+    pub fn check_truth(&self, args: &[Data]) -> Result<(), String> {
+        for (i, ((name, column_type), data)) in self.columns.iter().zip(args).enumerate() {
+            if !column_type.admits(data) {
+                return Err(format!(
+                    "column {} ({name}) of {} expects {column_type}, found {}",
+                    i + 1,
+                    self.rel_name,
+                    data.to_string()
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    // variables only get a type once the rule is evaluated, so just literals and destructured
+    // arrays and records are checked on the template
+    pub fn check_template(&self, template: &DeferedRelation) -> Result<(), String> {
+        for (i, ((name, column_type), arg)) in self.columns.iter().zip(&template.args).enumerate() {
+            let fits = match (arg.get_semantics(), column_type) {
+                (ExpresionSemantics::Literal(data), _) => column_type.admits(data),
+                (
+                    ExpresionSemantics::Var(VarName::DestructuredArray(_)),
+                    ColumnType::Number | ColumnType::String | ColumnType::Bool | ColumnType::Record,
+                ) => false,
+                (
+                    ExpresionSemantics::Var(VarName::DestructuredRecord(_, _)),
+                    ColumnType::Number
+                    | ColumnType::String
+                    | ColumnType::Bool
+                    | ColumnType::Array(_),
+                ) => false,
+                _ => true,
+            };
+            if !fits {
+                return Err(format!(
+                    "column {} ({name}) of {} expects {column_type}, found {arg} on the rule head {template}",
+                    i + 1,
+                    self.rel_name,
+                ));
+            }
+        }
+        Ok(())
+    }
+}
+
+pub fn read_column_type(
+    lexograms: &Vec<lexer::Lexogram>,
+    start_cursor: usize,
+) -> Result<Result<(ColumnType, usize), FailureExplanation>, ParserError> {
+    printparse!("read_column_type at {}", start_cursor);
+
+    match &lexograms[start_cursor].l_type {
+        Identifier(name) if name == "number" => Ok(Ok((ColumnType::Number, start_cursor + 1))),
+        Identifier(name) if name == "string" => Ok(Ok((ColumnType::String, start_cursor + 1))),
+        Identifier(name) if name == "bool" => Ok(Ok((ColumnType::Bool, start_cursor + 1))),
+        Identifier(name) if name == "record" => Ok(Ok((ColumnType::Record, start_cursor + 1))),
+        Identifier(name) if name == "any" => Ok(Ok((ColumnType::Any, start_cursor + 1))),
+        LeftBracket => match read_column_type(lexograms, start_cursor + 1)? {
+            Ok((item, jump_to)) => match lexograms.get(jump_to) {
+                Some(lexer::Lexogram {
+                    l_type: RightBracket,
+                    ..
+                }) => Ok(Ok((ColumnType::Array(Box::new(item)), jump_to + 1))),
+                _ => Ok(Err(FailureExplanation {
+                    lex_pos: jump_to,
+                    if_it_was: "column type".into(),
+                    failed_because: FailureReason::Other(
+                        "specting ] to close the array type".into(),
+                    ),
+                    parent_failure: vec![],
+                })),
+            },
+            Err(e) => Ok(Err(FailureExplanation {
+                lex_pos: start_cursor,
+                if_it_was: "column type".into(),
+                failed_because: FailureReason::Other("specting the type of the array items".into()),
+                parent_failure: vec![e],
+            })),
+        },
+        lex => Ok(Err(FailureExplanation {
+            lex_pos: start_cursor,
+            if_it_was: "column type".into(),
+            failed_because: FailureReason::Other(format!(
+                "{lex} is not number, string, bool, record, any nor [type]"
+            )),
+            parent_failure: vec![],
+        })),
+    }
+}
+
+pub fn read_schema(
+    lexograms: &Vec<lexer::Lexogram>,
+    start_cursor: usize,
+) -> Result<Result<(Schema, usize), FailureExplanation>, ParserError> {
+    #[derive(Debug, Clone, Copy)]
+    enum SchemaParserStates {
+        SpectingSchemaKeyword,
+        SpectingRelationName,
+        SpectingOpenParenthesis,
+        SpectingColumnName,
+        SpectingColon,
+        SpectingColumnType,
+        SpectingComaOrClosingParenthesis,
+    }
+    impl SchemaParserStates {
+        fn expected(&self) -> Vec<&'static str> {
+            match self {
+                SpectingSchemaKeyword => vec!["`schema`"],
+                SpectingRelationName => vec!["a relation name"],
+                SpectingOpenParenthesis => vec!["`(`"],
+                SpectingColumnName => vec!["a column name"],
+                SpectingColon => vec!["`:`"],
+                SpectingColumnType => vec!["a column type"],
+                SpectingComaOrClosingParenthesis => vec!["`,`", "`)`"],
+            }
+        }
+    }
+    use SchemaParserStates::*;
+
+    printparse!("read_schema at {}", start_cursor);
+
+    let mut cursor = start_cursor;
+    let mut state = SpectingSchemaKeyword;
+
+    let mut op_rel_name = None;
+    let mut op_column_name = None;
+    let mut columns = vec![];
+
+    for (i, lex) in lexograms.iter().enumerate() {
+        if cursor > i {
+            continue;
+        }
+        match (lex.l_type.to_owned(), state) {
+            (Identifier(keyword), SpectingSchemaKeyword) if keyword == "schema" => {
+                state = SpectingRelationName
+            }
+            (Identifier(rel_name), SpectingRelationName) => {
+                op_rel_name = Some(rel_name);
+                state = SpectingOpenParenthesis;
+            }
+            (LeftParenthesis, SpectingOpenParenthesis) => state = SpectingColumnName,
+            (Identifier(column_name), SpectingColumnName) => {
+                op_column_name = Some(column_name);
+                state = SpectingColon;
+            }
+            (Colon, SpectingColon) => state = SpectingColumnType,
+            (_, SpectingColumnType) => match (read_column_type(lexograms, i)?, &op_column_name) {
+                (Ok((column_type, jump_to)), Some(column_name)) => {
+                    if columns.iter().any(|(name, _)| name == column_name) {
+                        return Ok(Err(FailureExplanation {
+                            lex_pos: i,
+                            if_it_was: "schema".into(),
+                            failed_because: FailureReason::Other(format!(
+                                "the column {column_name} is repeated"
+                            )),
+                            parent_failure: vec![],
+                        }));
+                    }
+                    columns.push((column_name.to_owned(), column_type));
+                    cursor = jump_to;
+                    state = SpectingComaOrClosingParenthesis;
+                }
+                (Err(e), _) => {
+                    return Ok(Err(FailureExplanation {
+                        lex_pos: i,
+                        if_it_was: "schema".into(),
+                        failed_because: FailureReason::Other("specting a column type".into()),
+                        parent_failure: vec![e],
+                    }))
+                }
+                _ => unreachable!(),
+            },
+            (Coma, SpectingComaOrClosingParenthesis) => state = SpectingColumnName,
+            (RightParenthesis, SpectingComaOrClosingParenthesis) => match op_rel_name {
+                Some(rel_name) => {
+                    return Ok(Ok((
+                        Schema {
+                            rel_name,
+                            columns,
+                            span: Some(Span {
+                                start: lexograms[start_cursor].pos_s,
+                                end: lex.pos_f,
+                            }),
+                        },
+                        i + 1,
+                    )))
+                }
+                None => unreachable!(),
+            },
+            _ => {
+                return Ok(Err(FailureExplanation {
+                    lex_pos: i,
+                    if_it_was: "schema".into(),
+                    failed_because: FailureReason::Specting(state.expected()),
+                    parent_failure: vec![],
+                }))
+            }
+        }
+    }
+    Ok(Err(FailureExplanation {
+        lex_pos: lexograms.len() - 1,
+        if_it_was: "schema".into(),
+        failed_because: FailureReason::FileEnded,
+        parent_failure: vec![],
+    }))
+}
@@ -1,14 +1,19 @@
+use std::cmp::Ordering;
 use std::collections::hash_map::DefaultHasher;
-use std::collections::{BTreeMap, HashSet};
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
 use std::hash::{Hash, Hasher};
 use std::{fmt, vec};
 
 use print_macros::*;
 
+use crate::diagnostic::Span;
+use crate::engine::explain::{adornment, PlanNode};
+use crate::engine::provenance::Proof;
 use crate::engine::recursion_tally::RecursionTally;
+use crate::engine::relation::truth::Truth;
 use crate::engine::var_context::VarContext;
 use crate::engine::var_context_universe::VarContextUniverse;
-use crate::engine::Engine;
+use crate::engine::{DeductionError, Engine, EvaluationMode};
 use crate::lexer::LexogramType::*;
 
 use crate::parser::defered_relation_token::read_defered_relation;
@@ -18,8 +23,12 @@ use crate::lexer::{self};
 
 use super::data_token::Data;
 use super::defered_relation_token::DeferedRelation;
-use super::error::{FailureExplanation, ParserError};
-use super::expresion_token::Expresion;
+use super::error::{FailureExplanation, FailureReason, ParserError};
+use super::expresion_token::{Expresion, ExpresionSemantics, VarName};
+use super::function_token::{BuiltInFunction, FunctionCall};
+use super::line_token::Comment;
+use super::matches_token::{read_matches, RegexMatch};
+use super::HasRelId;
 
 #[derive(Clone, Copy)]
 enum AppendModes {
@@ -31,6 +40,7 @@ enum AppendModes {
 #[derive(Debug, Clone, Eq, PartialEq, Hash)]
 pub enum Comparison {
     Eq,
+    NotEq,
     Lt,
     Gt,
     Gte,
@@ -45,13 +55,20 @@ pub enum StatementSemantics {
     Or(Box<Statement>, Box<Statement>),
     Not(Box<Statement>),
     ExpresionComparison(Expresion, Expresion, Comparison),
+    Matches(Box<RegexMatch>),
+    // a call to a built in function giving a bool
+    Predicate(Expresion),
     Relation(DeferedRelation),
 }
 
-#[derive(Debug, Clone, Eq, PartialEq, Hash)]
+#[derive(Debug, Clone, Eq)]
 pub struct Statement {
-    memoizer: BTreeMap<u64, Result<VarContextUniverse, String>>,
+    memoizer: BTreeMap<u64, Result<VarContextUniverse, DeductionError>>,
     semantics: StatementSemantics,
+    // where it was read from, statements made up by the engine have none
+    span: Option<Span>,
+    // the comments written right before it
+    comments: Vec<Comment>,
 }
 
 impl From<StatementSemantics> for Statement {
@@ -59,6 +76,34 @@ impl From<StatementSemantics> for Statement {
         Self {
             memoizer: BTreeMap::new(),
             semantics: value,
+            span: None,
+            comments: vec![],
+        }
+    }
+}
+
+impl PartialEq for Statement {
+    fn eq(&self, other: &Self) -> bool {
+        self.memoizer == other.memoizer && self.semantics == other.semantics
+    }
+}
+
+impl Hash for Statement {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.memoizer.hash(state);
+        self.semantics.hash(state);
+    }
+}
+
+impl fmt::Display for Comparison {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Comparison::Eq => write!(f, "="),
+            Comparison::NotEq => write!(f, "!="),
+            Comparison::Lt => write!(f, "<"),
+            Comparison::Gt => write!(f, ">"),
+            Comparison::Gte => write!(f, ">="),
+            Comparison::Lte => write!(f, "<="),
         }
     }
 }
@@ -69,27 +114,36 @@ impl fmt::Display for Statement {
             StatementSemantics::And(sta, stb) => write!(f, "({sta} && {stb})"),
             StatementSemantics::Or(sta, stb) => write!(f, "({sta} || {stb})"),
             StatementSemantics::Not(st) => write!(f, "!({st})"),
-            StatementSemantics::ExpresionComparison(sta, stb, Comparison::Eq) => {
-                write!(f, "({sta}={stb})")
-            }
-            StatementSemantics::ExpresionComparison(sta, stb, Comparison::Lt) => {
-                write!(f, "({sta}<{stb})")
-            }
-            StatementSemantics::ExpresionComparison(sta, stb, Comparison::Gt) => {
-                write!(f, "({sta}>{stb})")
-            }
-            StatementSemantics::ExpresionComparison(sta, stb, Comparison::Gte) => {
-                write!(f, "({sta}>={stb})")
-            }
-            StatementSemantics::ExpresionComparison(sta, stb, Comparison::Lte) => {
-                write!(f, "({sta}<={stb})")
+            StatementSemantics::ExpresionComparison(sta, stb, comparison) => {
+                write!(f, "({sta}{comparison}{stb})")
             }
+            StatementSemantics::Matches(regex_match) => write!(f, "{regex_match}"),
+            StatementSemantics::Predicate(call) => write!(f, "{call}"),
             StatementSemantics::Relation(rel) => write!(f, "{rel}"),
             StatementSemantics::True => write!(f, "true"),
         }
     }
 }
 
+// the cheapest conjunct that can run with the variables bound so far, or the first one left when
+// none of them can
+fn pick_conjunct<'a>(
+    conjuncts: impl Iterator<Item = &'a Statement>,
+    engine: &Engine,
+    universe: &VarContextUniverse,
+) -> usize {
+    let bound_vars = universe.get_bound_vars();
+    conjuncts
+        .enumerate()
+        .filter_map(|(i, conjunct)| {
+            conjunct
+                .estimate_cardinality(engine, &bound_vars)
+                .map(|estimate| (i, estimate))
+        })
+        .min_by(|(_, a), (_, b)| a.total_cmp(b))
+        .map_or(0, |(i, _)| i)
+}
+
 pub fn read_statement(
     lexograms: &Vec<lexer::Lexogram>,
     start_cursor: usize,
@@ -103,6 +157,20 @@ pub fn read_statement(
         SpectingOperatorOrEnd,
         SpectingClosingParenthesis,
     }
+    impl StatementParserStates {
+        fn expected(&self) -> Vec<&'static str> {
+            match self {
+                StatementParserStates::SpectingStatementOrNegationOrOpenParenthesisOrTrue => {
+                    vec!["a statement", "`!`", "`(`", "`true`"]
+                }
+                StatementParserStates::SpectingStatementOrOpenParenthesis => {
+                    vec!["a statement", "`(`"]
+                }
+                SpectingOperatorOrEnd => vec!["`&&`", "`||`"],
+                SpectingClosingParenthesis => vec!["`)`"],
+            }
+        }
+    }
     use StatementParserStates::*;
     let mut cursor = start_cursor;
     let mut state = SpectingStatementOrNegationOrOpenParenthesisOrTrue;
@@ -129,13 +197,34 @@ pub fn read_statement(
                 state = SpectingStatementOrOpenParenthesis;
             }
 
-            (OpNot, SpectingStatementOrNegationOrOpenParenthesisOrTrue, _) => {
-                negate_next_statement = true;
+            (
+                OpNot,
+                SpectingStatementOrNegationOrOpenParenthesisOrTrue
+                | SpectingStatementOrOpenParenthesis,
+                _,
+            ) => {
+                negate_next_statement = !negate_next_statement;
                 state = SpectingStatementOrOpenParenthesis
             }
 
-            (True, SpectingStatementOrNegationOrOpenParenthesisOrTrue, _) => {
-                return Ok(Ok((StatementSemantics::True.into(), i + 1)));
+            // true = x compares the bool instead of standing for the trivial statement
+            (True, SpectingStatementOrNegationOrOpenParenthesisOrTrue, _)
+                if !matches!(
+                    lexograms.get(i + 1),
+                    Some(lexer::Lexogram {
+                        l_type: OpEq | OpNotEq | OpGT | OpLT | OpGTE | OpLTE,
+                        ..
+                    })
+                ) =>
+            {
+                let span = Span {
+                    start: lex.pos_s,
+                    end: lex.pos_f,
+                };
+                return Ok(Ok((
+                    Statement::from(StatementSemantics::True).with_span(span),
+                    i + 1,
+                )));
             }
 
             (
@@ -160,7 +249,9 @@ pub fn read_statement(
                         return Ok(Err(FailureExplanation {
                             lex_pos: i,
                             if_it_was: "statement concatenation".into(),
-                            failed_because: "specting nested statement concatenation".into(),
+                            failed_because: FailureReason::Other(
+                                "specting nested statement concatenation".into(),
+                            ),
                             parent_failure: (vec![e]),
                         }))
                     }
@@ -180,10 +271,14 @@ pub fn read_statement(
                 match read_statement_item(lexograms, i)? {
                     Ok((new_statement, jump_to)) => {
                         cursor = jump_to;
+                        let span = Span {
+                            start: lexograms[i].pos_s,
+                            end: lexograms[jump_to - 1].pos_f,
+                        };
 
                         op_ret = merge_statements(
                             op_ret,
-                            new_statement,
+                            new_statement.with_span(span),
                             &append_mode,
                             &negate_next_statement,
                         );
@@ -194,7 +289,9 @@ pub fn read_statement(
                         return Ok(Err(FailureExplanation {
                             lex_pos: i,
                             if_it_was: "statement concatenation".into(),
-                            failed_because: "specting nested statement concatenation".into(),
+                            failed_because: FailureReason::Other(
+                                "specting nested statement concatenation".into(),
+                            ),
                             parent_failure: (vec![e]),
                         }))
                     }
@@ -207,7 +304,7 @@ pub fn read_statement(
                 return Ok(Err(FailureExplanation {
                     lex_pos: i,
                     if_it_was: "statement".into(),
-                    failed_because: format!("pattern missmatch on {:#?} state", state).into(),
+                    failed_because: FailureReason::Specting(state.expected()),
                     parent_failure: vec![],
                 }))
             }
@@ -218,7 +315,7 @@ pub fn read_statement(
         _ => Ok(Err(FailureExplanation {
             lex_pos: lexograms.len() - 1,
             if_it_was: "statement".into(),
-            failed_because: "file ended".into(),
+            failed_because: FailureReason::FileEnded,
             parent_failure: vec![],
         })),
     }
@@ -231,9 +328,20 @@ pub fn read_statement_item(
     #[derive(Debug, Clone, Copy)]
     enum StatementParserStates {
         SpectingFirstExpresionOrRelation,
-        SpectingExrpresionComparisonOperator,
+        SpectingComparisonOperator,
         SpectingSecondExpresion,
     }
+    impl StatementParserStates {
+        fn expected(&self) -> Vec<&'static str> {
+            match self {
+                StatementParserStates::SpectingFirstExpresionOrRelation => {
+                    vec!["a relation", "an expresion"]
+                }
+                SpectingComparisonOperator => vec!["a comparison operator"],
+                SpectingSecondExpresion => vec!["an expresion"],
+            }
+        }
+    }
     use StatementParserStates::*;
 
     printparse!("read_statement at {}", start_cursor);
@@ -255,36 +363,121 @@ pub fn read_statement_item(
             op_first_expresion.to_owned(),
             op_append_mode.to_owned(),
         ) {
+            // matches is built in, so it isnt read as a relation
+            (Identifier(keyword), SpectingFirstExpresionOrRelation, _, _)
+                if keyword == "matches"
+                    && matches!(
+                        lexograms.get(i + 1).map(|lex| &lex.l_type),
+                        Some(LeftParenthesis)
+                    ) =>
+            {
+                return Ok(match read_matches(lexograms, i)? {
+                    Ok((regex_match, jump_to)) => Ok((
+                        StatementSemantics::Matches(Box::new(regex_match)).into(),
+                        jump_to,
+                    )),
+                    Err(e) => Err(FailureExplanation {
+                        lex_pos: i,
+                        if_it_was: "statement".into(),
+                        failed_because: FailureReason::Other("specting a regex match".into()),
+                        parent_failure: vec![e],
+                    }),
+                });
+            }
+            // so are the functions, which stand as statements when they give a bool
+            (Identifier(name), SpectingFirstExpresionOrRelation, _, _)
+                if BuiltInFunction::from_name(&name).is_some()
+                    && matches!(
+                        lexograms.get(i + 1).map(|lex| &lex.l_type),
+                        Some(LeftParenthesis)
+                    ) =>
+            {
+                match read_expresion(lexograms, i, false)? {
+                    Ok((exp, jump_to))
+                        if matches!(
+                            lexograms.get(jump_to).map(|lex| &lex.l_type),
+                            Some(OpEq | OpNotEq | OpGT | OpLT | OpGTE | OpLTE)
+                        ) =>
+                    {
+                        op_first_expresion = Some(exp);
+                        cursor = jump_to;
+                        state = SpectingComparisonOperator;
+                    }
+                    Ok((exp, jump_to))
+                        if matches!(
+                            exp.get_semantics(),
+                            ExpresionSemantics::Function(FunctionCall { function, .. })
+                                if function.is_predicate()
+                        ) =>
+                    {
+                        return Ok(Ok((StatementSemantics::Predicate(exp).into(), jump_to)))
+                    }
+                    Ok((exp, _)) => {
+                        return Ok(Err(FailureExplanation {
+                            lex_pos: i,
+                            if_it_was: "statement".into(),
+                            failed_because: FailureReason::Other(format!(
+                                "{exp} doesnt give a bool, so it has to be compared"
+                            )),
+                            parent_failure: vec![],
+                        }))
+                    }
+                    Err(e) => {
+                        return Ok(Err(FailureExplanation {
+                            lex_pos: i,
+                            if_it_was: "statement".into(),
+                            failed_because: FailureReason::Other("specting a function call".into()),
+                            parent_failure: vec![e],
+                        }))
+                    }
+                }
+            }
             (_, SpectingFirstExpresionOrRelation, _, _) => {
                 let err1;
 
                 match read_defered_relation(lexograms, i, false)? {
-                    Ok((def_rel, jump_to)) => {
+                    // a built in function call looks like a relation until it is compared
+                    Ok((def_rel, jump_to))
+                        if !matches!(
+                            lexograms.get(jump_to).map(|lex| &lex.l_type),
+                            Some(OpEq | OpNotEq | OpGT | OpLT | OpGTE | OpLTE)
+                        ) =>
+                    {
                         return Ok(Ok((StatementSemantics::Relation(def_rel).into(), jump_to)))
                     }
-
+                    Ok((def_rel, _)) => {
+                        err1 = FailureExplanation {
+                            lex_pos: i,
+                            if_it_was: "statement".into(),
+                            failed_because: FailureReason::Other(format!(
+                                "the relation {def_rel} is being compared"
+                            )),
+                            parent_failure: vec![],
+                        }
+                    }
                     Err(err) => err1 = err,
                 }
                 match read_expresion(lexograms, i, false)? {
                     Ok((e, jump_to)) => {
                         op_first_expresion = Some(e);
                         cursor = jump_to;
-                        state = SpectingExrpresionComparisonOperator;
+                        state = SpectingComparisonOperator;
                     }
                     Err(err2) => {
                         return Ok(Err(FailureExplanation {
                             lex_pos: i,
                             if_it_was: "statement".into(),
-                            failed_because: "was neither a relation nor a expresion comparation"
-                                .into(),
+                            failed_because: FailureReason::Other(
+                                "was neither a relation nor a expresion comparation".into(),
+                            ),
                             parent_failure: (vec![err1, err2]),
                         }))
                     }
                 }
             }
             (
-                op @ (OpEq | OpGT | OpLT | OpGTE | OpLTE),
-                SpectingExrpresionComparisonOperator,
+                op @ (OpEq | OpNotEq | OpGT | OpLT | OpGTE | OpLTE),
+                SpectingComparisonOperator,
                 _,
                 _,
             ) => {
@@ -301,6 +494,11 @@ pub fn read_statement_item(
                                     second_expresion,
                                     Comparison::Eq,
                                 ),
+                                OpNotEq => StatementSemantics::ExpresionComparison(
+                                    first_expresion,
+                                    second_expresion,
+                                    Comparison::NotEq,
+                                ),
                                 OpLT => StatementSemantics::ExpresionComparison(
                                     first_expresion,
                                     second_expresion,
@@ -325,7 +523,9 @@ pub fn read_statement_item(
                                     return Ok(Err(FailureExplanation {
                                         lex_pos: i,
                                         if_it_was: "statement".into(),
-                                        failed_because: "corrupted operator".into(),
+                                        failed_because: FailureReason::Other(
+                                            "corrupted operator".into(),
+                                        ),
                                         parent_failure: vec![],
                                     }))
                                 }
@@ -338,22 +538,20 @@ pub fn read_statement_item(
                         return Ok(Err(FailureExplanation {
                             lex_pos: i,
                             if_it_was: "statement".into(),
-                            failed_because: "specting second statement after operator".into(),
+                            failed_because: FailureReason::Other(
+                                "specting second statement after operator".into(),
+                            ),
                             parent_failure: (vec![e]),
                         }))
                     }
                 }
             }
 
-            (lex, state, a, b) => {
+            (_, state, _, _) => {
                 return Ok(Err(FailureExplanation {
                     lex_pos: i,
                     if_it_was: "statement".into(),
-                    failed_because: format!(
-                        "pattern missmatch on {:#?} state ({a:?} {b:?}) reading lex {:#?}",
-                        state, lex
-                    )
-                    .into(),
+                    failed_because: FailureReason::Specting(state.expected()),
                     parent_failure: vec![],
                 }))
             }
@@ -369,41 +567,528 @@ fn merge_statements(
     append_mode: &AppendModes,
     negate_next_statement: &bool,
 ) -> Option<Statement> {
-    Some(match (op_ret, append_mode, negate_next_statement) {
-        (None, _, _) => new_statement,
-        (Some(prev_statement), AppendModes::And, false) => {
-            StatementSemantics::And(Box::new(prev_statement), Box::new(new_statement)).into()
+    // the nodes made out of others span from the first of them to the last
+    let new_statement = if *negate_next_statement {
+        Statement {
+            span: new_statement.span,
+            ..StatementSemantics::Not(Box::new(new_statement)).into()
         }
-        (Some(prev_statement), AppendModes::Or, false) => {
-            StatementSemantics::Or(Box::new(prev_statement), Box::new(new_statement)).into()
+    } else {
+        new_statement
+    };
+    Some(match (op_ret, append_mode) {
+        (None, _) => new_statement,
+        (Some(_), AppendModes::None) => unreachable!(),
+        (Some(prev_statement), append_mode) => {
+            let span = match (prev_statement.span, new_statement.span) {
+                (Some(prev_span), Some(new_span)) => Some(Span {
+                    start: prev_span.start,
+                    end: new_span.end,
+                }),
+                _ => None,
+            };
+            let semantics = match append_mode {
+                AppendModes::And => {
+                    StatementSemantics::And(Box::new(prev_statement), Box::new(new_statement))
+                }
+                _ => StatementSemantics::Or(Box::new(prev_statement), Box::new(new_statement)),
+            };
+            Statement {
+                span,
+                ..semantics.into()
+            }
         }
-        (Some(prev_statement), AppendModes::And, true) => StatementSemantics::And(
-            Box::new(prev_statement),
-            Box::new(StatementSemantics::Not(Box::new(new_statement)).into()),
-        )
-        .into(),
-        (Some(prev_statement), AppendModes::Or, true) => StatementSemantics::Or(
-            Box::new(prev_statement),
-            Box::new(StatementSemantics::Not(Box::new(new_statement)).into()),
-        )
-        .into(),
-        (Some(_), AppendModes::None, _) => unreachable!(),
     })
 }
 
 impl Statement {
+    pub fn get_semantics(&self) -> &StatementSemantics {
+        &self.semantics
+    }
+
+    pub fn get_span(&self) -> Option<Span> {
+        self.span
+    }
+
+    fn with_span(mut self, span: Span) -> Statement {
+        self.span = Some(span);
+        self
+    }
+
+    pub fn get_comments(&self) -> &Vec<Comment> {
+        &self.comments
+    }
+
+    // the comments are kept in the order they were written
+    fn add_comment(&mut self, comment: Comment) {
+        let at = self
+            .comments
+            .partition_point(|other| other.span.start < comment.span.start);
+        self.comments.insert(at, comment);
+    }
+
+    // a comment inside a chain of && and || goes with the operand right after it, and is given
+    // back when it isnt before any of them
+    pub fn attach_comment(&mut self, comment: Comment) -> Result<(), Comment> {
+        let after_comment = |statement: &Statement| {
+            statement
+                .span
+                .is_some_and(|span| span.start >= comment.span.end)
+        };
+        let before_self = after_comment(self);
+        match &mut self.semantics {
+            StatementSemantics::And(statement_a, statement_b)
+            | StatementSemantics::Or(statement_a, statement_b) => {
+                if after_comment(statement_a) {
+                    statement_a.attach_comment(comment)
+                } else if after_comment(statement_b) {
+                    statement_b.add_comment(comment);
+                    Ok(())
+                } else {
+                    Err(comment)
+                }
+            }
+            _ if before_self => {
+                self.add_comment(comment);
+                Ok(())
+            }
+            _ => Err(comment),
+        }
+    }
+
+    pub fn count_relations(&self) -> usize {
+        match &self.semantics {
+            StatementSemantics::And(statement_a, statement_b)
+            | StatementSemantics::Or(statement_a, statement_b) => {
+                statement_a.count_relations() + statement_b.count_relations()
+            }
+            StatementSemantics::Relation(_) => 1,
+            StatementSemantics::Not(_)
+            | StatementSemantics::ExpresionComparison(_, _, _)
+            | StatementSemantics::Matches(_)
+            | StatementSemantics::Predicate(_)
+            | StatementSemantics::True => 0,
+        }
+    }
+
+    pub fn get_relations(&self) -> Vec<(DeferedRelation, bool)> {
+        match &self.semantics {
+            StatementSemantics::And(statement_a, statement_b)
+            | StatementSemantics::Or(statement_a, statement_b) => {
+                let mut ret = statement_a.get_relations();
+                ret.extend(statement_b.get_relations());
+                ret
+            }
+            // a negation inside a negation still needs its relations complete
+            StatementSemantics::Not(statement) => statement
+                .get_relations()
+                .into_iter()
+                .map(|(rel, _)| (rel, true))
+                .collect(),
+            StatementSemantics::Relation(rel) => vec![(rel.to_owned(), false)],
+            // aggregates need their relations complete, same as a negation does
+            StatementSemantics::ExpresionComparison(exp_a, exp_b, _) => exp_a
+                .get_aggregates()
+                .into_iter()
+                .chain(exp_b.get_aggregates())
+                .flat_map(|aggregate| aggregate.condition.get_relations())
+                .map(|(rel, _)| (rel, true))
+                .collect(),
+            StatementSemantics::Predicate(call) => call
+                .get_aggregates()
+                .into_iter()
+                .flat_map(|aggregate| aggregate.condition.get_relations())
+                .map(|(rel, _)| (rel, true))
+                .collect(),
+            StatementSemantics::Matches(_) | StatementSemantics::True => vec![],
+        }
+    }
+
+    // gives every _ on a positive relation a hidden variable of its own, so that tuples only
+    // differing on those columns still yield different contexts
+    pub fn name_anonymous_vars(&self, counter: &mut usize) -> Statement {
+        let named: Statement = match &self.semantics {
+            StatementSemantics::And(statement_a, statement_b) => StatementSemantics::And(
+                Box::new(statement_a.name_anonymous_vars(counter)),
+                Box::new(statement_b.name_anonymous_vars(counter)),
+            )
+            .into(),
+            StatementSemantics::Or(statement_a, statement_b) => StatementSemantics::Or(
+                Box::new(statement_a.name_anonymous_vars(counter)),
+                Box::new(statement_b.name_anonymous_vars(counter)),
+            )
+            .into(),
+            StatementSemantics::Relation(rel) => {
+                let mut named_rel = rel.to_owned();
+                for arg in named_rel.args.iter_mut() {
+                    if let ExpresionSemantics::Literal(Data::Any) = arg.get_semantics() {
+                        // '#' always starts a comment on the lexer, so no user variable collides
+                        let named_arg: Expresion =
+                            ExpresionSemantics::Var(VarName::Direct(format!("#{counter}"))).into();
+                        *arg = match arg.get_span() {
+                            Some(span) => named_arg.with_span(span),
+                            None => named_arg,
+                        };
+                        *counter += 1;
+                    }
+                }
+                StatementSemantics::Relation(named_rel).into()
+            }
+            _ => return self.to_owned(),
+        };
+        Statement {
+            span: self.span,
+            comments: self.comments.to_owned(),
+            ..named
+        }
+    }
+
+    pub fn get_vars(&self) -> BTreeSet<String> {
+        match &self.semantics {
+            StatementSemantics::And(statement_a, statement_b)
+            | StatementSemantics::Or(statement_a, statement_b) => {
+                let mut ret = statement_a.get_vars();
+                ret.extend(statement_b.get_vars());
+                ret
+            }
+            StatementSemantics::Not(statement) => statement.get_vars(),
+            StatementSemantics::ExpresionComparison(exp_a, exp_b, _) => {
+                let mut ret = exp_a.get_vars();
+                ret.extend(exp_b.get_vars());
+                ret
+            }
+            StatementSemantics::Matches(regex_match) => regex_match.get_vars(),
+            StatementSemantics::Predicate(call) => call.get_vars(),
+            StatementSemantics::Relation(rel) => rel.get_vars(),
+            StatementSemantics::True => BTreeSet::new(),
+        }
+    }
+
+    // an aggregate groups by the variables it shares with the rest of the rule, every other
+    // variable on it is local
+    pub fn scope_aggregates(&self, outer_vars: &BTreeSet<String>) -> Statement {
+        let scoped: Statement = match &self.semantics {
+            StatementSemantics::And(statement_a, statement_b) => StatementSemantics::And(
+                Box::new(statement_a.scope_aggregates(outer_vars)),
+                Box::new(statement_b.scope_aggregates(outer_vars)),
+            )
+            .into(),
+            StatementSemantics::Or(statement_a, statement_b) => StatementSemantics::Or(
+                Box::new(statement_a.scope_aggregates(outer_vars)),
+                Box::new(statement_b.scope_aggregates(outer_vars)),
+            )
+            .into(),
+            StatementSemantics::Not(statement) => {
+                StatementSemantics::Not(Box::new(statement.scope_aggregates(outer_vars))).into()
+            }
+            StatementSemantics::ExpresionComparison(exp_a, exp_b, comp) => {
+                StatementSemantics::ExpresionComparison(
+                    exp_a.scope_aggregates(outer_vars),
+                    exp_b.scope_aggregates(outer_vars),
+                    comp.to_owned(),
+                )
+                .into()
+            }
+            StatementSemantics::Predicate(call) => {
+                StatementSemantics::Predicate(call.scope_aggregates(outer_vars)).into()
+            }
+            StatementSemantics::Matches(_)
+            | StatementSemantics::Relation(_)
+            | StatementSemantics::True => self.to_owned(),
+        };
+        Statement {
+            span: self.span,
+            comments: self.comments.to_owned(),
+            ..scoped
+        }
+    }
+
+    fn get_conjuncts(&self) -> Vec<&Statement> {
+        match &self.semantics {
+            StatementSemantics::And(statement_a, statement_b) => {
+                let mut ret = statement_a.get_conjuncts();
+                ret.extend(statement_b.get_conjuncts());
+                ret
+            }
+            _ => vec![self],
+        }
+    }
+
+    fn get_conjuncts_mut(&mut self) -> Vec<&mut Statement> {
+        if !matches!(self.semantics, StatementSemantics::And(_, _)) {
+            return vec![self];
+        }
+        match &mut self.semantics {
+            StatementSemantics::And(statement_a, statement_b) => {
+                let mut ret = statement_a.get_conjuncts_mut();
+                ret.extend(statement_b.get_conjuncts_mut());
+                ret
+            }
+            _ => unreachable!(),
+        }
+    }
+
+    // contexts expected out of each incoming one, or None while the statement still needs
+    // variables that arent bound yet
+    fn estimate_cardinality(&self, engine: &Engine, bound_vars: &BTreeSet<String>) -> Option<f64> {
+        let needed_vars = |exp: &Expresion| {
+            let mut ret = exp.get_vars();
+            for aggregate in exp.get_aggregates() {
+                ret.extend(aggregate.group_by.iter().cloned());
+            }
+            ret
+        };
+
+        match &self.semantics {
+            StatementSemantics::And(_, _) => self
+                .get_conjuncts()
+                .iter()
+                .filter_map(|conjunct| conjunct.estimate_cardinality(engine, bound_vars))
+                .min_by(f64::total_cmp),
+            StatementSemantics::Or(statement_a, statement_b) => Some(
+                statement_a.estimate_cardinality(engine, bound_vars)?
+                    + statement_b.estimate_cardinality(engine, bound_vars)?,
+            ),
+            StatementSemantics::Not(statement) => {
+                statement.get_vars().is_subset(bound_vars).then_some(0.0)
+            }
+            // an equality with one side known binds the other one
+            StatementSemantics::ExpresionComparison(exp_a, exp_b, Comparison::Eq) => {
+                match (
+                    needed_vars(exp_a).is_subset(bound_vars),
+                    needed_vars(exp_b).is_subset(bound_vars),
+                ) {
+                    (true, true) => Some(0.0),
+                    (true, false) => exp_b.is_solvable().then_some(1.0),
+                    (false, true) => exp_a.is_solvable().then_some(1.0),
+                    (false, false) => None,
+                }
+            }
+            StatementSemantics::ExpresionComparison(exp_a, exp_b, _) => {
+                let mut vars = needed_vars(exp_a);
+                vars.extend(needed_vars(exp_b));
+                vars.is_subset(bound_vars).then_some(0.0)
+            }
+            StatementSemantics::Predicate(call) => {
+                needed_vars(call).is_subset(bound_vars).then_some(0.0)
+            }
+            // a match binds its captures once the text and the pattern are known
+            StatementSemantics::Matches(regex_match) => {
+                if !regex_match.get_needed_vars().is_subset(bound_vars) {
+                    None
+                } else if regex_match.get_vars().is_subset(bound_vars) {
+                    Some(0.0)
+                } else {
+                    Some(1.0)
+                }
+            }
+            StatementSemantics::Relation(rel) => {
+                let bound_columns: Vec<usize> = rel
+                    .args
+                    .iter()
+                    .enumerate()
+                    .filter(|(_, arg)| {
+                        !matches!(arg.get_semantics(), ExpresionSemantics::Literal(Data::Any))
+                            && arg.get_vars().is_subset(bound_vars)
+                    })
+                    .map(|(i, _)| i)
+                    .collect();
+
+                Some(match engine.get_table(rel.get_rel_id()) {
+                    Some(relation) => relation.estimate_cardinality(&bound_columns),
+                    None => 0.0,
+                })
+            }
+            StatementSemantics::True => Some(1.0),
+        }
+    }
+
+    pub fn map_relation(
+        &self,
+        occurrence: usize,
+        f: &dyn Fn(&DeferedRelation) -> DeferedRelation,
+    ) -> Statement {
+        self.map_relation_from(occurrence, &mut 0, f)
+    }
+
+    fn map_relation_from(
+        &self,
+        occurrence: usize,
+        visited: &mut usize,
+        f: &dyn Fn(&DeferedRelation) -> DeferedRelation,
+    ) -> Statement {
+        match &self.semantics {
+            StatementSemantics::And(statement_a, statement_b) => StatementSemantics::And(
+                Box::new(statement_a.map_relation_from(occurrence, visited, f)),
+                Box::new(statement_b.map_relation_from(occurrence, visited, f)),
+            )
+            .into(),
+            StatementSemantics::Or(statement_a, statement_b) => StatementSemantics::Or(
+                Box::new(statement_a.map_relation_from(occurrence, visited, f)),
+                Box::new(statement_b.map_relation_from(occurrence, visited, f)),
+            )
+            .into(),
+            StatementSemantics::Relation(rel) => {
+                *visited += 1;
+                if *visited - 1 == occurrence {
+                    StatementSemantics::Relation(f(rel)).into()
+                } else {
+                    self.to_owned()
+                }
+            }
+            _ => self.to_owned(),
+        }
+    }
+
+    // the children of an and node are its conjuncts in the order they are planned to run
+    pub fn explain(
+        &self,
+        engine: &Engine,
+        recursion_tally: &RecursionTally,
+        universe: &VarContextUniverse,
+    ) -> Result<PlanNode, String> {
+        let (operation, children) = match &self.semantics {
+            StatementSemantics::And(statement_a, statement_b) => {
+                let mut pending = statement_a.get_conjuncts();
+                pending.extend(statement_b.get_conjuncts());
+
+                let mut children = vec![];
+                let mut current_universe = universe.to_owned();
+                while !pending.is_empty() && !current_universe.is_empty() {
+                    let next = pick_conjunct(pending.iter().copied(), engine, &current_universe);
+                    let conjunct = pending.remove(next);
+
+                    children.push(conjunct.explain(engine, recursion_tally, &current_universe)?);
+                    current_universe = conjunct.to_owned().memo_get_posible_contexts(
+                        engine,
+                        recursion_tally,
+                        &current_universe,
+                    )?;
+                }
+                ("and (most selective first)".to_string(), children)
+            }
+            StatementSemantics::Or(statement_a, statement_b) => (
+                "or".to_string(),
+                vec![
+                    statement_a.explain(engine, recursion_tally, universe)?,
+                    statement_b.explain(engine, recursion_tally, universe)?,
+                ],
+            ),
+            StatementSemantics::Not(statement) => (
+                "not (over the lower stratum)".to_string(),
+                vec![statement.explain(
+                    &engine.get_lower_stratum(),
+                    &recursion_tally.restart(),
+                    universe,
+                )?],
+            ),
+            StatementSemantics::ExpresionComparison(_, _, _) => {
+                (format!("comparison {self}"), vec![])
+            }
+            StatementSemantics::Matches(_) => (format!("regex {self}"), vec![]),
+            StatementSemantics::Predicate(_) => (format!("predicate {self}"), vec![]),
+            StatementSemantics::Relation(rel) => (
+                format!("relation {rel} [{}]", adornment(rel, universe)),
+                vec![],
+            ),
+            StatementSemantics::True => ("true".to_string(), vec![]),
+        };
+
+        let outgoing = self
+            .to_owned()
+            .memo_get_posible_contexts(engine, recursion_tally, universe)?
+            .len();
+
+        Ok(PlanNode {
+            operation,
+            incoming: universe.len(),
+            outgoing,
+            children,
+        })
+    }
+
+    // the supporting derivations of each conjunct, given the bindings the statement held with
+    pub fn prove(
+        &self,
+        engine: &Engine,
+        recursion_tally: &RecursionTally,
+        bindings: &VarContext,
+        path: &mut Vec<Truth>,
+    ) -> Result<Option<Vec<Proof>>, String> {
+        let mut universe = VarContextUniverse::new();
+        universe.insert(bindings.to_owned());
+
+        Ok(match &self.semantics {
+            StatementSemantics::And(statement_a, statement_b) => {
+                match statement_a.prove(engine, recursion_tally, bindings, path)? {
+                    Some(mut support) => {
+                        match statement_b.prove(engine, recursion_tally, bindings, path)? {
+                            Some(support_b) => {
+                                support.extend(support_b);
+                                Some(support)
+                            }
+                            None => None,
+                        }
+                    }
+                    None => None,
+                }
+            }
+            StatementSemantics::Or(statement_a, statement_b) => {
+                match statement_a.prove(engine, recursion_tally, bindings, path)? {
+                    Some(support) => Some(support),
+                    None => statement_b.prove(engine, recursion_tally, bindings, path)?,
+                }
+            }
+            StatementSemantics::Relation(rel) => {
+                let mut truths = engine
+                    .query(&rel.clone_and_apply(bindings), bindings, recursion_tally)?
+                    .to_vector();
+                truths.sort();
+
+                let mut ret = None;
+                for truth in truths {
+                    if let Some(proof) = engine.prove_truth(&truth, path)? {
+                        ret = Some(vec![proof]);
+                        break;
+                    }
+                }
+                ret
+            }
+            StatementSemantics::Not(_)
+            | StatementSemantics::ExpresionComparison(_, _, _)
+            | StatementSemantics::Matches(_)
+            | StatementSemantics::Predicate(_) => {
+                if self
+                    .to_owned()
+                    .memo_get_posible_contexts(engine, recursion_tally, &universe)?
+                    .is_empty()
+                {
+                    None
+                } else {
+                    Some(vec![Proof::Check(format!("{self}"))])
+                }
+            }
+            StatementSemantics::True => Some(vec![]),
+        })
+    }
+
     pub fn memo_get_posible_contexts(
         &mut self,
         engine: &Engine,
         recursion_tally: &RecursionTally,
         universe: &VarContextUniverse,
-    ) -> Result<VarContextUniverse, String> {
+    ) -> Result<VarContextUniverse, DeductionError> {
         printprocess!(
             "get posible contexts of {} over universe:{}",
             self,
             universe
         );
 
+        if let EvaluationMode::Tabled = engine.get_evaluation_mode() {
+            // answer tables grow without changing the engine hash, so recalls would be stale
+            return self.get_posible_contexts(engine, recursion_tally, universe);
+        }
+
         let mut memo_hash = DefaultHasher::new();
         engine.hash(&mut memo_hash);
         universe.hash(&mut memo_hash);
@@ -431,7 +1116,7 @@ impl Statement {
         engine: &Engine,
         recursion_tally: &RecursionTally,
         universe: &VarContextUniverse,
-    ) -> Result<VarContextUniverse, String> {
+    ) -> Result<VarContextUniverse, DeductionError> {
         let ret = match &mut self.semantics {
             StatementSemantics::Or(statement_a, statement_b) => {
                 let deep_universe_a =
@@ -444,41 +1129,44 @@ impl Statement {
             }
 
             StatementSemantics::And(statement_a, statement_b) => {
-                let mut ret = universe.to_owned();
-                loop {
-                    let first_universe_a =
-                        statement_a.memo_get_posible_contexts(engine, recursion_tally, universe)?;
+                // each conjunct runs once over the contexts the previous ones left
+                let mut pending = statement_a.get_conjuncts_mut();
+                pending.extend(statement_b.get_conjuncts_mut());
 
-                    let first_universe_b =
-                        statement_b.memo_get_posible_contexts(engine, recursion_tally, universe)?;
-
-                    let universe_a = statement_a.memo_get_posible_contexts(
+                let mut ret = universe.to_owned();
+                while !pending.is_empty() && !ret.is_empty() {
+                    let next = pick_conjunct(pending.iter().map(|c| &**c), engine, &ret);
+                    ret = pending.remove(next).memo_get_posible_contexts(
                         engine,
                         recursion_tally,
-                        &first_universe_b,
+                        &ret,
                     )?;
+                }
+                ret
+            }
+            StatementSemantics::Not(statement) => {
+                // stratification guarantees the negated relations dont depend on the ones being
+                // evaluated, so they get a complete evaluation of their own
+                let lower_stratum = engine.get_lower_stratum();
+                let lower_recursion_tally = recursion_tally.restart();
 
-                    let universe_b = statement_b.memo_get_posible_contexts(
-                        engine,
-                        recursion_tally,
-                        &first_universe_a,
+                let mut ret = VarContextUniverse::new();
+                for context in universe.iter() {
+                    let mut single_universe = VarContextUniverse::new();
+                    single_universe.insert(context.to_owned());
+
+                    let negated_contexts = statement.memo_get_posible_contexts(
+                        &lower_stratum,
+                        &lower_recursion_tally,
+                        &single_universe,
                     )?;
 
-                    let new_ret = universe_a.or(universe_b);
-                    if new_ret != ret {
-                        ret = new_ret
-                    } else {
-                        break;
+                    if negated_contexts.is_empty() {
+                        ret.insert(context);
                     }
                 }
                 ret
             }
-            StatementSemantics::Not(statement) => {
-                let negated_contexts =
-                    statement.memo_get_posible_contexts(engine, recursion_tally, universe)?;
-
-                universe.difference(&negated_contexts) //TODO i dont think i can simplify a not, look into it
-            }
             StatementSemantics::ExpresionComparison(exp_a, exp_b, Comparison::Eq) => {
                 printprocess!(
                     "equality of {} and {} on universe {}",
@@ -489,10 +1177,15 @@ impl Statement {
 
                 let mut fitting_contexts = HashSet::new();
 
-                let owned_exp_a = exp_a.to_owned();
-                let owned_exp_b = exp_b.to_owned();
-
                 for context in universe.iter() {
+                    let (owned_exp_a, owned_exp_b) = match (
+                        exp_a.resolve_aggregates(engine, recursion_tally, &context)?,
+                        exp_b.resolve_aggregates(engine, recursion_tally, &context)?,
+                    ) {
+                        (Some(owned_exp_a), Some(owned_exp_b)) => (owned_exp_a, owned_exp_b),
+                        _ => continue,
+                    };
+
                     let a = owned_exp_a.literalize(&context);
                     let b = owned_exp_b.to_owned().literalize(&context);
                     match (&owned_exp_a, &owned_exp_b, a, b) {
@@ -521,24 +1214,71 @@ impl Statement {
             }
 
             StatementSemantics::ExpresionComparison(exp_a, exp_b, comp) => {
-                let fitting_contexts = universe
-                    .iter()
-                    .filter(|context| {
-                        let a = exp_a.literalize(context);
-                        let b = exp_b.literalize(context);
-                        match (a, b) {
-                            (Ok(data_a), Ok(data_b)) => match comp {
-                                Comparison::Lt => data_a < data_b,
-                                Comparison::Gt => data_a > data_b,
-                                Comparison::Gte => data_a <= data_b,
-                                Comparison::Lte => data_a >= data_b,
-                                Comparison::Eq => unreachable!(),
-                            },
-
-                            _ => false,
+                let mut fitting_contexts = HashSet::new();
+
+                for context in universe.iter() {
+                    let (a, b) = match (
+                        exp_a.resolve_aggregates(engine, recursion_tally, &context)?,
+                        exp_b.resolve_aggregates(engine, recursion_tally, &context)?,
+                    ) {
+                        (Some(exp_a), Some(exp_b)) => {
+                            (exp_a.literalize(&context), exp_b.literalize(&context))
                         }
-                    })
-                    .collect::<HashSet<VarContext>>();
+                        _ => continue,
+                    };
+                    let fits = match (a, b) {
+                        (Ok(data_a), Ok(data_b)) => match comp {
+                            Comparison::NotEq => data_a != data_b,
+                            Comparison::Lt => data_a.compare(&data_b) == Some(Ordering::Less),
+                            Comparison::Gt => data_a.compare(&data_b) == Some(Ordering::Greater),
+                            Comparison::Gte => matches!(
+                                data_a.compare(&data_b),
+                                Some(Ordering::Greater | Ordering::Equal)
+                            ),
+                            Comparison::Lte => matches!(
+                                data_a.compare(&data_b),
+                                Some(Ordering::Less | Ordering::Equal)
+                            ),
+                            Comparison::Eq => unreachable!(),
+                        },
+
+                        _ => false,
+                    };
+                    if fits {
+                        fitting_contexts.insert(context);
+                    }
+                }
+
+                VarContextUniverse {
+                    contents: fitting_contexts,
+                }
+            }
+            StatementSemantics::Predicate(call) => {
+                let mut fitting_contexts = HashSet::new();
+                for context in universe.iter() {
+                    let fits = match call.resolve_aggregates(engine, recursion_tally, &context)? {
+                        Some(call) => call.literalize(&context) == Ok(Data::Bool(true)),
+                        None => false,
+                    };
+                    if fits {
+                        fitting_contexts.insert(context);
+                    }
+                }
+
+                VarContextUniverse {
+                    contents: fitting_contexts,
+                }
+            }
+            StatementSemantics::Matches(regex_match) => {
+                printprocess!("regex {} on universe {}", regex_match, universe);
+
+                let mut cache = HashMap::new();
+                let mut fitting_contexts = HashSet::new();
+                for context in universe.iter() {
+                    if let Some(new_context) = regex_match.fit(&context, &mut cache)? {
+                        fitting_contexts.insert(new_context);
+                    }
+                }
 
                 VarContextUniverse {
                     contents: fitting_contexts,
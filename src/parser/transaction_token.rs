@@ -0,0 +1,61 @@
+use core::fmt;
+
+use print_macros::*;
+
+use crate::lexer::{self, LexogramType::*};
+
+use super::error::{FailureExplanation, FailureReason, ParserError};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Transaction {
+    Begin,
+    Commit,
+    Rollback,
+}
+
+impl fmt::Display for Transaction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Transaction::Begin => write!(f, "begin"),
+            Transaction::Commit => write!(f, "commit"),
+            Transaction::Rollback => write!(f, "rollback"),
+        }
+    }
+}
+
+// the keywords are only taken as such when they stand alone, begin(1) is still a relation
+pub fn read_transaction(
+    lexograms: &Vec<lexer::Lexogram>,
+    start_cursor: usize,
+) -> Result<Result<(Transaction, usize), FailureExplanation>, ParserError> {
+    printparse!("read_transaction at {}", start_cursor);
+
+    let transaction = match &lexograms[start_cursor].l_type {
+        Identifier(name) if name == "begin" => Transaction::Begin,
+        Identifier(name) if name == "commit" => Transaction::Commit,
+        Identifier(name) if name == "rollback" => Transaction::Rollback,
+        lex => {
+            return Ok(Err(FailureExplanation {
+                lex_pos: start_cursor,
+                if_it_was: "transaction".into(),
+                failed_because: FailureReason::Other(format!(
+                    "{lex:?} is not begin, commit nor rollback"
+                )),
+                parent_failure: vec![],
+            }))
+        }
+    };
+
+    match lexograms.get(start_cursor + 1) {
+        Some(lexer::Lexogram {
+            l_type: LeftParenthesis,
+            ..
+        }) => Ok(Err(FailureExplanation {
+            lex_pos: start_cursor + 1,
+            if_it_was: "transaction".into(),
+            failed_because: FailureReason::Other(format!("{transaction} is followed by arguments")),
+            parent_failure: vec![],
+        })),
+        _ => Ok(Ok((transaction, start_cursor + 1))),
+    }
+}
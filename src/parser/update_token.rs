@@ -6,7 +6,10 @@ use crate::lexer::LexogramType::*;
 
 use crate::{
     lexer,
-    parser::{defered_relation_token::read_defered_relation, error::FailureExplanation},
+    parser::{
+        defered_relation_token::read_defered_relation,
+        error::{FailureExplanation, FailureReason},
+    },
 };
 
 use super::defered_relation_token::DeferedRelation;
@@ -34,6 +37,15 @@ pub fn read_update(
         SpectingUpdate,
         SpectingDeferedRelationGoal,
     }
+    impl IntensionalParserStates {
+        fn expected(&self) -> Vec<&'static str> {
+            match self {
+                SpectingDeferedRelationFilter => vec!["a relation"],
+                SpectingUpdate => vec!["`->`"],
+                SpectingDeferedRelationGoal => vec!["a relation"],
+            }
+        }
+    }
     use IntensionalParserStates::*;
 
     printparse!("read_intensional at {}", start_cursor);
@@ -53,7 +65,7 @@ pub fn read_update(
                         return Ok(Err(FailureExplanation {
                             lex_pos: i,
                             if_it_was: "update".into(),
-                            failed_because: "specting relation".into(),
+                            failed_because: FailureReason::Other("specting relation".into()),
                             parent_failure: (vec![e]),
                         }))
                     }
@@ -71,7 +83,7 @@ pub fn read_update(
                         return Ok(Err(FailureExplanation {
                             lex_pos: i,
                             if_it_was: "update".into(),
-                            failed_because: "specting relation".into(),
+                            failed_because: FailureReason::Other("specting relation".into()),
                             parent_failure: (vec![e]),
                         }))
                     }
@@ -92,7 +104,7 @@ pub fn read_update(
                 return Ok(Err(FailureExplanation {
                     lex_pos: i,
                     if_it_was: "update".into(),
-                    failed_because: format!("pattern missmatch on {:#?} state", state).into(),
+                    failed_because: FailureReason::Specting(state.expected()),
                     parent_failure: vec![],
                 }))
             }
@@ -101,7 +113,7 @@ pub fn read_update(
     Ok(Err(FailureExplanation {
         lex_pos: lexograms.len() - 1,
         if_it_was: "update".into(),
-        failed_because: "file ended".into(),
+        failed_because: FailureReason::FileEnded,
         parent_failure: vec![],
     }))
 }
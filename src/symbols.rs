@@ -0,0 +1,157 @@
+use std::collections::BTreeSet;
+
+use crate::diagnostic::{Diagnostic, Span};
+use crate::engine::{read_source, type_inference::TypeInference, Engine, RelId};
+use crate::lexer::{Lexogram, LexogramType::*};
+use crate::parser::{
+    aggregate_token::AggregateFunction, assumption_token::Assumption,
+    function_token::BuiltInFunction, line_token::Line, schema_token::ColumnType, HasRelId,
+    SourceLine,
+};
+
+// a relation written on the source
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Occurrence {
+    pub rel_id: RelId,
+    pub span: Span,
+    // the head of a fact or a rule
+    pub is_definition: bool,
+}
+
+// where the relations of a source are used and defined, for editors to move around it
+#[derive(Debug, Clone)]
+pub struct SymbolIndex {
+    pub occurrences: Vec<Occurrence>,
+    pub types: TypeInference,
+    pub diagnostics: Vec<Diagnostic>,
+}
+
+impl SymbolIndex {
+    pub fn new(source: &str) -> Self {
+        let (lexograms, lines, mut diagnostics) = read_source(source);
+
+        let heads: Vec<(RelId, usize)> = lines
+            .iter()
+            .filter_map(|SourceLine { line, span, .. }| match line {
+                Line::Assumption(Assumption::RelationInmediate(rel)) if !rel.negated => {
+                    Some((rel.get_rel_id(), span.start))
+                }
+                Line::Assumption(Assumption::RelationDefered(rel)) => {
+                    Some((rel.get_rel_id(), span.start))
+                }
+                Line::Assumption(Assumption::Conditional(cond)) => {
+                    Some((cond.get_rel_id(), span.start))
+                }
+                _ => None,
+            })
+            .collect();
+
+        let occurrences = get_occurrences(&lexograms)
+            .into_iter()
+            .map(|(rel_id, span)| Occurrence {
+                is_definition: heads.contains(&(rel_id.to_owned(), span.start)),
+                rel_id,
+                span,
+            })
+            .collect();
+
+        let types = Engine::new().infer_types(&lines);
+        diagnostics.extend(types.get_diagnostics());
+
+        Self {
+            occurrences,
+            types,
+            diagnostics,
+        }
+    }
+
+    pub fn get_occurrence_at(&self, byte: usize) -> Option<&Occurrence> {
+        self.occurrences
+            .iter()
+            .find(|occurrence| occurrence.span.start <= byte && byte <= occurrence.span.end)
+    }
+
+    pub fn get_definitions(&self, rel_id: &RelId) -> Vec<&Occurrence> {
+        self.occurrences
+            .iter()
+            .filter(|occurrence| occurrence.is_definition && occurrence.rel_id == *rel_id)
+            .collect()
+    }
+
+    pub fn get_rel_ids(&self) -> BTreeSet<RelId> {
+        self.occurrences
+            .iter()
+            .map(|occurrence| occurrence.rel_id.to_owned())
+            .collect()
+    }
+
+    // the columns of the relation with the types inferred for them
+    pub fn describe(&self, rel_id: &RelId) -> String {
+        let columns = match self.types.column_types.get(rel_id) {
+            Some(column_types) => column_types.to_owned(),
+            None => vec![ColumnType::Any; rel_id.column_count],
+        };
+        let columns = columns
+            .iter()
+            .map(|column_type| column_type.to_string())
+            .collect::<Vec<String>>()
+            .join(", ");
+        format!(
+            "{}({columns})\n{} columns, {} facts and rules",
+            rel_id.identifier,
+            rel_id.column_count,
+            self.get_definitions(rel_id).len()
+        )
+    }
+}
+
+// an identifier opening a parenthesis is a relation, unless it names a built in
+fn get_occurrences(lexograms: &[Lexogram]) -> Vec<(RelId, Span)> {
+    let mut ret = vec![];
+    for (i, lex) in lexograms.iter().enumerate() {
+        let name = match (&lex.l_type, lexograms.get(i + 1).map(|next| &next.l_type)) {
+            (Identifier(name), Some(LeftParenthesis)) => name,
+            _ => continue,
+        };
+        if name == "matches"
+            || BuiltInFunction::from_name(name).is_some()
+            || AggregateFunction::from_name(name).is_some()
+        {
+            continue;
+        }
+        ret.push((
+            RelId {
+                identifier: name.to_owned(),
+                column_count: count_columns(lexograms, i + 1),
+            },
+            Span {
+                start: lex.pos_s,
+                end: lex.pos_f,
+            },
+        ));
+    }
+    ret
+}
+
+// the items between the parenthesis opened at the cursor and the one closing it
+fn count_columns(lexograms: &[Lexogram], open: usize) -> usize {
+    if let Some(RightParenthesis) = lexograms.get(open + 1).map(|lex| &lex.l_type) {
+        return 0;
+    }
+    let mut depth = 0;
+    let mut comas = 0;
+    for lex in &lexograms[open..] {
+        match lex.l_type {
+            LeftParenthesis | LeftBracket | LeftKey => depth += 1,
+            RightParenthesis | RightBracket | RightKey => {
+                depth -= 1;
+                if depth == 0 {
+                    break;
+                }
+            }
+            Coma if depth == 1 => comas += 1,
+            _ => (),
+        }
+    }
+    comas + 1
+}
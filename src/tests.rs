@@ -1,7 +1,17 @@
 #[cfg(test)]
 
 mod tests {
-    use crate::engine::Engine;
+    use crate::diagnostic::{self, Span};
+    use crate::engine::{read_source, Engine, EvaluationMode, RelId};
+    use crate::formatter::format_source;
+    use crate::lexer::{is_identifier, lex, LexogramType};
+    use crate::parser::{
+        assumption_token::Assumption,
+        expresion_token::ExpresionSemantics,
+        line_token::{Comment, Line},
+        statement_token::StatementSemantics,
+    };
+    use crate::symbols::SymbolIndex;
 
     #[test]
     fn query_full_table_1() {
@@ -350,4 +360,1025 @@ mod tests {
             )
         );
     }
+
+    #[test]
+    fn update_1() {
+        let mut engine = Engine::new();
+        assert_eq!(
+            "\n(1, 15)\n(2, 17)\n",
+            engine.input(
+                "balance(1,5) balance(2,7) balance(acc,x) -> balance(acc,x+10) balance(_,_)?"
+                    .into(),
+            )
+        );
+    }
+
+    #[test]
+    fn update_2() {
+        let mut engine = Engine::new();
+        assert_eq!(
+            "\n(1, 5)\n(2, 0)\n",
+            engine.input(
+                "balance(1,5) balance(2,7) balance(2,_) -> balance(2,0) balance(_,_)?".into()
+            )
+        );
+    }
+
+    #[test]
+    fn update_into_other_relation() {
+        let mut engine = Engine::new();
+        assert_eq!(
+            "\nEmpty Result\n\n(\"ana\")\n",
+            engine
+                .input("pending(\"ana\") pending(name) -> done(name) pending(_)? done(_)?".into())
+        );
+    }
+
+    #[test]
+    fn semi_naive_transitive_closure() {
+        let mut engine = Engine::new();
+        engine.set_recursion_limit(2);
+        engine.set_evaluation_mode(EvaluationMode::SemiNaive);
+        assert_eq!(
+            "\n(1, 2)\n(1, 3)\n(1, 4)\n(1, 5)\n(1, 6)\n",
+            engine.input(
+                "edge(1,2) edge(2,3) edge(3,4) edge(4,5) edge(5,6) path(a,b) :- edge(a,b) path(a,b) :- path(a,mid) && edge(mid,b) path(1,_)?".into(),
+            )
+        );
+    }
+
+    #[test]
+    fn semi_naive_recursion() {
+        let mut engine = Engine::new();
+        engine.set_recursion_limit(0);
+        engine.set_evaluation_mode(EvaluationMode::SemiNaive);
+        assert_eq!(
+            "\n(0)\n(1)\n(2)\n(3)\n(4)\n(5)\n",
+            engine.input("test(a+1) :- test(a) && a < 5 test(0) test(_)?".into(),)
+        );
+    }
+
+    #[test]
+    fn semi_naive_hypothesis() {
+        let mut engine = Engine::new();
+        engine.set_evaluation_mode(EvaluationMode::SemiNaive);
+        assert_eq!(
+            "\n(1, 2)\n(3, 4)\n\n(1, 2)\n(1, 3)\n(1, 4)\n(2, 3)\n(2, 4)\n(3, 4)\n",
+            engine.input(
+                "edge(1,2) edge(3,4) path(a,b) :- edge(a,b) path(a,b) :- path(a,mid) && edge(mid,b) path(_,_)? {edge(2,3)}=>path(_,_)?".into(),
+            )
+        );
+    }
+
+    #[test]
+    fn tabled_left_recursion() {
+        let mut engine = Engine::new();
+        engine.set_recursion_limit(1);
+        engine.set_evaluation_mode(EvaluationMode::Tabled);
+        assert_eq!(
+            "\n(1, 2)\n(1, 3)\n(1, 4)\n(1, 5)\n(1, 6)\n",
+            engine.input(
+                "edge(1,2) edge(2,3) edge(3,4) edge(4,5) edge(5,6) path(a,b) :- path(a,mid) && edge(mid,b) path(a,b) :- edge(a,b) path(1,_)?".into(),
+            )
+        );
+    }
+
+    #[test]
+    fn tabled_conectivity_problem() {
+        let mut engine = Engine::new();
+        engine.set_recursion_limit(0);
+        engine.set_evaluation_mode(EvaluationMode::Tabled);
+        assert_eq!(
+            "\n(1, 1)\n(1, 2)\n(1, 3)\n(1, 4)\n(2, 1)\n(2, 2)\n(2, 3)\n(2, 4)\n(3, 1)\n(3, 2)\n(3, 3)\n(3, 4)\n(4, 1)\n(4, 2)\n(4, 3)\n(4, 4)\n",
+            engine.input(
+                "edge(1,2) edge(3,4) edge(2,3) conected(a,b) :- conected(a,mid) && edge(mid,b) conected(a,a):- true edge(b,a) :- edge(a,b) conected(_,_)?".into(),
+            )
+        );
+    }
+
+    #[test]
+    fn tabled_hypothesis() {
+        let mut engine = Engine::new();
+        engine.set_evaluation_mode(EvaluationMode::Tabled);
+        assert_eq!(
+            "\n(1, 2)\n(3, 4)\n\n(1, 2)\n(1, 3)\n(1, 4)\n(2, 3)\n(2, 4)\n(3, 4)\n\n(1, 2)\n(3, 4)\n",
+            engine.input(
+                "edge(1,2) edge(3,4) conected(a,b) :- conected(a,mid) && edge(mid,b) conected(a,a):- true conected(_,_)? {edge(2,3)}=>conected(_,_)? conected(_,_)?".into(),
+            )
+        );
+    }
+
+    #[test]
+    fn negation_1() {
+        let mut engine = Engine::new();
+        assert_eq!(
+            "\n(2)\n\n(2)\n\n(2)\n",
+            engine.input(
+                "a(1) a(2) b(1) t(x) :- a(x) && !b(x) u(x) :- !b(x) && a(x) v(x) :- a(x) && !(b(x)) t(_)? u(_)? v(_)?".into(),
+            )
+        );
+    }
+
+    #[test]
+    fn stratified_negation() {
+        let mut engine = Engine::new();
+        engine.set_recursion_limit(2);
+        assert_eq!(
+            "\n(1, 1)\n(6, 1)\n(6, 6)\n",
+            engine.input(
+                "node(1) node(6) edge(1,2) edge(2,3) edge(3,4) edge(4,5) edge(5,6) path(a,b) :- edge(a,b) path(a,b) :- path(a,mid) && edge(mid,b) unreachable(a,b) :- node(a) && node(b) && !path(a,b) unreachable(_,_)?".into(),
+            )
+        );
+    }
+
+    #[test]
+    fn negation_cycle_is_rejected() {
+        let mut engine = Engine::new();
+        let out = engine.input("a(1) p(x) :- a(x) && !q(x) q(x) :- a(x) && !p(x)".into());
+        assert!(out.contains("error[E0201]: p/1, q/1 depend on their own negation"));
+        assert_eq!("\n(1)\n", engine.input("p(_)?".into()));
+    }
+
+    #[test]
+    fn semi_naive_stratified_negation() {
+        let mut engine = Engine::new();
+        engine.set_evaluation_mode(EvaluationMode::SemiNaive);
+        assert_eq!(
+            "\n(4)\n",
+            engine.input(
+                "node(1) node(2) node(3) node(4) edge(1,2) edge(2,3) reached(b) :- edge(1,b) reached(b) :- reached(a) && edge(a,b) lonely(x) :- node(x) && !reached(x) && !edge(x,_) lonely(_)?".into(),
+            )
+        );
+    }
+
+    #[test]
+    fn aggregates() {
+        let mut engine = Engine::new();
+        engine.input(
+            "customer(1) customer(2) customer(3) order(1,1,10) order(1,2,10) order(1,3,25) order(2,4,5) edge(1,2) edge(2,3)".into(),
+        );
+        assert_eq!(
+            "\n(1, 45)\n(2, 5 )\n(3, 0 )\n",
+            engine.input(
+                "total(c, s) :- customer(c) && s = sum(x : order(c, _, x)) total(_,_)?".into()
+            )
+        );
+        assert_eq!(
+            "\n(1, 3)\n(2, 1)\n",
+            engine.input(
+                "orders(c, n) :- customer(c) && n = count(id : order(c, id, _)) && n > 0 orders(_,_)?".into()
+            )
+        );
+        assert_eq!(
+            "\n(2)\n",
+            engine.input("edges(n) :- n = count(_ : edge(_,_)) edges(_)?".into())
+        );
+        assert_eq!(
+            "\n(1, 10, 25, 15)\n(2, 5 , 5 , 5 )\n",
+            engine.input(
+                "stats(c, l, h, a) :- customer(c) && l = min(x : order(c, id, x)) && h = max(x : order(c, id, x)) && a = avg(x : order(c, id, x)) stats(_,_,_,_)?".into()
+            )
+        );
+        let out =
+            engine.input("name(1, \"ana\") names(s) :- s = sum(n : name(_, n)) names(_)?".into());
+        assert!(out.contains("error[E0200]: cant add up the non numeric ana on sum(n : name(_,n))"));
+        assert!(!out.contains("Empty Result"));
+        assert!(engine
+            .input("big(c) :- customer(c) && sum(n : name(c, n)) > 1 big(_)?".into())
+            .contains("error[E0200]: cant add up the non numeric ana on sum(n : name(c,n))"));
+    }
+
+    #[test]
+    fn aggregate_cycle_is_rejected() {
+        let mut engine = Engine::new();
+        let out = engine.input("a(1) p(n) :- a(n) && n = count(_ : p(_))".into());
+        assert!(out.contains("error[E0201]: p/1 depends on its own negation"));
+        let out = engine.input("q(n) :- a(n) && !(n = count(_ : q(_)))".into());
+        assert!(out.contains("error[E0201]: q/1 depends on its own negation"));
+    }
+
+    #[test]
+    fn conjuncts_wait_for_their_bindings() {
+        let mut engine = Engine::new();
+        assert_eq!(
+            "\n(3, 30)\n",
+            engine.input(
+                "a(1) a(2) a(3) b(2) t(x,y) :- y = x * 10 && x > 1 && !b(x) && a(x) t(_,_)?".into()
+            )
+        );
+    }
+
+    #[test]
+    fn explain() {
+        let mut engine = Engine::new();
+        engine.input("edge(1,2) edge(2,3) edge(3,4) conected(a,b) :- edge(a,b) conected(a,b) :- conected(a,c) && edge(c,b)".into());
+        assert_eq!(
+            "conected(1,_) on TopDown evaluation\nfacts: 0\nrule 1: conected(a,b) :- edge(a,b)\n  bindings: |a:1|\n  relation edge(a,b) [bf] (in: 1, out: 1)\n  deductions: 1\nrule 2: conected(a,b) :- (conected(a,c) && edge(c,b))\n  bindings: |a:1|\n  and (most selective first) (in: 1, out: 2)\n    relation edge(c,b) [ff] (in: 1, out: 3)\n    relation conected(a,c) [bb] (in: 3, out: 2)\n  deductions: 2\n",
+            engine.explain_input("conected(1,_)?".into())
+        );
+        assert_eq!(
+            "only queries can be explained, found: edge(1,_)\n",
+            engine.explain_input("edge(1,_)".into())
+        );
+    }
+
+    #[test]
+    fn proof_trees() {
+        let mut engine = Engine::new();
+        engine.input("edge(1,2) edge(2,3) edge(3,4) edge(4,4) node(5) conected(a,b) :- edge(a,b) conected(a,b) :- conected(a,c) && edge(c,b) isolated(x) :- node(x) && !edge(x,_) && x > 2".into());
+        assert_eq!(
+            "\nconected(1,4) by conected(a,b) :- (conected(a,c) && edge(c,b)) with |a:1|b:4|c:3|\n  conected(1,3) by conected(a,b) :- (conected(a,c) && edge(c,b)) with |a:1|b:3|c:2|\n    conected(1,2) by conected(a,b) :- edge(a,b) with |a:1|b:2|\n      edge(1,2) is a fact\n    edge(2,3) is a fact\n  edge(3,4) is a fact\n",
+            engine.input("conected(1,4)??".into())
+        );
+        assert_eq!(
+            "\nisolated(5) by isolated(x) :- ((node(x) && !(edge(x,_))) && (x>2)) with |x:5|\n  node(5) is a fact\n  !(edge(x,_)) holds\n  (x>2) holds\n",
+            engine.input("isolated(_)??".into())
+        );
+        assert_eq!("\nEmpty Result\n", engine.input("conected(9,9)??".into()));
+    }
+
+    #[test]
+    fn why_lines_are_ingested() {
+        let mut engine = Engine::new();
+        engine.input("edge(1,2)".into());
+        let (_, mut lines, _) = read_source("edge(1,_)??");
+        assert_eq!(
+            "\nedge(1,2) is a fact\n",
+            engine.ingest_line(lines.remove(0).line).unwrap()
+        );
+    }
+
+    #[test]
+    fn escaped_strings() {
+        let mut engine = Engine::new();
+        assert_eq!(
+            "\n(\"say \\\"hi\\\"\")\n",
+            engine.input("name(\"say \\\"hi\\\"\") name(_)?".into())
+        );
+    }
+
+    #[test]
+    fn parenthesized_right_operand() {
+        let mut engine = Engine::new();
+        assert_eq!(
+            "\n(3)\n",
+            engine.input("n(1) n(3) big(a) :- n(a) && a * (a - 1) > 5 big(_)?".into())
+        );
+    }
+
+    #[test]
+    fn rules_display_as_source() {
+        let mut engine = Engine::new();
+        assert_eq!(
+            "\ns(3,1) by s(a,k) :- ((n(a) && (a-(a-1)>0)) && (k=count(_ : (n(_) && t(_,\"x\"))))) with |a:3|k:1|\n  n(3) is a fact\n  (a-(a-1)>0) holds\n  (k=count(_ : (n(_) && t(_,\"x\")))) holds\n",
+            engine.input("n(3) t(3, \"x\") s(a, k) :- n(a) && a - (a - 1) > 0 && k = count(_ : n(_) && t(_, \"x\")) s(_,_)??".into())
+        );
+    }
+
+    #[test]
+    fn floats_keep_their_fraction() {
+        let mut engine = Engine::new();
+        assert_eq!("\n(2.0)\n", engine.input("n(2e0) n(_)?".into()));
+        assert_eq!(
+            "\nh(2.0) by h(x) :- (n(x) && (x=2.0)) with |x:2.0|\n  n(2.0) is a fact\n  (x=2.0) holds\n",
+            engine.input("h(x) :- n(x) && x = 2e0 h(_)??".into())
+        );
+    }
+
+    fn lexed(source: &str) -> Vec<String> {
+        lex(&source.to_string())
+            .unwrap()
+            .into_iter()
+            .map(|l| match l.l_type {
+                LexogramType::Identifier(name) => name,
+                other => format!("{other:?}"),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn underscores_in_identifiers() {
+        assert_eq!(vec!["a_b"], lexed("a_b"));
+        assert_eq!(vec!["_a"], lexed("_a"));
+        assert_eq!(
+            vec![
+                "f",
+                "LeftParenthesis",
+                "Any",
+                "Coma",
+                "x_",
+                "RightParenthesis"
+            ],
+            lexed("f(_, x_)")
+        );
+        assert!(is_identifier("a_b") && is_identifier("_a"));
+        assert!(!is_identifier("_") && !is_identifier("true") && !is_identifier("full name"));
+    }
+
+    // removes the storage directory once the test is done with it
+    struct StoragePath(String);
+
+    impl std::ops::Deref for StoragePath {
+        type Target = str;
+        fn deref(&self) -> &str {
+            &self.0
+        }
+    }
+
+    impl Drop for StoragePath {
+        fn drop(&mut self) {
+            let _ = std::fs::remove_dir_all(&self.0);
+        }
+    }
+
+    fn storage_path(name: &str) -> StoragePath {
+        let path = std::env::temp_dir().join(format!("datalang_{}_{name}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&path);
+        StoragePath(path.to_string_lossy().to_string())
+    }
+
+    #[test]
+    fn storage_survives_reopening() {
+        let path = storage_path("reopen");
+        {
+            let mut engine = Engine::open(&path).unwrap();
+            engine.input("schema edge(from: number, to: number) person(\"ana \\\"la\\\" lopez\", [1,[2]]) person(\"bob\", [3]) edge(1,2) edge(2,3) conected(a,b) :- edge(a,b) && a * (b - 1) > 0 conected(a,b) :- conected(a,c) && edge(c,b) edge(2,3) -> edge(2,4) !edge(1,2) edge(1,3) edge(3,5)".into());
+            engine.snapshot().unwrap();
+            engine.input("person(\"carla\", []) !person(\"bob\", [3]) seen(true, [false, null], 12.50, 9007199254740993, {id: 1e3, \"full name\": \"ana\"})".into());
+        }
+
+        let mut engine = Engine::open(&path).unwrap();
+        assert_eq!(
+            "\n(\"ana \\\"la\\\" lopez\", [1,[2]])\n(\"carla\"           , []     )\n\n(1, 3)\n(1, 5)\n",
+            engine.input("person(_,_)? conected(1,_)?".into())
+        );
+        assert_eq!(
+            "\n(true, [false,null], 12.5, 9007199254740993, {\"full name\": \"ana\", id: 1000.0})\n",
+            engine.input("seen(_,_,_,9007199254740993,_)?".into())
+        );
+        assert!(engine
+            .input("edge(\"x\", 1)".into())
+            .contains("column 1 (from) of edge expects number"));
+    }
+
+    #[test]
+    fn storage_discards_torn_log_tail() {
+        let path = storage_path("torn");
+        {
+            let mut engine = Engine::open(&path).unwrap();
+            engine.input("a(1) a(2)".into());
+        }
+        let log_path = std::path::Path::new(&*path).join("log");
+        let mut log = std::fs::read_to_string(&log_path).unwrap();
+        log += "+a 1:n3;\n+a 1:n4";
+        std::fs::write(&log_path, log).unwrap();
+
+        let mut engine = Engine::open(&path).unwrap();
+        assert_eq!("\n(1)\n(2)\n", engine.input("a(_)? a(4)".into()));
+
+        let mut engine = Engine::open(&path).unwrap();
+        assert_eq!("\n(1)\n(2)\n(4)\n", engine.input("a(_)?".into()));
+    }
+
+    #[test]
+    fn storage_replay_reports_bad_rules() {
+        let write_log = |path: &str, entries: &[&str]| {
+            std::fs::create_dir_all(path).unwrap();
+            let log: String = entries
+                .iter()
+                .map(|entry| format!(":{}:{entry}\n", entry.len()))
+                .collect();
+            std::fs::write(std::path::Path::new(path).join("log"), log + ".\n").unwrap();
+        };
+
+        let path = storage_path("duplicate_rule");
+        write_log(&path, &["p(x) :- a(x)", "p(x) :- a(x)"]);
+        let mut engine = Engine::open(&path).unwrap();
+        assert_eq!("\n(1)\n", engine.input("a(1) p(_)?".into()));
+
+        let path = storage_path("bad_rule");
+        write_log(&path, &["schema p(x: number)", "p(x, y) :- a(x, y)"]);
+        assert_eq!(
+            "p has 1 columns on its schema, found 2: schema p(x: number)",
+            Engine::open(&path).unwrap_err()
+        );
+    }
+
+    #[test]
+    fn storage_rejects_negation_cycles() {
+        let path = storage_path("cycle");
+        std::fs::create_dir_all(&*path).unwrap();
+        let rules = ["p(x) :- a(x) && !q(x)", "q(x) :- a(x) && !p(x)"];
+        let log: String = rules
+            .iter()
+            .map(|rule| format!(":{}:{rule}\n", rule.len()))
+            .collect();
+        std::fs::write(std::path::Path::new(&*path).join("log"), log + ".\n").unwrap();
+
+        assert_eq!(
+            "p/1, q/1 depend on their own negation",
+            Engine::open(&path).unwrap_err()
+        );
+    }
+
+    #[test]
+    fn transactions() {
+        let mut engine = Engine::new();
+        assert_eq!(
+            "\n(1)\n\n(1)\n(4)\n",
+            engine.input("a(1) begin a(2) !a(1) rollback a(_)? begin a(4) commit a(_)?".into())
+        );
+        let out = engine.input("begin a(5) b(x) :- a(x) && !b(x)".into());
+        assert!(out.contains("error[E0201]: b/1 depends on its own negation"));
+        assert!(!out.contains("rolled back"));
+        assert_eq!("\n(1)\n(4)\n(5)\n", engine.input("a(_)?".into()));
+        assert_eq!("\n(1)\n(4)\n", engine.input("rollback a(_)?".into()));
+        assert!(engine
+            .input("commit".into())
+            .contains("there is no transaction to commit"));
+    }
+
+    #[test]
+    fn storage_skips_uncommitted_transactions() {
+        let path = storage_path("transaction");
+        {
+            let mut engine = Engine::open(&path).unwrap();
+            engine.input("a(1) begin a(2) a(3) commit begin a(4)".into());
+        }
+
+        let mut engine = Engine::open(&path).unwrap();
+        assert_eq!("\n(1)\n(2)\n(3)\n", engine.input("a(_)?".into()));
+    }
+
+    #[test]
+    fn snapshots_leave_open_transactions_out() {
+        let path = storage_path("snapshot_transaction");
+        {
+            let mut engine = Engine::open(&path).unwrap();
+            engine.input("a(1) begin a(2)".into());
+            engine.snapshot().unwrap();
+            assert_eq!("\n(1)\n", engine.input("rollback a(_)?".into()));
+        }
+        let mut engine = Engine::open(&path).unwrap();
+        assert_eq!("\n(1)\n", engine.input("a(_)? begin a(3)".into()));
+        engine.snapshot().unwrap();
+        engine.input("commit".into());
+
+        let mut engine = Engine::open(&path).unwrap();
+        assert_eq!("\n(1)\n(3)\n", engine.input("a(_)?".into()));
+    }
+
+    #[test]
+    fn storage_keeps_commits_when_the_snapshot_fails() {
+        let path = storage_path("snapshot_fails");
+        {
+            let mut engine = Engine::open(&path).unwrap();
+            // the snapshot is written to snapshot.tmp first, a directory there makes it fail
+            std::fs::create_dir(std::path::Path::new(&*path).join("snapshot.tmp")).unwrap();
+            let facts: String = (0..1000).map(|i| format!("a({i}) ")).collect();
+            let output = engine.input(format!("begin {facts}commit"));
+            assert!(output.contains("warning[W0204]: the snapshot couldnt be taken"));
+            assert!(!output.contains("rolled back"));
+        }
+        std::fs::remove_dir(std::path::Path::new(&*path).join("snapshot.tmp")).unwrap();
+
+        let mut engine = Engine::open(&path).unwrap();
+        assert_eq!(
+            "\n(1000)\n",
+            engine.input("n(c) :- c = count(x : a(x)) n(_)?".into())
+        );
+    }
+
+    #[test]
+    fn storage_logs_what_a_failed_line_changed() {
+        let path = storage_path("failed_line");
+        let state = {
+            let mut engine = Engine::open(&path).unwrap();
+            engine.input("schema b(x: number) a(1) a(\"s\")".into());
+            assert!(engine
+                .input("a(x) -> b(x)".into())
+                .contains("of b expects number"));
+            engine.input("a(_)? b(_)?".into())
+        };
+
+        let mut engine = Engine::open(&path).unwrap();
+        assert_eq!(state, engine.input("a(_)? b(_)?".into()));
+    }
+
+    #[test]
+    fn indexed_lookups() {
+        let mut engine = Engine::new();
+        assert_eq!(
+            "\n(1, 2)\n(1, 4)\n\n(2, 3)\n(2, 4)\n\n(1, 3)\n(1, 4)\n",
+            engine.input(
+                "edge(1,2) edge(1,3) edge(2,3) edge(_,4) !edge(1,3) edge(1,_)? edge(2,_)? path(a,b) :- edge(a,c) && edge(c,b) path(1,_)?"
+                    .into()
+            )
+        );
+    }
+
+    #[test]
+    fn schemas() {
+        let mut engine = Engine::new();
+        engine.input(
+            "schema person(name: string, age: number, tags: [string]) person(\"ana\", 30, [\"a\"])"
+                .into(),
+        );
+        assert!(engine
+            .input("person(\"bob\", \"old\", [])".into())
+            .contains("error[E0200]: column 2 (age) of person expects number, found \"old\""));
+        assert!(engine
+            .input("person(n, 1, 1) :- other(n)".into())
+            .contains("error[E0200]: column 3 (tags) of person expects [string], found 1 on the rule head person(n,1,1)"));
+        assert!(engine
+            .input("person(\"carla\")".into())
+            .contains("error[E0200]: person has 3 columns on its schema, found 1: schema person(name: string, age: number, tags: [string])"));
+        assert_eq!(
+            "\n(\"ana\", 30, [\"a\"])\n",
+            engine.input("person(_,_,_)?".into())
+        );
+        assert!(engine
+            .input("schema p(x: bogus)".into())
+            .contains("bogus is not number, string, bool, record, any nor [type]"));
+    }
+
+    #[test]
+    fn schemas_export_and_positions() {
+        let mut engine = Engine::new();
+        engine.input("schema p(x: number, y: string) p(1, \"a b\") q(x) :- p(x, _)".into());
+        let export = format!("{engine}");
+        assert_eq!(
+            "schema p(x: number, y: string)\np(1,\"a b\")\nq(x) :- p(x,_)\n",
+            export
+        );
+        let mut imported = Engine::new();
+        assert_eq!("", imported.input(export));
+        assert_eq!("\n(1)\n", imported.input("q(_)?".into()));
+
+        let (_, diagnostics) = imported.run("p(3, \"c\") p(\"a\", \"d\") p(4, \"e\")".into());
+        assert_eq!(Some(Span { start: 10, end: 21 }), diagnostics[0].span);
+    }
+
+    fn type_warnings(engine: &Engine, source: &str) -> Vec<String> {
+        engine
+            .type_diagnostics(&read_source(source).1)
+            .into_iter()
+            .map(|diagnostic| diagnostic.message)
+            .collect()
+    }
+
+    #[test]
+    fn type_inference() {
+        let mut engine = Engine::new();
+        engine.input("age(\"ana\", 30) adult(n) :- age(n, a) && a > 17".into());
+        assert_eq!(
+            "adult(string)\nage(string, number)\n",
+            engine.infer_types(&[]).to_string()
+        );
+        assert_eq!(
+            vec![
+                "age(\"bob\",\"old\"): column 2 of age holds number since age(\"ana\",30), found \"old\"",
+                "older(n) :- (age(n,a) && (a>\"x\")): (a>\"x\") compares number with string",
+                "tagged(n,t) :- age(n,[t,...rest]): on column 2 of age (number since age(\"ana\",30)), [t,...rest] destructures a number",
+            ],
+            type_warnings(&engine, "age(\"bob\", \"old\") older(n) :- age(n, a) && a > \"x\" tagged(n, t) :- age(n, [t,...rest])")
+        );
+        assert!(type_warnings(&engine, "age(\"bob\", 41) adult(\"carla\")?").is_empty());
+
+        // the input warns before running, and the facts it holds keep shaping the types
+        let source = "age(\"dan\", 50)\nage(\"eve\", \"young\")";
+        let (_, diagnostics) = engine.run(source.into());
+        assert_eq!(1, diagnostics.len());
+        assert_eq!(Some(Span { start: 15, end: 34 }), diagnostics[0].span);
+        engine.input("begin age(1, 1) rollback".into());
+        assert!(type_warnings(&engine, "age(\"gus\", 1)").is_empty());
+    }
+
+    #[test]
+    fn type_conflicts_name_both_facts() {
+        let engine = Engine::new();
+        assert_eq!(
+            vec!["p(\"a\"): column 1 of p holds number since p(1), found \"a\""],
+            type_warnings(&engine, "p(1) p(\"a\")")
+        );
+        assert_eq!(
+            vec!["p(1): column 1 of p holds string since p(\"a\"), found 1"],
+            type_warnings(&engine, "p(\"a\") p(1)")
+        );
+        assert_eq!(
+            vec!["p(x+1) :- q(x): on column 1 of p (string since p(\"a\")), x+1 is number, not string"],
+            type_warnings(&engine, "p(x + 1) :- q(x) p(\"a\")")
+        );
+    }
+
+    #[test]
+    fn bools_and_nulls() {
+        let mut engine = Engine::new();
+        assert_eq!(
+            "\n(\"ana\"  )\n(\"carla\")\n\n(\"bob\"  , false, null)\n(\"carla\", true , null)\n\n(\"ana\")\n",
+            engine.input("user(\"ana\", true, 30) user(\"bob\", false, null) user(\"carla\", true, null) active(n) :- user(n, true, _) adult(n) :- user(n, _, age) && age > 17 active(_)? user(_, _, null)? adult(_)?".into())
+        );
+        assert_eq!(
+            "\n(true)\n",
+            engine.input("flag(false) flag(true) on(x) :- flag(x) && x > false on(_)?".into())
+        );
+        assert!(engine
+            .input("schema setting(name: string, on: bool) setting(\"dark\", null) setting(\"beta\", 1)".into())
+            .contains("error[E0200]: column 2 (on) of setting expects bool, found 1"));
+    }
+
+    #[test]
+    fn exact_numbers() {
+        let mut engine = Engine::new();
+        assert_eq!(
+            "\n(9007199254740993)\n\n(0.3)\n\n(0.33333333333333333333)\n\n(2.5)\n",
+            engine.input("id(9007199254740992) id(9007199254740993) id(9007199254740993)? price(\"a\", 0.1) price(\"b\", 0.2) total(t) :- t = sum(p : price(_, p)) total(_)? third(x) :- x = 1 / 3 third(_)? half(x) :- 2 * x = 5 half(_)?".into())
+        );
+        assert_eq!(
+            "\n(7)\n\n(9223372036854775808)\n\n(1000.0)\n",
+            engine.input("diff(x) :- 10 - x = 3 diff(_)? big(x) :- x = 9223372036854775807 + 1 big(_)? f(1e3) same(x) :- f(x) && x = 1000.0 same(_)?".into())
+        );
+        // a rounded quotient would not give the result back
+        assert_eq!(
+            "\nEmpty Result\n\nEmpty Result\n\n(0.25, 1)\n",
+            engine.input("a(x) :- 1 = x * 3 a(_)? b(x) :- 1 / x = 3 b(_)? c(x, y) :- 1 = x * 4 && y = x * 4 c(_,_)?".into())
+        );
+    }
+
+    #[test]
+    fn records() {
+        let mut engine = Engine::new();
+        engine.input("doc({name: \"ana\", age: 30, address: {city: \"lima\"}}) doc({name: \"bob\", age: 4}) doc({})".into());
+        assert_eq!(
+            "\n(\"bob\", 4)\n\n(\"ana\", {address: {city: \"lima\"}, age: 30})\n(\"bob\", {age: 4}                          )\n\n(\"lima\")\n",
+            engine.input("exact(n, a) :- doc({name: n, age: a}) exact(_,_)? rest(n, r) :- doc({name: n, ...r}) rest(_,_)? city(c) :- doc({address: {city: c}, ...r}) city(_)?".into())
+        );
+        assert_eq!(
+            "\n({age: 31, name: \"ana\"})\n",
+            engine.input("older(x) :- rest(n, {age: a, ...r}) && a > 17 && x = {name: n, age: a + 1} older(_)?".into())
+        );
+    }
+
+    #[test]
+    fn string_functions() {
+        let mut engine = Engine::new();
+        assert_eq!(
+            "\n(\"Ana\")\n\n(\"Ana\", 3)\n(\"bob\", 3)\n\n(\"bob\")\n",
+            engine.input("name(\"Ana\") name(\"bob\") big(n) :- name(n) && upper(n) = \"ANA\" && s = upper(n) big(_)? size(n, l) :- name(n) && l = len(n) size(_,_)? b(n) :- name(n) && starts_with(n, \"b\") = true b(_)?".into())
+        );
+        assert_eq!(
+            "\n(\"hello\")\n\n(\"x-y\")\n\n(\"ell\", true)\n",
+            engine.input("first(a) :- concat(a, \" world\") = \"hello world\" first(_)? joined(s) :- split(s, \"-\") = [\"x\", \"y\"] joined(_)? sub(s, c) :- s = substr(\"hello\", 1, 3) && c = contains([1, 2], 2) sub(_,_)?".into())
+        );
+        // the ones giving a bool stand as statements on their own
+        assert_eq!(
+            "\n(\"bob\")\n\n(\"Ana\")\n",
+            engine.input("s(n) :- name(n) && starts_with(n, \"b\") s(_)? o(n) :- name(n) && !contains(n, \"o\") o(_)?".into())
+        );
+        assert!(engine
+            .input("l(n) :- name(n) && len(n)".into())
+            .contains("len(n) doesnt give a bool, so it has to be compared"));
+    }
+
+    #[test]
+    fn regex_matches() {
+        let mut engine = Engine::new();
+        engine.input(r#"log("port=8080") log("host=lima") log("oops")"#.into());
+        assert_eq!(
+            "\n(\"port\", \"8080\")\n\n(\"host=lima\")\n\n(\"host=lima\", null)\n(\"port=8080\", null)\n",
+            engine.input(r#"kv(k, v) :- log(l) && matches(l, "^(\\w+)=(\\d+)$", [k, v]) kv(_,_)? host(l) :- log(l) && matches(l, "^host") host(_)? opt(l, x) :- log(l) && matches(l, "^(o)?(\\w+)=", [x, _]) opt(_,_)?"#.into())
+        );
+        assert_eq!(
+            "\n(\"port=8080\")\n",
+            engine.input(
+                r#"pat("^p") starting(l) :- pat(p) && log(l) && matches(l, p) starting(_)?"#.into()
+            )
+        );
+    }
+
+    #[test]
+    fn modulo_power_and_negation() {
+        let mut engine = Engine::new();
+        engine.input("n(1) n(2) n(-7)".into());
+        assert_eq!(
+            "\n(2)\n\n(-7, 7 )\n(1 , -1)\n(2 , -2)\n\n(-3)\n\n(-7, -4, -1)\n(1 , 0 , -1)\n(2 , 1 , 0 )\n",
+            engine.input("even(x) :- n(x) && x % 2 = 0 even(_)? neg(x, m) :- n(x) && m = -x neg(_,_)? back(m) :- -m = 3 back(_)? r(x, a, b) :- n(x) && a = x // 2 && b = x % -2 r(_,_,_)?".into())
+        );
+        assert_eq!(
+            "\n(1024)\n\n(0.25)\n\n(7)\n\n(4)\n\n(-6)\n",
+            engine.input("p(x) :- x = 2 ^ 10 p(_)? q(x) :- x = 2 ** -2 q(_)? s(x) :- x ^ 2 = 49 s(_)? e(x) :- 3 ^ x = 81 e(_)? k(x) :- x = -(1 + 2) * 2 k(_)?".into())
+        );
+    }
+
+    #[test]
+    fn operator_precedence() {
+        let mut engine = Engine::new();
+        assert_eq!(
+            "\n(512, 64, 7, 9, 3, 18, 5)\n",
+            engine.input("p(a, b, c, d, e, f, g) :- a = 2 ^ 3 ^ 2 && b = (2 ^ 3) ^ 2 && c = 1 + 2 * 3 && d = (1 + 2) * 3 && e = 10 - 4 - 3 && f = 2 * 3 ^ 2 && g = 7 - 6 / 3 p(_,_,_,_,_,_,_)?".into())
+        );
+        let source =
+            "p(x) :- x = (1 + 2) * 3 + 2 ^ (3 ^ 2) + (2 ^ 3) ^ 2 - (1 - 2) + 6 / (3 * 2)\n";
+        assert_eq!(
+            "p(x) :- x = (1 + 2) * 3 + 2 ^ 3 ^ 2 + (2 ^ 3) ^ 2 - (1 - 2) + 6 / (3 * 2)\n",
+            format_source(source).unwrap()
+        );
+    }
+
+    #[test]
+    fn comparison_operators() {
+        let mut engine = Engine::new();
+        assert_eq!(
+            "\n(1)\n\n(1)\n(2)\n\n(3)\n\n(2)\n(3)\n\n(2)\n\n(1)\n(3)\n",
+            engine.input("v(1) v(2) v(3) lt(x) :- v(x) && x < 2 lt(_)? lte(x) :- v(x) && x <= 2 lte(_)? gt(x) :- v(x) && x > 2 gt(_)? gte(x) :- v(x) && x >= 2 gte(_)? eq(x) :- v(x) && x = 2 eq(_)? ne(x) :- v(x) && x != 2 ne(_)?".into())
+        );
+        engine.input("w(\"apple\") w(\"b\") w(\"Zed\") w([1, 2]) w([1]) w([1, \"a\"]) w(null) w(true) w(false) w(0.5)".into());
+        assert_eq!(
+            "\n(\"Zed\"  )\n(\"apple\")\n\n([1,2])\n\n(true)\n",
+            engine.input("s(x) :- w(x) && x < \"b\" s(_)? a(x) :- w(x) && x >= [1, 1] a(_)? b(x) :- w(x) && x > false b(_)?".into())
+        );
+    }
+
+    #[test]
+    fn syntax_errors() {
+        let mut engine = Engine::new();
+        let out = engine.input("a(1)\nb(2 3)\ne(x) :- a(x) && x = len(1, 2)\nf(x) : a(x)\ng(\"x\", [1, 2)\nh(1)\nq(x) :- a(x) &&".into());
+        assert_eq!(5, out.matches("error[").count());
+        assert!(out.contains("unexpected `3` on a list"));
+        assert!(out.contains("len cant take 2 arguments"));
+        assert!(out.contains("a line cant start with `:`"));
+        assert!(out.contains("hint: check that every bracket opened is closed"));
+        assert!(out.contains("the input ended in the middle of a statement"));
+        assert_eq!("\nEmpty Result\n", engine.input("a(_)?".into()));
+    }
+
+    #[test]
+    fn non_ascii_source() {
+        let mut engine = Engine::new();
+        assert_eq!("\nEmpty Result\n", engine.input("x(ü)?".into()));
+        assert_eq!("\n(\"ñandú\")\n", engine.input("n(\"ñandú\") n(_)?".into()));
+        let (_, diagnostics) = engine.run("café(1 2)\nñ(1 2)".into());
+        assert_eq!(
+            vec![
+                Some(Span { start: 8, end: 9 }),
+                Some(Span { start: 16, end: 17 })
+            ],
+            diagnostics.iter().map(|d| d.span).collect::<Vec<_>>()
+        );
+        assert!(engine
+            .input("café(1 2)".into())
+            .contains("unexpected `2` on a list"));
+    }
+
+    #[test]
+    fn json_diagnostics() {
+        let mut engine = Engine::new();
+        let input = "a(1)\nb(2 3)";
+        let (output, diagnostics) = engine.run(input.into());
+        assert_eq!("", output);
+        let json: serde_json::Value =
+            serde_json::from_str(&diagnostic::to_json(&diagnostics, input)).unwrap();
+        assert_eq!(1, json.as_array().unwrap().len());
+        let error = &json[0];
+        assert_eq!("error", error["severity"]);
+        assert_eq!("E0101", error["code"]);
+        assert_eq!("unexpected `3` on a list", error["message"]);
+        assert_eq!(
+            serde_json::json!({"byte": 9, "line": 2, "column": 5}),
+            error["span"]["start"]
+        );
+        assert_eq!(
+            serde_json::json!({"byte": 10, "line": 2, "column": 6}),
+            error["span"]["end"]
+        );
+        assert_eq!(serde_json::json!(["`,`", "`)`"]), error["expected"]);
+        assert!(error["hint"].is_null());
+        assert_eq!(serde_json::json!([]), error["notes"]);
+
+        let input = "p(1) p(x)?\nq(x) :- p(x) && !r(x)\nr(x) :- p(x) && !q(x)";
+        let (output, diagnostics) = engine.run(input.into());
+        assert_eq!("\n(1)\n", output);
+        let json: serde_json::Value =
+            serde_json::from_str(&diagnostic::to_json(&diagnostics, input)).unwrap();
+        let error = &json[0];
+        assert_eq!("E0201", error["code"]);
+        assert_eq!("q/1, r/1 depend on their own negation", error["message"]);
+        assert_eq!(33, error["span"]["start"]["byte"]);
+        assert_eq!(54, error["span"]["end"]["byte"]);
+        assert_eq!(serde_json::json!([]), error["expected"]);
+    }
+
+    #[test]
+    fn symbol_index() {
+        let source = "edge(1, 2)\npath(a, b) :- edge(a, b)\npath(a, b) :- path(a, c) && edge(c, b) && len(a) > 0\npath(x, 2)?\nb(2 3)";
+        let index = SymbolIndex::new(source);
+        let path = RelId {
+            identifier: "path".into(),
+            column_count: 2,
+        };
+        let query = source.find("path(x").unwrap();
+        assert_eq!(
+            Some(&path),
+            index.get_occurrence_at(query + 2).map(|o| &o.rel_id)
+        );
+        let definitions: Vec<usize> = index
+            .get_definitions(&path)
+            .iter()
+            .map(|o| o.span.start)
+            .collect();
+        assert_eq!(vec![11, 36], definitions);
+        assert_eq!(
+            "path(number, number)\n2 columns, 2 facts and rules",
+            index.describe(&path)
+        );
+        let names: Vec<String> = index
+            .get_rel_ids()
+            .into_iter()
+            .map(|rel_id| rel_id.identifier)
+            .collect();
+        assert_eq!(vec!["b", "edge", "path"], names);
+        assert_eq!(1, index.diagnostics.len());
+    }
+
+    #[test]
+    fn format_source_canonically() {
+        let source = "edge(1,2) person(\"ann\",[1,2.5])\nedge(2,3)\npath(a,b):-edge(a,b)\npath(a,b) :- path(a,mid)&&edge(mid,b)\npath(_,_)?\n# {edge(3,4)}=>path(1,x)?";
+        assert_eq!(
+            "edge(1, 2)\nedge(2, 3)\n\nperson(\"ann\", [1, 2.5])\n\npath(a, b) :- edge(a, b)\npath(a, b) :- path(a, mid) && edge(mid, b)\n\npath(_, _)?\n\n# {edge(3,4)}=>path(1,x)?\n",
+            format_source(source).unwrap()
+        );
+
+        let source = "long(aaaaaaaa,bbbbbbbbbb,c) :- first(aaaaaaaa, x) && second(x, y) || third(y,bbbbbbbbbb) && !(x=1 || y>2) && c = x+y*(2-1) && n = sum(z : second(z,_))";
+        let formatted = format_source(source).unwrap();
+        assert_eq!(
+            "long(aaaaaaaa, bbbbbbbbbb, c) :- first(aaaaaaaa, x)\n    && second(x, y)\n    || third(y, bbbbbbbbbb)\n    && !(x = 1 || y > 2)\n    && c = x + y * (2 - 1)\n    && n = sum(z : second(z, _))\n",
+            formatted
+        );
+        assert_eq!(formatted, format_source(&formatted).unwrap());
+        let lines = |source: &str| -> Vec<String> {
+            read_source(source)
+                .1
+                .iter()
+                .map(|source_line| source_line.line.to_string())
+                .collect()
+        };
+        assert_eq!(lines(source), lines(&formatted));
+        assert!(format_source("b(2 3)").is_err());
+    }
+
+    #[test]
+    fn comments_and_spans() {
+        let source =
+            "# numbers\nn(1) # the first\nn(2)\n\n# loose\n\nhalf(x, y) :- n(x)\n    && y = x / 2";
+        let (_, lines, diagnostics) = read_source(source);
+        assert!(diagnostics.is_empty());
+        let texts = |comments: &Vec<Comment>| -> Vec<String> {
+            comments.iter().map(|c| c.text.to_owned()).collect()
+        };
+        assert_eq!(vec!["numbers"], texts(&lines[0].leading_comments));
+        assert_eq!(vec!["the first"], texts(&lines[0].trailing_comments));
+        assert!(matches!(&lines[2].line, Line::Comment(comment) if comment.text == "loose"));
+        let rule = match &lines[3].line {
+            Line::Assumption(Assumption::Conditional(rule)) => rule,
+            line => panic!("{line} is not a rule"),
+        };
+        let text = |span: Option<Span>| span.map(|span| &source[span.start..span.end]);
+        assert_eq!(Some("half(x, y)"), text(rule.relation.span));
+        assert_eq!(
+            Some("n(x)\n    && y = x / 2"),
+            text(rule.conditional.get_span())
+        );
+        assert_eq!(Some(lines[3].span), rule.get_span());
+        assert_eq!(
+            "# numbers\nn(1) # the first\nn(2)\n\n# loose\n\nhalf(x, y) :- n(x) && y = x / 2\n",
+            format_source(source).unwrap()
+        );
+
+        // every node keeps where it was read from
+        let source = "schema p(n: number, m: number)\np(1, -2)\nq(x, t) :- p(x, y) && t = -(y + 1) * sum(n : p(n, _))";
+        let (_, lines, diagnostics) = read_source(source);
+        assert!(diagnostics.is_empty());
+        let text = |span: Option<Span>| span.map(|span| &source[span.start..span.end]);
+        match &lines[0].line {
+            Line::Assumption(Assumption::Schema(schema)) => {
+                assert_eq!(Some("schema p(n: number, m: number)"), text(schema.span))
+            }
+            line => panic!("{line} is not a schema"),
+        }
+        match &lines[1].line {
+            Line::Assumption(Assumption::RelationInmediate(fact)) => {
+                assert_eq!(Some("p(1, -2)"), text(fact.span));
+                let args: Vec<_> = fact
+                    .arg_spans
+                    .iter()
+                    .map(|span| text(Some(*span)))
+                    .collect();
+                assert_eq!(vec![Some("1"), Some("-2")], args);
+            }
+            line => panic!("{line} is not a fact"),
+        }
+        let rule = match &lines[2].line {
+            Line::Assumption(Assumption::Conditional(rule)) => rule,
+            line => panic!("{line} is not a rule"),
+        };
+        let (t, product) = match rule.conditional.get_semantics() {
+            StatementSemantics::And(_, comparison) => match comparison.get_semantics() {
+                StatementSemantics::ExpresionComparison(t, product, _) => (t, product),
+                _ => panic!("{comparison} is not a comparison"),
+            },
+            _ => panic!("{} is not a conjunction", rule.conditional),
+        };
+        assert_eq!(Some("t"), text(t.get_span()));
+        assert_eq!(
+            Some("-(y + 1) * sum(n : p(n, _))"),
+            text(product.get_span())
+        );
+        let (negation, aggregate) = match product.get_semantics() {
+            ExpresionSemantics::Arithmetic(negation, aggregate, _) => (negation, aggregate),
+            _ => panic!("{product} is not an operation"),
+        };
+        assert_eq!(Some("-(y + 1)"), text(negation.get_span()));
+        assert_eq!(Some("sum(n : p(n, _))"), text(aggregate.get_span()));
+        match negation.get_semantics() {
+            ExpresionSemantics::Negation(sum) => assert_eq!(Some("y + 1"), text(sum.get_span())),
+            _ => panic!("{negation} is not a negation"),
+        }
+        match aggregate.get_semantics() {
+            ExpresionSemantics::Aggregate(aggregate) => {
+                assert_eq!(Some("sum(n : p(n, _))"), text(aggregate.span));
+                assert_eq!(Some("n"), text(aggregate.target.get_span()));
+            }
+            _ => panic!("{aggregate} is not an aggregate"),
+        }
+    }
+
+    #[test]
+    fn format_keeps_comments_inside_rule_bodies() {
+        let source = "path(a, b) :- # straight away\n edge(a, b)\npath(a, b) :- edge(a, mid) # one step\n    # then the rest of the way\n    && path(mid, b) # trailing\n";
+        let formatted = format_source(source).unwrap();
+        assert_eq!(
+            "path(a, b) :-\n    # straight away\n    edge(a, b)\npath(a, b) :- edge(a, mid)\n    # one step\n    # then the rest of the way\n    && path(mid, b) # trailing\n",
+            formatted
+        );
+        assert_eq!(formatted, format_source(&formatted).unwrap());
+
+        let (_, lines, _) = read_source(&formatted);
+        let rule = match &lines[1].line {
+            Line::Assumption(Assumption::Conditional(rule)) => rule,
+            line => panic!("{line} is not a rule"),
+        };
+        match rule.conditional.get_semantics() {
+            StatementSemantics::And(edge, path) => {
+                assert!(edge.get_comments().is_empty());
+                let texts: Vec<_> = path
+                    .get_comments()
+                    .iter()
+                    .map(|c| c.text.as_str())
+                    .collect();
+                assert_eq!(vec!["one step", "then the rest of the way"], texts);
+            }
+            _ => panic!("{} is not a conjunction", rule.conditional),
+        }
+        assert_eq!(
+            vec!["trailing"],
+            lines[1]
+                .trailing_comments
+                .iter()
+                .map(|c| c.text.as_str())
+                .collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn runtime_errors_point_to_the_rule() {
+        let mut engine = Engine::new();
+        let source = "pat(\"(\")\nr(p) :- pat(p) && matches(\"a\", p)\nr(_)?";
+        let (_, diagnostics) = engine.run(source.into());
+        assert_eq!(1, diagnostics.len());
+        assert_eq!(Some(Span { start: 43, end: 48 }), diagnostics[0].span);
+        let note = &diagnostics[0].notes[0];
+        assert_eq!(Some(Span { start: 9, end: 42 }), note.span);
+        assert!(note
+            .message
+            .starts_with("the error came from the rule r(p)"));
+        let (_, diagnostics) = engine.run("r(1)?".into());
+        assert_eq!(None, diagnostics[0].notes[0].span);
+        assert!(diagnostics[0].notes[0]
+            .message
+            .ends_with("given on an earlier input"));
+
+        // the innermost rule is blamed, in every evaluation mode
+        engine.input("s(p) :- r(p)".into());
+        for mode in [
+            EvaluationMode::TopDown,
+            EvaluationMode::SemiNaive,
+            EvaluationMode::Tabled,
+        ] {
+            engine.set_evaluation_mode(mode);
+            let (_, diagnostics) = engine.run("s(_)?".into());
+            assert!(diagnostics[0].notes[0]
+                .message
+                .starts_with("the error came from the rule r(p)"));
+        }
+    }
 }
@@ -6,10 +6,13 @@ pub fn print_hilighted(
 ) -> String {
     let context_margin = 30;
 
-    let precontext =
-        &base_string[(start as isize - context_margin as isize).max(0) as usize..start];
+    // the margins are in bytes, so they are moved to the nearest char boundary
+    let context_start = base_string.floor_char_boundary(start.saturating_sub(context_margin));
+    let context_end = base_string.ceil_char_boundary(end + context_margin);
+
+    let precontext = &base_string[context_start..start];
     let error = &base_string[start..end];
-    let postcontext = &base_string[end..(end + context_margin).min(base_string.len())];
+    let postcontext = &base_string[end..context_end];
 
     let lined_precontext = &precontext[precontext.find("\n").unwrap_or(0)..precontext.len()];
     let lined_postcontext = &postcontext[0..postcontext.rfind("\n").unwrap_or(postcontext.len())];
@@ -18,7 +21,7 @@ pub fn print_hilighted(
     // let padded_error = error.replace("\n", &format!("\n{indentation} 00 : "));
     // let padded_postcontext = lined_postcontext.replace("\n", &format!("\n{indentation} 00 : "));
 
-    let preprecontext = &base_string[0..(start as isize - context_margin as isize).max(0) as usize];
+    let preprecontext = &base_string[0..context_start];
 
     let mut running_line_numer: usize = preprecontext.chars().filter(|e| e == &'\n').count();
 